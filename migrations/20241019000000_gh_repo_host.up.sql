@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE gh_repo ADD COLUMN host VARCHAR(255) NOT NULL DEFAULT 'github.com';
@@ -0,0 +1,10 @@
+-- Add up migration script here
+CREATE TABLE gh_http_cache (
+    url_hash CHAR(64) PRIMARY KEY,
+    url TEXT NOT NULL,
+    etag VARCHAR(255),
+    last_modified VARCHAR(64),
+    status SMALLINT UNSIGNED NOT NULL,
+    body MEDIUMTEXT NOT NULL,
+    updated_at DATETIME(3) NOT NULL
+);
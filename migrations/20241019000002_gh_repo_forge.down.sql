@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE gh_repo DROP INDEX idx_forge_stars;
+ALTER TABLE gh_repo DROP COLUMN forge;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE gh_repo ADD COLUMN forge VARCHAR(32) NOT NULL DEFAULT 'github';
+ALTER TABLE gh_repo ADD INDEX idx_forge_stars(forge, stars);
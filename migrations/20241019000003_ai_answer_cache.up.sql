@@ -0,0 +1,13 @@
+-- Add up migration script here
+CREATE TABLE ai_answer_cache (
+    cache_key CHAR(64) PRIMARY KEY,
+    owner_name VARCHAR(255) NOT NULL,
+    repo_name VARCHAR(255) NOT NULL,
+    template_name VARCHAR(255) NOT NULL,
+    template_version INTEGER UNSIGNED NOT NULL,
+    model VARCHAR(255) NOT NULL,
+    content_hash CHAR(64) NOT NULL,
+    answer MEDIUMTEXT NOT NULL,
+    created_at DATETIME(3) NOT NULL,
+    INDEX idx_repo(owner_name, repo_name)
+);
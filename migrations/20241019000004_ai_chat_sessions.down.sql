@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE ai_chat_messages;
+DROP TABLE ai_chat_sessions;
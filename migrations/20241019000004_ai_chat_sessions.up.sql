@@ -0,0 +1,22 @@
+-- Add up migration script here
+CREATE TABLE ai_chat_sessions (
+    id CHAR(32) PRIMARY KEY,
+    owner_name VARCHAR(255) NOT NULL,
+    repo_name VARCHAR(255) NOT NULL,
+    provider VARCHAR(32),
+    model VARCHAR(255) NOT NULL,
+    system_prompt MEDIUMTEXT NOT NULL,
+    created_at DATETIME(3) NOT NULL,
+    updated_at DATETIME(3) NOT NULL,
+    INDEX idx_updated_at(updated_at)
+);
+
+CREATE TABLE ai_chat_messages (
+    id BIGINT UNSIGNED AUTO_INCREMENT PRIMARY KEY,
+    session_id CHAR(32) NOT NULL,
+    role VARCHAR(16) NOT NULL,
+    content MEDIUMTEXT NOT NULL,
+    created_at DATETIME(3) NOT NULL,
+    INDEX idx_session(session_id, id),
+    FOREIGN KEY (session_id) REFERENCES ai_chat_sessions(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE ai_answer_cache DROP COLUMN host;
@@ -0,0 +1,35 @@
+#[allow(dead_code)]
+#[path = "../pkg/github/stub/stub_server.rs"]
+mod stub_server;
+
+use std::error::Error;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use clap::Parser;
+use crate::stub_server::{start_stub_server, Scenario};
+
+#[derive(Parser)]
+#[command(name = "github-stub")]
+struct Cli {
+    /// Directory holding the fixture JSON files
+    #[arg(long, default_value = "fixtures/github")]
+    fixtures: PathBuf,
+    #[arg(long, default_value = "127.0.0.1:8081")]
+    bind: String,
+    /// normal, not-found, rate-limited, malformed, slow or slow:{millis}
+    #[arg(long, default_value = "normal")]
+    scenario: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let scenario: Scenario = cli.scenario.parse()?;
+
+    let listener = TcpListener::bind(&cli.bind)?;
+    let (server, handle) = start_stub_server(cli.fixtures, scenario, listener)?;
+    println!("GitHub stub listening on {}", handle.base_url);
+    server.await?;
+
+    Ok(())
+}
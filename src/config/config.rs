@@ -0,0 +1,790 @@
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use clap::Parser;
+use serde::Deserialize;
+use crate::pkg::ai::model::model::AIProvider;
+use crate::pkg::utils::http_fixture::http_fixture::FixtureMode;
+use crate::pkg::utils::validation::validation::ValidationErrors;
+
+const DEFAULT_CONFIG_PATH: &str = "fynoss.toml";
+
+/// Command line flags. They are the last layer and override the file and the environment.
+#[derive(Parser, Debug, Default)]
+#[command(name = "fynoss")]
+pub struct Args {
+    /// Path to the TOML configuration file (defaults to ./fynoss.toml when present)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    #[arg(long)]
+    pub bind: Option<String>,
+    #[arg(long)]
+    pub workers: Option<usize>,
+    #[arg(long)]
+    pub log_level: Option<String>,
+    #[arg(long)]
+    pub database_url: Option<String>,
+    #[arg(long)]
+    pub github_base_url: Option<String>,
+    #[arg(long)]
+    pub ai_provider: Option<String>,
+    #[arg(long)]
+    pub ai_model: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub log: LogConfig,
+    pub database: DatabaseConfig,
+    pub github: GithubConfig,
+    /// GitLab instances searched alongside GitHub; none by default
+    pub gitlab: Vec<GitlabHostConfig>,
+    /// Gitea, Forgejo or Codeberg instances searched alongside GitHub; none by default
+    pub gitea: Vec<GiteaHostConfig>,
+    pub ai: AIConfig,
+    pub cache: CacheConfig,
+    pub chat: ChatConfig,
+    pub admin: AdminConfig,
+    pub fixtures: FixturesConfig,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind: String,
+    pub workers: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:8080".to_string(),
+            workers: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Filter in `env_logger` syntax, e.g. `info` or `fynoss=debug,actix_web=info`
+    pub level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "".to_string(),
+            max_connections: 10,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct GithubConfig {
+    pub base_url: String,
+    pub tokens: Vec<String>,
+    /// Authenticate as a GitHub App installation instead of with `tokens`
+    pub app: Option<GithubAppConfig>,
+    /// Additional hosts (GitHub Enterprise Server, stubs) searched alongside `base_url`
+    pub hosts: Vec<GithubHostConfig>,
+    /// Upstream calls a batch README or issues request runs at once
+    pub batch_concurrency: usize,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.github.com".to_string(),
+            tokens: vec![],
+            app: None,
+            hosts: vec![],
+            batch_concurrency: 8,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct GithubHostConfig {
+    pub base_url: String,
+    pub tokens: Vec<String>,
+    pub app: Option<GithubAppConfig>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct GithubAppConfig {
+    pub app_id: String,
+    pub installation_id: u64,
+    /// PEM file holding the app's RSA private key
+    pub private_key_path: PathBuf,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct GitlabHostConfig {
+    pub base_url: String,
+    /// Only needed for private projects and higher rate limits
+    pub token: Option<String>,
+    /// Labels that count towards `good_first_issues_count`
+    pub good_first_issue_labels: Vec<String>,
+    /// Labels that count towards `help_wanted_count`
+    pub help_wanted_labels: Vec<String>,
+}
+
+impl Default for GitlabHostConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://gitlab.com/api/v4".to_string(),
+            token: None,
+            good_first_issue_labels: vec!["good first issue".to_string(), "quick win".to_string()],
+            help_wanted_labels: vec!["help wanted".to_string()],
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct GiteaHostConfig {
+    /// API root of the instance, ending in `/api/v1`
+    pub base_url: String,
+    /// Only needed for private repositories
+    pub token: Option<String>,
+    /// Labels that count towards `good_first_issues_count`
+    pub good_first_issue_labels: Vec<String>,
+    /// Labels that count towards `help_wanted_count`
+    pub help_wanted_labels: Vec<String>,
+}
+
+impl Default for GiteaHostConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://codeberg.org/api/v1".to_string(),
+            token: None,
+            good_first_issue_labels: vec!["good first issue".to_string()],
+            help_wanted_labels: vec!["help wanted".to_string()],
+        }
+    }
+}
+
+impl GithubAppConfig {
+    fn validate(&self, field: &str, errors: &mut ValidationErrors) {
+        if self.app_id.is_empty() {
+            errors.add(&format!("{}.app_id", field), "is required");
+        }
+        if self.installation_id == 0 {
+            errors.add(&format!("{}.installation_id", field), "is required");
+        }
+        if self.private_key_path.as_os_str().is_empty() {
+            errors.add(&format!("{}.private_key_path", field), "is required");
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct AIConfig {
+    /// Provider asked when a request names none
+    pub provider: AIProvider,
+    /// API root; empty for the provider's public endpoint
+    pub base_url: String,
+    pub api_key: String,
+    pub model: String,
+    pub max_tokens: Option<u32>,
+    /// Token budget for prompts; the provider's default when unset
+    pub max_prompt_tokens: Option<u32>,
+    pub timeout_secs: u64,
+    /// Further providers that requests can pick with `?provider=`
+    pub providers: Vec<AIProviderConfig>,
+    /// Directory of `.toml` prompt templates added to the built-in ones
+    pub prompts_dir: Option<PathBuf>,
+}
+
+impl Default for AIConfig {
+    fn default() -> Self {
+        Self {
+            provider: AIProvider::OpenAI,
+            base_url: "".to_string(),
+            api_key: "".to_string(),
+            model: "gpt-4o-mini-2024-07-18".to_string(),
+            max_tokens: None,
+            max_prompt_tokens: None,
+            timeout_secs: 120,
+            providers: vec![],
+            prompts_dir: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AIProviderConfig {
+    pub provider: AIProvider,
+    /// API root; empty for the provider's public endpoint
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    pub model: String,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub max_prompt_tokens: Option<u32>,
+}
+
+impl AIProviderConfig {
+    /// `base_url`, or the provider's public endpoint when it is empty.
+    pub fn resolved_base_url(&self) -> String {
+        match self.base_url.is_empty() {
+            true => self.provider.default_base_url().unwrap_or_default().to_string(),
+            false => self.base_url.clone(),
+        }
+    }
+
+    /// `max_prompt_tokens`, or the provider's default when it is unset.
+    pub fn resolved_max_prompt_tokens(&self) -> u32 {
+        self.max_prompt_tokens.unwrap_or_else(|| self.provider.default_max_prompt_tokens())
+    }
+
+    fn validate(&self, field: &str, offline: bool, errors: &mut ValidationErrors) {
+        if self.base_url.is_empty() && self.provider.default_base_url().is_none() {
+            errors.add(&format!("{}.base_url", field), format!("is required for {}", self.provider));
+        }
+        if !offline && self.provider.requires_api_key() && self.api_key.is_empty() {
+            errors.add(&format!("{}.api_key", field), format!("is required for {}", self.provider));
+        }
+        if self.model.is_empty() {
+            errors.add(&format!("{}.model", field), "is required");
+        }
+        if self.max_prompt_tokens == Some(0) {
+            errors.add(&format!("{}.max_prompt_tokens", field), "must be greater than 0");
+        }
+    }
+}
+
+impl AIConfig {
+    /// Every configured provider, the default one first.
+    pub fn all_providers(&self) -> Vec<AIProviderConfig> {
+        let default = AIProviderConfig {
+            provider: self.provider,
+            base_url: self.base_url.clone(),
+            api_key: self.api_key.clone(),
+            model: self.model.clone(),
+            max_tokens: self.max_tokens,
+            max_prompt_tokens: self.max_prompt_tokens,
+        };
+        [default].into_iter().chain(self.providers.iter().cloned()).collect()
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GithubCacheTier {
+    None,
+    Disk,
+    Mysql,
+}
+
+impl FromStr for GithubCacheTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(GithubCacheTier::None),
+            "disk" => Ok(GithubCacheTier::Disk),
+            "mysql" => Ok(GithubCacheTier::Mysql),
+            _ => Err(format!("unknown cache tier {:?}", s)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AIAnswerCacheTier {
+    None,
+    /// Per process, lost on restart
+    Memory,
+    Mysql,
+}
+
+impl FromStr for AIAnswerCacheTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(AIAnswerCacheTier::None),
+            "memory" => Ok(AIAnswerCacheTier::Memory),
+            "mysql" => Ok(AIAnswerCacheTier::Mysql),
+            _ => Err(format!("unknown cache tier {:?}", s)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// How long a cached GitHub README is served before asking GitHub whether it changed
+    pub readme_ttl_secs: u64,
+    /// The same for issue searches
+    pub issues_ttl_secs: u64,
+    pub ai_answer_ttl_secs: u64,
+    /// Where AI answers are stored for reuse
+    pub ai_answer_tier: AIAnswerCacheTier,
+    /// Capacity of the in-memory LRU of conditional GitHub responses
+    pub github_memory_entries: usize,
+    /// Persistent tier behind the LRU
+    pub github_tier: GithubCacheTier,
+    pub github_disk_dir: PathBuf,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            readme_ttl_secs: 60 * 60,
+            issues_ttl_secs: 10 * 60,
+            ai_answer_ttl_secs: 7 * 24 * 60 * 60,
+            ai_answer_tier: AIAnswerCacheTier::Mysql,
+            github_memory_entries: 1000,
+            github_tier: GithubCacheTier::None,
+            github_disk_dir: PathBuf::from("cache/github"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatStoreTier {
+    /// Per process, lost on restart
+    Memory,
+    Mysql,
+}
+
+impl FromStr for ChatStoreTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "memory" => Ok(ChatStoreTier::Memory),
+            "mysql" => Ok(ChatStoreTier::Mysql),
+            _ => Err(format!("unknown chat store {:?}", s)),
+        }
+    }
+}
+
+/// Contribution chat sessions
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ChatConfig {
+    pub store: ChatStoreTier,
+    /// Time without a question after which a session expires
+    pub session_ttl_secs: u64,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            store: ChatStoreTier::Mysql,
+            session_ttl_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+/// Record/replay of GitHub and AI provider HTTP exchanges, for tests and offline development
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Bearer token the `/admin` endpoints require; they refuse every request while it is unset
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct FixturesConfig {
+    pub mode: FixtureMode,
+    pub dir: PathBuf,
+}
+
+impl Default for FixturesConfig {
+    fn default() -> Self {
+        Self {
+            mode: FixtureMode::Off,
+            dir: PathBuf::from("fixtures/http"),
+        }
+    }
+}
+
+impl Config {
+    /// Loads defaults, then the TOML file, then the process environment, then `args`,
+    /// and validates the result.
+    pub fn load(args: Args) -> Result<Self, ValidationErrors> {
+        let (path, required) = match &args.config {
+            Some(path) => (path.clone(), true),
+            None => match env::var("FYNOSS_CONFIG") {
+                Ok(path) => (PathBuf::from(path), true),
+                Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+            },
+        };
+
+        let file = match fs::read_to_string(&path) {
+            Ok(contents) => Some(contents),
+            Err(_) if !required => None,
+            Err(e) => {
+                let mut errors = ValidationErrors::new();
+                errors.add("config", format!("cannot read {}: {}", path.display(), e));
+                return Err(errors);
+            }
+        };
+
+        Self::from_layers(file.as_deref(), |key| env::var(key).ok(), args)
+    }
+
+    pub fn from_layers(file: Option<&str>, env: impl Fn(&str) -> Option<String>, args: Args) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let mut config = match file.map(toml::from_str::<Config>) {
+            None => Config::default(),
+            Some(Ok(config)) => config,
+            Some(Err(e)) => {
+                errors.add("config", e.message().to_string());
+                return Err(errors);
+            }
+        };
+
+        config.apply_env(&env, &mut errors);
+        config.apply_args(args, &mut errors);
+        config.validate(&mut errors);
+
+        errors.into_result(config)
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>, errors: &mut ValidationErrors) {
+        let string = |key: &str, target: &mut String| {
+            if let Some(value) = env(key) {
+                *target = value;
+            }
+        };
+        string("FYNOSS_SERVER_BIND", &mut self.server.bind);
+        string("FYNOSS_LOG_LEVEL", &mut self.log.level);
+        string("DATABASE_URL", &mut self.database.url);
+        string("FYNOSS_GITHUB_BASE_URL", &mut self.github.base_url);
+        string("FYNOSS_AI_BASE_URL", &mut self.ai.base_url);
+        string("OPENAI_KEY", &mut self.ai.api_key);
+        string("FYNOSS_AI_API_KEY", &mut self.ai.api_key);
+        string("FYNOSS_AI_MODEL", &mut self.ai.model);
+        if let Some(dir) = env("FYNOSS_CACHE_GITHUB_DISK_DIR") {
+            self.cache.github_disk_dir = PathBuf::from(dir);
+        }
+        if let Some(dir) = env("FYNOSS_AI_PROMPTS_DIR") {
+            self.ai.prompts_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(token) = env("FYNOSS_ADMIN_TOKEN") {
+            self.admin.token = Some(token);
+        }
+        if let Some(dir) = env("FYNOSS_FIXTURES_DIR") {
+            self.fixtures.dir = PathBuf::from(dir);
+        }
+
+        if let Some(tokens) = env("GITHUB_TOKENS") {
+            self.github.tokens = split_list(&tokens);
+        } else if let Some(token) = env("GITHUB_TOKEN") {
+            self.github.tokens = vec![token];
+        }
+        if let Some(app_id) = env("FYNOSS_GITHUB_APP_ID") {
+            self.github.app.get_or_insert_with(Default::default).app_id = app_id;
+        }
+        if let Some(path) = env("FYNOSS_GITHUB_APP_PRIVATE_KEY_PATH") {
+            self.github.app.get_or_insert_with(Default::default).private_key_path = PathBuf::from(path);
+        }
+        // The environment can describe one GitLab instance; more need the config file.
+        if let Some(url) = env("FYNOSS_GITLAB_BASE_URL") {
+            self.gitlab_host().base_url = url;
+        }
+        if let Some(token) = env("GITLAB_TOKEN") {
+            self.gitlab_host().token = Some(token);
+        }
+        if let Some(url) = env("FYNOSS_GITEA_BASE_URL") {
+            self.gitea_host().base_url = url;
+        }
+        if let Some(token) = env("GITEA_TOKEN") {
+            self.gitea_host().token = Some(token);
+        }
+        parse_env(env, errors, "FYNOSS_GITHUB_APP_INSTALLATION_ID", "github.app.installation_id", |v| {
+            self.github.app.get_or_insert_with(Default::default).installation_id = v
+        });
+
+        parse_env(env, errors, "FYNOSS_GITHUB_BATCH_CONCURRENCY", "github.batch_concurrency", |v| self.github.batch_concurrency = v);
+        parse_env(env, errors, "FYNOSS_SERVER_WORKERS", "server.workers", |v| self.server.workers = Some(v));
+        parse_env(env, errors, "FYNOSS_DATABASE_MAX_CONNECTIONS", "database.max_connections", |v| self.database.max_connections = v);
+        parse_env(env, errors, "FYNOSS_DATABASE_ACQUIRE_TIMEOUT_SECS", "database.acquire_timeout_secs", |v| self.database.acquire_timeout_secs = v);
+        parse_env(env, errors, "FYNOSS_DATABASE_IDLE_TIMEOUT_SECS", "database.idle_timeout_secs", |v| self.database.idle_timeout_secs = v);
+        parse_env(env, errors, "FYNOSS_AI_PROVIDER", "ai.provider", |v| self.ai.provider = v);
+        parse_env(env, errors, "FYNOSS_AI_MAX_TOKENS", "ai.max_tokens", |v| self.ai.max_tokens = Some(v));
+        parse_env(env, errors, "FYNOSS_AI_MAX_PROMPT_TOKENS", "ai.max_prompt_tokens", |v| self.ai.max_prompt_tokens = Some(v));
+        parse_env(env, errors, "FYNOSS_AI_TIMEOUT_SECS", "ai.timeout_secs", |v| self.ai.timeout_secs = v);
+        parse_env(env, errors, "FYNOSS_CACHE_README_TTL_SECS", "cache.readme_ttl_secs", |v| self.cache.readme_ttl_secs = v);
+        parse_env(env, errors, "FYNOSS_CACHE_ISSUES_TTL_SECS", "cache.issues_ttl_secs", |v| self.cache.issues_ttl_secs = v);
+        parse_env(env, errors, "FYNOSS_CACHE_AI_ANSWER_TTL_SECS", "cache.ai_answer_ttl_secs", |v| self.cache.ai_answer_ttl_secs = v);
+        parse_env(env, errors, "FYNOSS_CACHE_GITHUB_MEMORY_ENTRIES", "cache.github_memory_entries", |v| self.cache.github_memory_entries = v);
+        parse_env(env, errors, "FYNOSS_CACHE_GITHUB_TIER", "cache.github_tier", |v| self.cache.github_tier = v);
+        parse_env(env, errors, "FYNOSS_CACHE_AI_ANSWER_TIER", "cache.ai_answer_tier", |v| self.cache.ai_answer_tier = v);
+        parse_env(env, errors, "FYNOSS_CHAT_STORE", "chat.store", |v| self.chat.store = v);
+        parse_env(env, errors, "FYNOSS_CHAT_SESSION_TTL_SECS", "chat.session_ttl_secs", |v| self.chat.session_ttl_secs = v);
+        parse_env(env, errors, "FYNOSS_FIXTURES_MODE", "fixtures.mode", |v| self.fixtures.mode = v);
+    }
+
+    fn gitlab_host(&mut self) -> &mut GitlabHostConfig {
+        if self.gitlab.is_empty() {
+            self.gitlab.push(GitlabHostConfig::default());
+        }
+        &mut self.gitlab[0]
+    }
+
+    fn gitea_host(&mut self) -> &mut GiteaHostConfig {
+        if self.gitea.is_empty() {
+            self.gitea.push(GiteaHostConfig::default());
+        }
+        &mut self.gitea[0]
+    }
+
+    fn apply_args(&mut self, args: Args, errors: &mut ValidationErrors) {
+        if let Some(bind) = args.bind {
+            self.server.bind = bind;
+        }
+        if let Some(workers) = args.workers {
+            self.server.workers = Some(workers);
+        }
+        if let Some(level) = args.log_level {
+            self.log.level = level;
+        }
+        if let Some(url) = args.database_url {
+            self.database.url = url;
+        }
+        if let Some(url) = args.github_base_url {
+            self.github.base_url = url;
+        }
+        if let Some(provider) = args.ai_provider {
+            match provider.parse() {
+                Ok(provider) => self.ai.provider = provider,
+                Err(e) => errors.add("ai.provider", e),
+            }
+        }
+        if let Some(model) = args.ai_model {
+            self.ai.model = model;
+        }
+    }
+
+    fn validate(&self, errors: &mut ValidationErrors) {
+        if self.server.bind.parse::<SocketAddr>().is_err() {
+            errors.add("server.bind", format!("must be an address like 0.0.0.0:8080, got {:?}", self.server.bind));
+        }
+        if self.server.workers == Some(0) {
+            errors.add("server.workers", "must be greater than 0");
+        }
+        if self.database.url.is_empty() {
+            errors.add("database.url", "is required (set DATABASE_URL)");
+        }
+        if self.database.max_connections == 0 {
+            errors.add("database.max_connections", "must be greater than 0");
+        }
+        if self.cache.github_memory_entries == 0 {
+            errors.add("cache.github_memory_entries", "must be greater than 0");
+        }
+        if self.github.base_url.is_empty() {
+            errors.add("github.base_url", "is required");
+        }
+        // Replaying fixtures never reaches GitHub or the AI provider, so no credentials are needed.
+        let offline = self.fixtures.mode == FixtureMode::Replay;
+        if !offline && self.github.app.is_none() && self.github.tokens.iter().all(|t| t.trim().is_empty()) {
+            errors.add("github.tokens", "at least one token is required (set GITHUB_TOKEN)");
+        }
+        if let Some(app) = &self.github.app {
+            app.validate("github.app", errors);
+        }
+        for (i, host) in self.github.hosts.iter().enumerate() {
+            if host.base_url.is_empty() {
+                errors.add(&format!("github.hosts[{}].base_url", i), "is required");
+            }
+            if !offline && host.app.is_none() && host.tokens.iter().all(|t| t.trim().is_empty()) {
+                errors.add(&format!("github.hosts[{}].tokens", i), "at least one token is required");
+            }
+            if let Some(app) = &host.app {
+                app.validate(&format!("github.hosts[{}].app", i), errors);
+            }
+        }
+        if self.github.batch_concurrency == 0 {
+            errors.add("github.batch_concurrency", "must be greater than 0");
+        }
+        for (i, host) in self.gitlab.iter().enumerate() {
+            if host.base_url.is_empty() {
+                errors.add(&format!("gitlab[{}].base_url", i), "is required");
+            }
+        }
+        for (i, host) in self.gitea.iter().enumerate() {
+            if host.base_url.is_empty() {
+                errors.add(&format!("gitea[{}].base_url", i), "is required");
+            }
+        }
+        if self.ai.base_url.is_empty() && self.ai.provider.default_base_url().is_none() {
+            errors.add("ai.base_url", format!("is required for {} (set FYNOSS_AI_BASE_URL)", self.ai.provider));
+        }
+        if !offline && self.ai.provider.requires_api_key() && self.ai.api_key.is_empty() {
+            errors.add("ai.api_key", "is required (set FYNOSS_AI_API_KEY or OPENAI_KEY)");
+        }
+        if self.ai.model.is_empty() {
+            errors.add("ai.model", "is required");
+        }
+        if self.ai.max_prompt_tokens == Some(0) {
+            errors.add("ai.max_prompt_tokens", "must be greater than 0");
+        }
+        for (i, provider) in self.ai.providers.iter().enumerate() {
+            provider.validate(&format!("ai.providers[{}]", i), offline, errors);
+        }
+        if self.ai.timeout_secs == 0 {
+            errors.add("ai.timeout_secs", "must be greater than 0");
+        }
+        if self.chat.session_ttl_secs == 0 {
+            errors.add("chat.session_ttl_secs", "must be greater than 0");
+        }
+        if self.admin.token.as_ref().is_some_and(|t| t.trim().is_empty()) {
+            errors.add("admin.token", "must not be empty; leave it unset to disable the admin API");
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(
+    env: &impl Fn(&str) -> Option<String>,
+    errors: &mut ValidationErrors,
+    key: &str,
+    field: &str,
+    mut set: impl FnMut(T),
+) {
+    if let Some(value) = env(key) {
+        match value.trim().parse() {
+            Ok(v) => set(v),
+            Err(_) => errors.add(field, format!("cannot parse {}={:?}", key, value)),
+        }
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::config::config::{Args, Config};
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let file = r#"
+            [server]
+            bind = "127.0.0.1:9000"
+            workers = 2
+
+            [database]
+            url = "mysql://file"
+            max_connections = 5
+
+            [ai]
+            model = "from-file"
+        "#;
+        let args = Args {
+            workers: Some(8),
+            ..Args::default()
+        };
+
+        let config = Config::from_layers(
+            Some(file),
+            env(&[("DATABASE_URL", "mysql://env"), ("GITHUB_TOKENS", "t1, t2"), ("OPENAI_KEY", "key")]),
+            args,
+        ).unwrap();
+
+        assert_eq!(config.server.bind, "127.0.0.1:9000");
+        assert_eq!(config.server.workers, Some(8));
+        assert_eq!(config.database.url, "mysql://env");
+        assert_eq!(config.database.max_connections, 5);
+        assert_eq!(config.database.acquire_timeout_secs, 30);
+        assert_eq!(config.github.tokens, vec!["t1", "t2"]);
+        assert_eq!(config.ai.model, "from-file");
+        assert_eq!(config.log.level, "info");
+    }
+
+    #[test]
+    fn test_reports_every_missing_setting() {
+        let errors = Config::from_layers(None, env(&[("FYNOSS_SERVER_WORKERS", "many")]), Args::default()).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["server.workers", "database.url", "github.tokens", "ai.api_key"]);
+    }
+
+    #[test]
+    fn test_ai_providers() {
+        let file = r#"
+            [ai]
+            provider = "ollama"
+            model = "llama3.1"
+
+            [[ai.providers]]
+            provider = "openai_compatible"
+            model = "qwen2.5-coder"
+
+            [[ai.providers]]
+            provider = "anthropic"
+            model = "claude-3-5-haiku-latest"
+        "#;
+        // Ollama needs neither a key nor a base URL, unlike the other two.
+        let errors = Config::from_layers(Some(file), env(&[("DATABASE_URL", "mysql://env"), ("GITHUB_TOKEN", "t1")]), Args::default()).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["ai.providers[0].base_url", "ai.providers[1].api_key"]);
+    }
+
+    #[test]
+    fn test_ai_provider_defaults() {
+        let file = r#"
+            [ai]
+            provider = "ollama"
+            model = "llama3.1"
+
+            [[ai.providers]]
+            provider = "openai"
+            api_key = "sk-test"
+            model = "gpt-4o-mini"
+        "#;
+        let config = Config::from_layers(Some(file), env(&[("DATABASE_URL", "mysql://env"), ("GITHUB_TOKEN", "t1")]), Args::default()).unwrap();
+
+        let urls: Vec<String> = config.ai.all_providers().iter().map(|p| p.resolved_base_url()).collect();
+        assert_eq!(urls, vec!["http://localhost:11434", "https://api.openai.com/v1"]);
+        let budgets: Vec<u32> = config.ai.all_providers().iter().map(|p| p.resolved_max_prompt_tokens()).collect();
+        assert_eq!(budgets, vec![3_000, 16_000]);
+    }
+}
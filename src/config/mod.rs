@@ -0,0 +1 @@
+pub mod config;
\ No newline at end of file
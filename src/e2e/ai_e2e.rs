@@ -0,0 +1,547 @@
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::Duration;
+use serde_json::{json, Value};
+use sqlx::mysql::MySqlPoolOptions;
+use crate::config::config::{Args, Config};
+use crate::pkg::ai::stub::stub_server::{start_ai_stub_server, AIStubHandle, DEFAULT_ANSWER};
+use crate::pkg::github::stub::stub_server::{start_stub_server, Scenario, StubHandle};
+use crate::server::server::build_server;
+
+const ADMIN_TOKEN: &str = "admin-token";
+
+struct TestServer {
+    base_url: String,
+    ai: AIStubHandle,
+    github: StubHandle,
+    http: reqwest::Client,
+}
+
+impl TestServer {
+    async fn ask(&self, query: &str) -> reqwest::Response {
+        let url = format!("{}/ai/inquiry/how-to-contribute/octo-org/octo-repo{}", self.base_url, query);
+        self.http.get(url).send().await.unwrap()
+    }
+
+    async fn ask_stream(&self, query: &str) -> reqwest::Response {
+        let url = format!("{}/ai/inquiry/how-to-contribute/octo-org/octo-repo/stream{}", self.base_url, query);
+        self.http.get(url).send().await.unwrap()
+    }
+
+    /// The streamed events, as names and parsed data, once the stream ended.
+    async fn events(&self, query: &str) -> Vec<(String, Value)> {
+        let text = self.ask_stream(query).await.text().await.unwrap();
+        text.split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let (name, data) = event.split_once('\n').unwrap();
+                (name.strip_prefix("event: ").unwrap().to_string(), serde_json::from_str(data.strip_prefix("data: ").unwrap()).unwrap())
+            })
+            .collect()
+    }
+
+    async fn chat(&self, path: &str, body: Option<Value>) -> reqwest::Response {
+        let url = format!("{}/ai/chat/sessions{}", self.base_url, path);
+        match body {
+            Some(body) => self.http.post(url).json(&body).send().await.unwrap(),
+            None => self.http.get(url).send().await.unwrap(),
+        }
+    }
+
+    async fn prompts(&self, path: &str) -> reqwest::Response {
+        self.http.get(format!("{}/admin/ai/prompts{}", self.base_url, path)).bearer_auth(ADMIN_TOKEN).send().await.unwrap()
+    }
+}
+
+async fn start() -> TestServer {
+    start_with("").await
+}
+
+/// Starts the servers with `ai_settings` in the `[ai]` table and without storing answers.
+async fn start_with(ai_settings: &str) -> TestServer {
+    launch(ai_settings, "none").await
+}
+
+/// Starts the GitHub and AI stubs and the real server, with OpenAI as the default provider and
+/// Anthropic and Ollama selectable per request, all served by the AI stub.
+async fn launch(ai_settings: &str, answer_tier: &str) -> TestServer {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/github");
+    let (github_server, github) = start_stub_server(fixtures, Scenario::Normal, TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(github_server);
+    let (ai_server, ai) = start_ai_stub_server(TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(ai_server);
+
+    let file = format!(r#"
+        [ai]
+        provider = "openai"
+        base_url = "{root}/v1"
+        model = "gpt-4o-mini"
+        {ai_settings}
+
+        [[ai.providers]]
+        provider = "anthropic"
+        base_url = "{root}/v1"
+        api_key = "anthropic-key"
+        model = "claude-3-5-haiku-latest"
+        max_tokens = 512
+
+        [[ai.providers]]
+        provider = "ollama"
+        base_url = "{root}"
+        model = "llama3.1"
+    "#, root = ai.base_url);
+    let vars: HashMap<&str, String> = HashMap::from([
+        ("DATABASE_URL", "mysql://fynoss@127.0.0.1:3306/fynoss".to_string()),
+        ("GITHUB_TOKEN", "test-token".to_string()),
+        ("OPENAI_KEY", "openai-key".to_string()),
+        ("FYNOSS_GITHUB_BASE_URL", github.base_url.clone()),
+        ("FYNOSS_SERVER_WORKERS", "1".to_string()),
+        ("FYNOSS_CACHE_AI_ANSWER_TIER", answer_tier.to_string()),
+        ("FYNOSS_CHAT_STORE", "memory".to_string()),
+        ("FYNOSS_ADMIN_TOKEN", ADMIN_TOKEN.to_string()),
+    ]);
+    let config = Config::from_layers(Some(&file), |key| vars.get(key).cloned(), Args::default()).unwrap();
+    let pool = MySqlPoolOptions::new().connect_lazy(&config.database.url).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    actix_web::rt::spawn(build_server(&config, pool, listener).unwrap());
+
+    TestServer {
+        base_url,
+        ai,
+        github,
+        http: reqwest::Client::new(),
+    }
+}
+
+#[actix_web::test]
+async fn test_default_provider_is_openai() {
+    let server = start().await;
+
+    let res = server.ask("").await;
+    assert_eq!(res.status(), 200);
+    let mut body: Value = res.json().await.unwrap();
+    let context = body.as_object_mut().unwrap().remove("context").unwrap();
+    assert_eq!(context["budget_tokens"], 16_000);
+    let sections: Vec<(&str, usize)> = context["sections"].as_array().unwrap().iter()
+        .map(|s| (s["name"].as_str().unwrap(), s["omitted"].as_array().unwrap().len()))
+        .collect();
+    assert_eq!(sections, vec![("readme", 0), ("open_issues", 0), ("contributing", 0), ("file_tree", 0)]);
+    assert_eq!(body, json!({
+        "status": "answered",
+        "guide": serde_json::from_str::<Value>(DEFAULT_ANSWER).unwrap(),
+        "model": "gpt-4o-mini",
+        "finish_reason": "stop",
+        "usage": {"prompt_tokens": 12, "completion_tokens": 8},
+        "template": {"name": "how_to_contribute", "version": 1},
+        "cached": false,
+    }));
+
+    let requests = server.ai.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/v1/chat/completions");
+    assert_eq!(requests[0].headers["authorization"], "Bearer openai-key");
+    assert_eq!(requests[0].body["model"], "gpt-4o-mini");
+    assert_eq!(requests[0].body["messages"][0]["role"], "system");
+    assert!(requests[0].body["messages"][0]["content"].as_str().unwrap().contains("at the beginner level"));
+    assert!(requests[0].body["messages"][1]["content"].as_str().unwrap().contains("# Octo Repo"));
+    assert_eq!(requests[0].body["response_format"]["type"], "json_schema");
+    assert_eq!(requests[0].body["response_format"]["json_schema"]["name"], "contribution_guide");
+    assert_eq!(requests[0].body["response_format"]["json_schema"]["strict"], true);
+}
+
+#[actix_web::test]
+async fn test_provider_is_chosen_per_request() {
+    let server = start().await;
+
+    let anthropic: Value = server.ask("?provider=anthropic").await.json().await.unwrap();
+    assert_eq!(anthropic["guide"]["recommended_issues"][0]["difficulty"], "easy");
+    assert_eq!(anthropic["finish_reason"], "tool_use");
+    let ollama: Value = server.ask("?provider=ollama").await.json().await.unwrap();
+    assert_eq!(ollama["model"], "llama3.1");
+    assert_eq!(ollama["usage"], json!({"prompt_tokens": 12, "completion_tokens": 8}));
+
+    let requests = server.ai.requests();
+    assert_eq!(requests[0].path, "/v1/messages");
+    assert_eq!(requests[0].headers["x-api-key"], "anthropic-key");
+    assert_eq!(requests[0].headers["anthropic-version"], "2023-06-01");
+    assert_eq!(requests[0].body["model"], "claude-3-5-haiku-latest");
+    assert_eq!(requests[0].body["max_tokens"], 512);
+    assert_eq!(requests[0].body["tool_choice"], json!({"type": "tool", "name": "contribution_guide"}));
+    assert_eq!(requests[0].body["tools"][0]["input_schema"]["additionalProperties"], false);
+    // The Messages API takes the system prompt apart from the messages.
+    assert!(requests[0].body["system"].as_str().unwrap().contains("at the beginner level"));
+    assert_eq!(requests[0].body["messages"].as_array().unwrap().len(), 1);
+
+    assert_eq!(requests[1].path, "/api/chat");
+    assert!(!requests[1].headers.contains_key("authorization"));
+    assert_eq!(requests[1].body["model"], "llama3.1");
+    assert_eq!(requests[1].body["stream"], false);
+    assert_eq!(requests[1].body["format"]["type"], "object");
+    assert_eq!(requests[1].body["messages"][0]["role"], "system");
+
+    let res = server.ask("?provider=openai_compatible").await;
+    assert_eq!(res.status(), 422);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "provider");
+    let res = server.ask("?provider=gemini").await;
+    assert_eq!(res.status(), 422);
+    let body: Value = res.json().await.unwrap();
+    assert!(body["errors"][0]["message"].as_str().unwrap().starts_with("unknown variant `gemini`"));
+    assert_eq!(server.ai.requests().len(), 2);
+}
+
+#[actix_web::test]
+async fn test_provider_errors_are_reported_as_bad_gateway() {
+    let server = start().await;
+    server.ai.fail_with(429, "Rate limit reached for requests");
+
+    let res = server.ask("").await;
+    assert_eq!(res.status(), 502);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body, json!({"provider": "openai", "status": 429, "message": "Rate limit reached for requests"}));
+
+    let body: Value = server.ask("?provider=anthropic").await.json().await.unwrap();
+    assert_eq!(body["message"], "Rate limit reached for requests");
+    let body: Value = server.ask("?provider=ollama").await.json().await.unwrap();
+    assert_eq!(body["provider"], "ollama");
+    assert_eq!(body["message"], "Rate limit reached for requests");
+}
+
+#[actix_web::test]
+async fn test_invalid_answer_is_repaired_once() {
+    let server = start().await;
+    let fenced = format!("```json\n{}\n```", DEFAULT_ANSWER.replace("\"easy\"", "\"trivial\""));
+    server.ai.set_answers(&[&fenced, DEFAULT_ANSWER]);
+
+    let res = server.ask("").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["guide"]["recommended_issues"][0]["difficulty"], "easy");
+    assert_eq!(body["usage"], json!({"prompt_tokens": 24, "completion_tokens": 16}));
+
+    let requests = server.ai.requests();
+    assert_eq!(requests.len(), 2);
+    let repair = requests[1].body["messages"][1]["content"].as_str().unwrap();
+    assert!(repair.contains("\"trivial\""));
+    assert!(repair.contains("It does not follow the schema: invalid JSON: unknown variant `trivial`"));
+
+    server.ai.set_answers(&["Start with the issues labelled good first issue."]);
+    let res = server.ask("").await;
+    assert_eq!(res.status(), 500);
+    assert!(res.text().await.unwrap().contains("answer does not follow the schema after a retry"));
+    assert_eq!(server.ai.requests().len(), 4);
+}
+
+#[actix_web::test]
+async fn test_prompt_includes_issues_contributing_and_file_tree() {
+    let server = start().await;
+    let made_up = DEFAULT_ANSWER.replace("\"easy\"", "\"medium\"}, {\"url\": \"https://github.com/octo-org/octo-repo/issues/99\", \"why\": \"\", \"difficulty\": \"easy\"");
+    server.ai.set_answers(&[&made_up]);
+
+    let body: Value = server.ask("").await.json().await.unwrap();
+    let issues = body["guide"]["recommended_issues"].as_array().unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0]["url"], "https://github.com/octo-org/octo-repo/issues/12");
+
+    let prompt = server.ai.requests()[0].body["messages"][1]["content"].as_str().unwrap().to_string();
+    let section = |tag: &str| {
+        let start = prompt.find(&format!("<{}>", tag)).unwrap_or_else(|| panic!("no <{}> in {}", tag, prompt));
+        let end = prompt.find(&format!("</{}>", tag)).unwrap();
+        prompt[start..end].to_string()
+    };
+    assert!(section("readme").contains("Octo Repo"));
+    assert!(section("contributing").contains("cargo test --all-features"));
+    let open_issues = section("open_issues");
+    assert!(open_issues.contains("- https://github.com/octo-org/octo-repo/issues/12 Document the configuration file"));
+    assert!(open_issues.contains("  Printing each step would help debugging."));
+    assert_eq!(open_issues.matches("/issues/15").count(), 1);
+    assert!(section("file_tree").contains("src/ (3 files)\n  src/config/ (2 files)"));
+
+    let searches: Vec<String> = server.github.requests().into_iter()
+        .filter(|r| r.path == "/search/issues")
+        .map(|r| reqwest::Url::parse(&format!("http://stub/?{}", r.query)).unwrap().query_pairs()
+            .find(|(name, _)| name == "q").unwrap().1.into_owned())
+        .collect();
+    assert_eq!(searches.len(), 2);
+    assert!(searches[0].ends_with(r#"label:"good first issue""#), "{}", searches[0]);
+    assert!(searches[1].ends_with(r#"label:"help wanted""#), "{}", searches[1]);
+}
+
+#[actix_web::test]
+async fn test_context_is_shortened_to_the_prompt_budget() {
+    let server = start_with("max_prompt_tokens = 1").await;
+
+    let res = server.ask("").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["context"]["budget_tokens"], 1);
+    let readme = &body["context"]["sections"][0];
+    assert_eq!(readme["name"], "readme");
+    assert_eq!(readme["tokens"], 0);
+    assert_eq!(readme["omitted"], json!(["Octo Repo", "Build", "Test"]));
+    let issues = &body["context"]["sections"][1];
+    assert_eq!(issues["omitted"][0], "- https://github.com/octo-org/octo-repo/issues/12 Document the configuration file");
+
+    // The instructions always go in whole, so only the context is left out.
+    let prompt = server.ai.requests()[0].body["messages"][1]["content"].as_str().unwrap().to_string();
+    assert!(prompt.contains("What do I have to study to contribute to octo-org/octo-repo?"));
+    assert!(prompt.contains("<readme>\n</readme>"));
+    assert!(!prompt.contains("cargo build"));
+}
+
+#[actix_web::test]
+async fn test_prompt_templates_are_versioned_and_previewed() {
+    let dir = std::env::temp_dir().join(format!("fynoss-e2e-prompts-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let v1 = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/prompts/how_to_contribute/v1.toml"));
+    let v2 = v1.replace("version = 1", "version = 2").replace("You help newcomers", "You mentor newcomers");
+    std::fs::write(dir.join("how_to_contribute_v2.toml"), v2).unwrap();
+    let server = start_with(&format!("prompts_dir = {:?}", dir.to_str().unwrap())).await;
+
+    // The latest version is used unless a request names one.
+    let body: Value = server.ask("?skill_level=advanced").await.json().await.unwrap();
+    assert_eq!(body["template"], json!({"name": "how_to_contribute", "version": 2}));
+    let body: Value = server.ask("?template_version=1").await.json().await.unwrap();
+    assert_eq!(body["template"], json!({"name": "how_to_contribute", "version": 1}));
+    let requests = server.ai.requests();
+    assert!(requests[0].body["messages"][0]["content"].as_str().unwrap().starts_with("You mentor newcomers"));
+    assert!(requests[0].body["messages"][0]["content"].as_str().unwrap().contains("at the advanced level"));
+    assert!(requests[1].body["messages"][0]["content"].as_str().unwrap().starts_with("You help newcomers"));
+
+    let res = server.ask("?template_version=9").await;
+    assert_eq!(res.status(), 422);
+    assert_eq!(res.json::<Value>().await.unwrap()["errors"][0]["field"], "template_version");
+    let res = server.ask("?skill_level=expert").await;
+    assert_eq!(res.status(), 422);
+    assert_eq!(res.json::<Value>().await.unwrap()["errors"][0]["field"], "skill_level");
+
+    let templates: Value = server.prompts("").await.json().await.unwrap();
+    let versions: Vec<(&str, u64)> = templates.as_array().unwrap().iter()
+        .map(|t| (t["name"].as_str().unwrap(), t["version"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(versions, vec![("contribution_chat", 1), ("how_to_contribute", 1), ("how_to_contribute", 2)]);
+    assert_eq!(templates[1]["variables"][2], json!({
+        "name": "skill_level",
+        "type": "text",
+        "description": "Experience of the reader",
+        "required": false,
+        "values": ["beginner", "intermediate", "advanced"],
+        "default": "beginner",
+        "weight": 1,
+    }));
+
+    // Previews fetch from the repository, so only admins may ask for them.
+    let fetched = server.github.requests().len();
+    let res = server.http.get(format!("{}/admin/ai/prompts/how_to_contribute/2/preview?owner=octo-org&repo=octo-repo", server.base_url)).send().await.unwrap();
+    assert_eq!(res.status(), 401);
+    assert_eq!(server.github.requests().len(), fetched);
+
+    let res = server.prompts("/how_to_contribute/1/preview?skill_level=intermediate").await;
+    assert_eq!(res.status(), 200);
+    let preview: Value = res.json().await.unwrap();
+    assert_eq!(preview["repository"], "octo-org/octo-repo");
+    assert!(preview["prompt"]["system"].as_str().unwrap().contains("at the intermediate level"));
+    assert!(preview["prompt"]["user"].as_str().unwrap().contains("tidies up Markdown files"));
+    assert_eq!(preview["context"]["budget_tokens"], 16_000);
+    let preview: Value = server.prompts("/how_to_contribute/2/preview?owner=octo-org&repo=octo-repo&provider=ollama").await.json().await.unwrap();
+    assert!(preview["prompt"]["user"].as_str().unwrap().contains("# Octo Repo"));
+    assert_eq!(preview["context"]["budget_tokens"], 3_000);
+    assert_eq!(server.prompts("/how_to_contribute/9/preview").await.status(), 404);
+    assert_eq!(server.prompts("/how_to_contribute/1/preview?owner=octo-org").await.status(), 422);
+    // Previews never reach the model.
+    assert_eq!(server.ai.requests().len(), 2);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_answers_are_reused_until_refreshed_or_invalidated() {
+    let server = launch("", "memory").await;
+
+    let first: Value = server.ask("").await.json().await.unwrap();
+    assert_eq!(first["cached"], false);
+    let fetched = server.github.requests().len();
+    let second: Value = server.ask("").await.json().await.unwrap();
+    assert_eq!(second["cached"], true);
+    // The repository is fetched again, as only an answer about its current content is reused.
+    assert!(server.github.requests().len() > fetched);
+    assert_eq!(second["guide"], first["guide"]);
+    assert_eq!(second["template"], first["template"]);
+    assert_eq!(server.ai.requests().len(), 1);
+
+    // Another skill level or model makes another prompt.
+    server.ask("?skill_level=advanced").await;
+    server.ask("?provider=ollama").await;
+    assert_eq!(server.ai.requests().len(), 3);
+
+    let refreshed: Value = server.ask("?refresh=true").await.json().await.unwrap();
+    assert_eq!(refreshed["cached"], false);
+    assert_eq!(server.ai.requests().len(), 4);
+    assert_eq!(server.ask("").await.json::<Value>().await.unwrap()["cached"], true);
+
+    let url = format!("{}/admin/ai/answers/Octo-Org/octo-repo", server.base_url);
+    assert_eq!(server.http.delete(&url).send().await.unwrap().status(), 401);
+    assert_eq!(server.ask("").await.json::<Value>().await.unwrap()["cached"], true);
+    let res = server.http.delete(&url).bearer_auth(ADMIN_TOKEN).send().await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.json::<Value>().await.unwrap(), json!({"deleted": 3}));
+    assert_eq!(server.ask("").await.json::<Value>().await.unwrap()["cached"], false);
+    assert_eq!(server.ai.requests().len(), 5);
+}
+
+#[actix_web::test]
+async fn test_answers_are_streamed_as_server_sent_events() {
+    let server = launch("", "memory").await;
+
+    let res = server.ask_stream("").await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "text/event-stream");
+    assert_eq!(res.headers()["cache-control"], "no-cache");
+
+    for provider in ["openai", "anthropic", "ollama"] {
+        let events = server.events(&format!("?provider={}&refresh=true", provider)).await;
+        let (done, deltas) = events.split_last().unwrap();
+        assert!(deltas.len() > 1, "{}: {:?}", provider, events);
+        assert!(deltas.iter().all(|(name, _)| name == "delta"), "{}: {:?}", provider, events);
+        let text: String = deltas.iter().map(|(_, data)| data["text"].as_str().unwrap()).collect();
+        assert_eq!(text, DEFAULT_ANSWER, "{}", provider);
+        assert_eq!(done.0, "done");
+        assert_eq!(done.1["guide"], serde_json::from_str::<Value>(DEFAULT_ANSWER).unwrap());
+        assert_eq!(done.1["usage"], json!({"prompt_tokens": 12, "completion_tokens": 8}), "{}", provider);
+        assert_eq!(done.1["template"], json!({"name": "how_to_contribute", "version": 1}));
+        assert_eq!(done.1["cached"], false);
+    }
+    let requests = server.ai.requests();
+    assert!(requests.iter().all(|r| r.body["stream"] == true));
+    assert_eq!(requests[1].body["stream_options"], json!({"include_usage": true}));
+    assert_eq!(requests[2].body["tool_choice"]["name"], "contribution_guide");
+
+    // Stored answers have nothing to stream.
+    let events = server.events("").await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, "done");
+    assert_eq!(events[0].1["cached"], true);
+    assert_eq!(server.ai.requests().len(), 4);
+}
+
+#[actix_web::test]
+async fn test_stream_errors_are_sent_as_events() {
+    let server = start().await;
+
+    let events = server.events("?template_version=9").await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, "error");
+    assert_eq!(events[0].1["errors"][0]["field"], "template_version");
+
+    server.ai.fail_with(429, "Rate limit reached for requests");
+    let events = server.events("?provider=anthropic").await;
+    assert_eq!(events, vec![("error".to_string(), json!({"provider": "anthropic", "status": 429, "message": "Rate limit reached for requests"}))]);
+}
+
+#[actix_web::test]
+async fn test_stream_stops_when_the_client_disconnects() {
+    let server = start().await;
+    server.ai.set_stream_delay(Duration::from_millis(100));
+
+    let mut res = server.ask_stream("").await;
+    let first = res.chunk().await.unwrap().unwrap();
+    assert!(first.starts_with(b"event: delta"));
+    drop(res);
+
+    // The stub stops once the server hung up on it, well before the answer's end.
+    actix_web::rt::time::sleep(Duration::from_millis(500)).await;
+    let sent = server.ai.streamed_events();
+    actix_web::rt::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(server.ai.streamed_events(), sent);
+    assert!(sent < DEFAULT_ANSWER.len() / 40, "{} events sent", sent);
+}
+
+#[actix_web::test]
+async fn test_chat_sessions_keep_the_conversation() {
+    let server = start().await;
+    server.ai.set_answers(&["Run cargo test.", "Install the MSVC toolchain first."]);
+
+    let res = server.chat("", Some(json!({"owner": "octo-org", "repo": "octo-repo", "provider": "anthropic"}))).await;
+    assert_eq!(res.status(), 201);
+    let session: Value = res.json().await.unwrap();
+    assert_eq!(session["repository"], "octo-org/octo-repo");
+    assert_eq!(session["model"], "anthropic/claude-3-5-haiku-latest");
+    assert_eq!(session["context"]["sections"][0]["name"], "readme");
+    assert_eq!(session["messages"], json!([]));
+    let id = session["id"].as_str().unwrap();
+
+    let path = format!("/{}/messages", id);
+    let reply: Value = server.chat(&path, Some(json!({"content": "How do I run the tests?"}))).await.json().await.unwrap();
+    assert_eq!(reply["message"]["role"], "assistant");
+    assert_eq!(reply["message"]["content"], "Run cargo test.");
+    assert_eq!(reply["omitted_messages"], 0);
+    let reply: Value = server.chat(&path, Some(json!({"content": "And on Windows?"}))).await.json().await.unwrap();
+    assert_eq!(reply["message"]["content"], "Install the MSVC toolchain first.");
+
+    // The repository goes in the system prompt, earlier turns before the new question.
+    let request = &server.ai.requests()[1].body;
+    assert!(request["system"].as_str().unwrap().contains("cargo test --all-features"));
+    let turns: Vec<(&str, &str)> = request["messages"].as_array().unwrap().iter()
+        .map(|m| (m["role"].as_str().unwrap(), m["content"].as_str().unwrap()))
+        .collect();
+    assert_eq!(turns, vec![
+        ("user", "How do I run the tests?"),
+        ("assistant", "Run cargo test."),
+        ("user", "And on Windows?"),
+    ]);
+
+    let session: Value = server.chat(&format!("/{}", id), None).await.json().await.unwrap();
+    assert_eq!(session["context"], Value::Null);
+    assert_eq!(session["messages"].as_array().unwrap().len(), 4);
+    assert_eq!(session["messages"][3]["content"], "Install the MSVC toolchain first.");
+
+    let res = server.chat(&path, Some(json!({"content": " "}))).await;
+    assert_eq!(res.status(), 422);
+    let res = server.http.delete(format!("{}/ai/chat/sessions/{}", server.base_url, id)).send().await.unwrap();
+    assert_eq!(res.status(), 204);
+    assert_eq!(server.chat(&format!("/{}", id), None).await.status(), 404);
+    assert_eq!(server.chat(&path, Some(json!({"content": "Still there?"}))).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn test_chat_questions_asked_at_once_are_answered_in_turn() {
+    let server = start().await;
+    server.ai.set_answers(&["Run cargo test.", "Install the MSVC toolchain first."]);
+
+    let session: Value = server.chat("", Some(json!({"owner": "octo-org", "repo": "octo-repo", "provider": "anthropic"}))).await.json().await.unwrap();
+    let path = format!("/{}/messages", session["id"].as_str().unwrap());
+    let (first, second) = futures::join!(
+        server.chat(&path, Some(json!({"content": "How do I run the tests?"}))),
+        server.chat(&path, Some(json!({"content": "And on Windows?"}))),
+    );
+    assert_eq!((first.status().as_u16(), second.status().as_u16()), (200, 200));
+
+    // The later question was answered knowing the earlier one and its answer.
+    assert_eq!(server.ai.requests()[1].body["messages"].as_array().unwrap().len(), 3);
+    let session: Value = server.chat(&format!("/{}", session["id"].as_str().unwrap()), None).await.json().await.unwrap();
+    let roles: Vec<&str> = session["messages"].as_array().unwrap().iter().map(|m| m["role"].as_str().unwrap()).collect();
+    assert_eq!(roles, vec!["user", "assistant", "user", "assistant"]);
+}
+
+#[actix_web::test]
+async fn test_chat_history_is_shortened_to_the_prompt_budget() {
+    let server = start_with("max_prompt_tokens = 1").await;
+
+    let session: Value = server.chat("", Some(json!({"owner": "octo-org", "repo": "octo-repo"}))).await.json().await.unwrap();
+    // The repository's context only gets part of the budget, leaving the rest to the conversation.
+    assert_eq!(session["context"]["budget_tokens"], 0);
+    let path = format!("/{}/messages", session["id"].as_str().unwrap());
+    server.chat(&path, Some(json!({"content": "How do I run the tests?"}))).await;
+    let reply: Value = server.chat(&path, Some(json!({"content": "And on Windows?"}))).await.json().await.unwrap();
+    assert_eq!(reply["omitted_messages"], 2);
+
+    let messages = &server.ai.requests()[1].body["messages"];
+    assert_eq!(messages.as_array().unwrap().len(), 2);
+    assert_eq!(messages[1]["content"], "And on Windows?");
+
+    let res = server.chat("", Some(json!({"owner": "octo-org", "repo": "octo-repo", "provider": "gemini"}))).await;
+    assert_eq!(res.status(), 422);
+}
@@ -0,0 +1,133 @@
+use std::net::TcpListener;
+use std::path::Path;
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::gitea::client::client::GiteaClient;
+use crate::pkg::gitea::repositories::GiteaRepositoryClient;
+use crate::pkg::gitea::stub::stub_server::{start_gitea_stub_server, GiteaStubHandle};
+use crate::pkg::github::model::model::{IssueState, SearchIssuesRequest, SearchIssuesSortKey, SearchRepositoriesRequest, SortOrder};
+use crate::pkg::github::repositories::RepositoryClient;
+
+/// Starts the Gitea stub on an ephemeral port and a repository client pointed at it.
+fn start(token: Option<&str>) -> (GiteaRepositoryClient<GiteaClient>, GiteaStubHandle) {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/gitea");
+    let (server, stub) = start_gitea_stub_server(fixtures, TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(server);
+
+    let client = GiteaClient::new(token.map(|t| t.to_string()), &stub.base_url, reqwest::Client::new()).unwrap();
+    let repository_client = GiteaRepositoryClient::new(
+        client,
+        vec!["good first issue".to_string()],
+        vec!["help wanted".to_string()],
+    );
+    (repository_client, stub)
+}
+
+#[actix_web::test]
+async fn test_gitea_search_filters_client_side() {
+    let (client, stub) = start(Some("gitea-token"));
+    // Below the client's page size, so the search takes several pages.
+    stub.set_max_response_items(2);
+
+    let repositories = client.fetch_repositories(SearchRepositoriesRequest {
+        min_stars: 1000,
+        max_stars: None,
+        last_pushed: "2024-09-07".to_string(),
+        language: "go".to_string(),
+        good_first_issues_count: 1,
+        help_wanted_count: 1,
+        forge: Some(Forge::Gitea),
+    }).await.unwrap();
+
+    // legacy is archived, website is TypeScript, stale was last updated in 2023, wiki was
+    // updated but last pushed in January and runner has no good first issues.
+    assert_eq!(repositories.total_count, 1);
+    let repository = &repositories.items[0];
+    assert_eq!(repository.full_name, "forgejo/forgejo");
+    assert_eq!(repository.stargazers_count, 2800);
+    assert_eq!(repository.forge, Forge::Gitea);
+    assert_eq!(repository.host, stub.base_url.trim_start_matches("http://").trim_end_matches("/api/v1"));
+
+    let requests = stub.requests();
+    assert_eq!(requests[0].query["sort"], "stars");
+    assert_eq!(requests[0].headers["authorization"], "token gitea-token");
+    // The fourth page reaches below the star floor, so the fifth is never asked for.
+    let pages: Vec<&str> = requests.iter()
+        .filter(|r| r.path == "/api/v1/repos/search")
+        .map(|r| r.query["page"].as_str())
+        .collect();
+    assert_eq!(pages, vec!["1", "2", "3", "4"]);
+    let branches: Vec<&str> = requests.iter()
+        .filter(|r| r.path.ends_with("/branches/main"))
+        .map(|r| r.path.as_str())
+        .collect();
+    assert_eq!(branches, vec![
+        "/api/v1/repos/forgejo/forgejo/branches/main",
+        "/api/v1/repos/forgejo/runner/branches/main",
+        "/api/v1/repos/forgejo/wiki/branches/main",
+    ]);
+    let label_counts: Vec<(&str, &str)> = requests.iter()
+        .filter(|r| r.path.ends_with("/issues"))
+        .map(|r| (r.path.as_str(), r.query["labels"].as_str()))
+        .collect();
+    assert_eq!(label_counts, vec![
+        ("/api/v1/repos/forgejo/forgejo/issues", "good first issue"),
+        ("/api/v1/repos/forgejo/forgejo/issues", "help wanted"),
+        ("/api/v1/repos/forgejo/runner/issues", "good first issue"),
+    ]);
+}
+
+#[actix_web::test]
+async fn test_gitea_issues_with_several_labels_count_once() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/gitea");
+    let (server, stub) = start_gitea_stub_server(fixtures, TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(server);
+    let client = GiteaRepositoryClient::new(
+        GiteaClient::new(None, &stub.base_url, reqwest::Client::new()).unwrap(),
+        vec!["good first issue".to_string(), "help wanted".to_string()],
+        vec![],
+    );
+    let search = |good_first_issues_count| SearchRepositoriesRequest {
+        min_stars: 2000,
+        max_stars: None,
+        last_pushed: "2024-09-07".to_string(),
+        language: "go".to_string(),
+        good_first_issues_count,
+        help_wanted_count: 0,
+        forge: None,
+    };
+
+    // forgejo has three open issues with either label, one of them with both.
+    assert_eq!(client.fetch_repositories(search(3)).await.unwrap().total_count, 1);
+    assert_eq!(client.fetch_repositories(search(4)).await.unwrap().total_count, 0);
+    let requests = stub.requests();
+    let counts: Vec<&str> = requests.iter()
+        .filter(|r| r.path.ends_with("/issues"))
+        .map(|r| r.query["labels"].as_str())
+        .collect();
+    assert_eq!(counts, vec!["good first issue,help wanted", "good first issue,help wanted"]);
+}
+
+#[actix_web::test]
+async fn test_gitea_readme_and_unassigned_issues() {
+    let (client, stub) = start(None);
+
+    let readme = client.fetch_top_readme(None, "forgejo", "forgejo").await.unwrap();
+    assert!(readme.found);
+    assert!(readme.content.unwrap().starts_with("# Forgejo"));
+
+    let issues = client.fetch_issues(None, "forgejo", "forgejo", SearchIssuesRequest {
+        state: IssueState::Open,
+        assignee: "none".to_string(),
+        labels: vec!["good first issue".to_string()],
+        sort_key: SearchIssuesSortKey::Created,
+        sort_order: SortOrder::Desc,
+    }).await.unwrap();
+    let titles: Vec<&str> = issues.iter().map(|i| i.title.as_str()).collect();
+    assert_eq!(titles, vec!["Translate the settings page", "Speed up the repository indexer"]);
+
+    let missing = client.fetch_top_readme(None, "forgejo", "runner").await.unwrap();
+    assert!(!missing.found);
+    let requests = stub.requests();
+    assert!(requests.iter().all(|r| !r.headers.contains_key("authorization")));
+    assert_eq!(requests.iter().filter(|r| r.path.starts_with("/api/v1/repos/forgejo/runner/contents/")).count(), 7);
+}
@@ -0,0 +1,583 @@
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use serde_json::{json, Value};
+use sqlx::mysql::MySqlPoolOptions;
+use crate::config::config::{Args, Config};
+use crate::pkg::gitea::stub::stub_server::start_gitea_stub_server;
+use crate::pkg::github::client::app_auth::GithubAppAuth;
+use crate::pkg::github::client::cache::{CacheTier, CacheTtls, ResponseCache};
+use crate::pkg::github::client::client::GithubClient;
+use crate::pkg::github::repositories::{GithubRepositoryClient, RepositoryClient};
+use crate::pkg::github::stub::stub_server::{start_stub_server, Scenario, StubHandle};
+use crate::pkg::utils::http_fixture::http_fixture::{FixtureMode, HttpFixtures};
+use crate::server::server::build_server;
+
+const ADMIN_TOKEN: &str = "admin-token";
+
+struct TestServer {
+    base_url: String,
+    stub: StubHandle,
+    http: reqwest::Client,
+}
+
+/// Starts the GitHub stub and the real server pointed at it, both on ephemeral ports.
+async fn start() -> TestServer {
+    start_with(&[]).await
+}
+
+/// Like `start`, with `overrides` layered over the default environment.
+async fn start_with(overrides: &[(&'static str, &str)]) -> TestServer {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/github");
+    let (stub_server, stub) = start_stub_server(fixtures, Scenario::Normal, TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(stub_server);
+
+    let vars: HashMap<&str, String> = HashMap::from([
+        ("DATABASE_URL", "mysql://fynoss@127.0.0.1:3306/fynoss".to_string()),
+        ("GITHUB_TOKEN", "test-token".to_string()),
+        ("OPENAI_KEY", "test-key".to_string()),
+        ("FYNOSS_GITHUB_BASE_URL", stub.base_url.clone()),
+        ("FYNOSS_SERVER_WORKERS", "1".to_string()),
+        ("FYNOSS_ADMIN_TOKEN", ADMIN_TOKEN.to_string()),
+    ]);
+    let vars: HashMap<&str, String> = vars.into_iter()
+        .chain(overrides.iter().map(|(k, v)| (*k, v.to_string())))
+        .collect();
+    let config = Config::from_layers(None, |key| vars.get(key).cloned(), Args::default()).unwrap();
+    let pool = MySqlPoolOptions::new().connect_lazy(&config.database.url).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    actix_web::rt::spawn(build_server(&config, pool, listener).unwrap());
+
+    TestServer {
+        base_url,
+        stub,
+        http: reqwest::Client::new(),
+    }
+}
+
+impl TestServer {
+    async fn search(&self) -> reqwest::Response {
+        self.http.post(format!("{}/github/repositories/search-list", self.base_url))
+            .json(&json!({
+                "min_stars": 1000,
+                "max_stars": null,
+                "last_pushed": "2024-09-07",
+                "language": "rust",
+                "good_first_issues_count": 1,
+                "help_wanted_count": 1
+            }))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    async fn get(&self, path: &str) -> reqwest::Response {
+        self.http.get(format!("{}{}", self.base_url, path)).send().await.unwrap()
+    }
+
+    async fn admin(&self, path: &str) -> reqwest::Response {
+        self.http.get(format!("{}{}", self.base_url, path)).bearer_auth(ADMIN_TOKEN).send().await.unwrap()
+    }
+}
+
+#[actix_web::test]
+async fn test_search_repositories_through_stub() {
+    let server = start().await;
+
+    let res = server.search().await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["total_count"], 3);
+    assert_eq!(body["items"][0]["full_name"], "octo-org/octo-repo");
+    assert_eq!(body["items"][0]["host"], server.stub.base_url.trim_start_matches("http://"));
+    assert_eq!(body["items"][0]["forge"], "github");
+
+    let requests = server.stub.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/search/repositories");
+    assert!(requests[0].query.contains("language:Rust"), "{}", requests[0].query);
+    assert!(requests[0].query.contains("pushed:%3E=2024-09-07") || requests[0].query.contains("pushed:>=2024-09-07"), "{}", requests[0].query);
+    assert_eq!(requests[0].headers["authorization"], "token test-token");
+    assert!(requests[0].headers.contains_key("user-agent"));
+}
+
+#[actix_web::test]
+async fn test_malformed_search_requests_are_unprocessable() {
+    let server = start().await;
+    let url = format!("{}/github/repositories/search-list", server.base_url);
+
+    let res = server.http.post(&url)
+        .json(&json!({"min_stars": "abc", "last_pushed": "30d", "language": "rust", "good_first_issues_count": 1, "help_wanted_count": 1}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "body");
+    assert!(body["errors"][0]["message"].as_str().unwrap().starts_with("invalid type: string \"abc\", expected u32"));
+
+    let res = server.http.post(&url)
+        .json(&json!({"min_stars": 1, "last_pushed": "30d", "good_first_issues_count": 1, "help_wanted_count": 1}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "language");
+
+    let res = server.http.post(&url).body("{}").send().await.unwrap();
+    assert_eq!(res.status(), 400, "a body that is not declared JSON is still a bad request");
+    assert!(server.stub.requests().iter().all(|r| r.path != "/search/repositories"));
+}
+
+#[actix_web::test]
+async fn test_search_filters_by_forge() {
+    let server = start().await;
+
+    let res = server.http.post(format!("{}/github/repositories/search-list", server.base_url))
+        .json(&json!({
+            "min_stars": 1000,
+            "max_stars": null,
+            "last_pushed": "2024-09-07",
+            "language": "rust",
+            "good_first_issues_count": 1,
+            "help_wanted_count": 1,
+            "forge": "gitlab"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["total_count"], 0);
+    assert!(server.stub.requests().is_empty());
+}
+
+#[actix_web::test]
+async fn test_search_includes_gitea_hosts() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/gitea");
+    let (gitea_server, gitea) = start_gitea_stub_server(fixtures, TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(gitea_server);
+    let server = start_with(&[("FYNOSS_GITEA_BASE_URL", &gitea.base_url)]).await;
+
+    let res = server.http.post(format!("{}/github/repositories/search-list", server.base_url))
+        .json(&json!({
+            "min_stars": 1000,
+            "max_stars": null,
+            "last_pushed": "2024-09-07",
+            "language": "go",
+            "good_first_issues_count": 1,
+            "help_wanted_count": 1,
+            "forge": "gitea"
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["total_count"], 1);
+    assert_eq!(body["items"][0]["full_name"], "forgejo/forgejo");
+    assert_eq!(body["items"][0]["forge"], "gitea");
+    assert!(server.stub.requests().is_empty());
+    assert!(!gitea.requests().is_empty());
+}
+
+#[actix_web::test]
+async fn test_repositories_are_looked_up_on_the_requested_host() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/gitea");
+    let (gitea_server, gitea) = start_gitea_stub_server(fixtures, TcpListener::bind("127.0.0.1:0").unwrap()).unwrap();
+    actix_web::rt::spawn(gitea_server);
+    let server = start_with(&[("FYNOSS_GITEA_BASE_URL", &gitea.base_url)]).await;
+    let gitea_host = gitea.base_url.trim_start_matches("http://").trim_end_matches("/api/v1");
+
+    let res = server.get(&format!("/github/repositories/forgejo/forgejo/top-readme?host={}", gitea_host)).await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["found"], true);
+    assert!(server.stub.requests().is_empty());
+
+    let res = server.get("/github/repositories/forgejo/forgejo/top-readme?host=gitlab.com").await;
+    assert_eq!(res.status(), 422);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "host");
+}
+
+#[actix_web::test]
+async fn test_top_readme_and_issues_through_stub() {
+    let server = start().await;
+
+    let res = server.get("/github/repositories/octo-org/octo-repo/top-readme").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["found"], true);
+    assert!(body["content"].as_str().unwrap().starts_with("# Octo Repo"));
+    assert_eq!(body["format"], "markdown");
+    assert!(body["html"].as_str().unwrap().starts_with("<h1>Octo Repo</h1>"), "{}", body["html"]);
+
+    let res = server.get("/github/repositories/octo-org/octo-repo/issues?state=open").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 2);
+
+    let requests = server.stub.requests();
+    assert_eq!(requests[0].path, "/repos/octo-org/octo-repo/readme");
+    assert_eq!(requests[1].path, "/search/issues");
+    assert!(requests[1].query.contains("repo%3Aocto-org%2Focto-repo"), "{}", requests[1].query);
+}
+
+#[actix_web::test]
+async fn test_upstream_failures_surface_as_errors() {
+    let server = start().await;
+
+    server.stub.set_scenario(Scenario::NotFound);
+    let res = server.get("/github/repositories/octo-org/octo-repo/top-readme").await;
+    assert_eq!(res.status(), 500);
+    assert!(res.text().await.unwrap().contains("Not found"));
+
+    server.stub.set_scenario(Scenario::RateLimited);
+    let res = server.search().await;
+    assert_eq!(res.status(), 500);
+    assert!(res.text().await.unwrap().contains("API rate limit exceeded"));
+
+    server.stub.set_scenario(Scenario::Malformed);
+    let res = server.search().await;
+    assert_eq!(res.status(), 500);
+}
+
+#[actix_web::test]
+async fn test_large_latin1_readme_is_fetched_raw() {
+    let server = start().await;
+
+    let res = server.get("/github/repositories/octo-org/big-repo/top-readme").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["found"], true);
+    assert!(body["content"].as_str().unwrap().contains("Bibliothèque de référence"), "{}", body["content"]);
+    assert_eq!(body["format"], "restructuredtext");
+
+    let requests = server.stub.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].path, "/repos/octo-org/big-repo/contents/README.rst");
+    assert_eq!(requests[1].headers["accept"], "application/vnd.github.raw+json");
+}
+
+#[actix_web::test]
+async fn test_onboarding_sections_from_readme_and_contributing() {
+    let server = start().await;
+
+    let res = server.get("/github/repositories/octo-org/octo-repo/onboarding").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["readme_found"], true);
+    assert_eq!(body["contributing_found"], true);
+    let sections: Vec<(&str, &str, &str)> = body["sections"].as_array().unwrap().iter()
+        .map(|s| (s["source"].as_str().unwrap(), s["kind"].as_str().unwrap(), s["heading"].as_str().unwrap()))
+        .collect();
+    assert_eq!(sections, vec![
+        ("readme", "build", "Build"),
+        ("readme", "test", "Test"),
+        ("contributing", "contributing", "Contributing to Octo Repo"),
+        ("contributing", "test", "Running the tests"),
+        ("contributing", "code_of_conduct", "Code of Conduct"),
+    ]);
+    assert_eq!(body["sections"][0]["commands"], json!(["cargo build"]));
+    assert_eq!(body["sections"][3]["commands"], json!(["cargo test --all-features", "cargo clippy --all-targets -- -D warnings"]));
+    assert_eq!(body["sections"][4]["commands"], json!([]));
+
+    let res = server.get("/github/repositories/octo-org/big-repo/onboarding").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["readme_found"], true);
+    assert_eq!(body["contributing_found"], false);
+    // The README is reStructuredText, which is not split into sections.
+    assert_eq!(body["sections"], json!([]));
+    let lookups: Vec<String> = server.stub.requests().iter()
+        .filter(|r| r.path.starts_with("/repos/octo-org/big-repo/contents/") && !r.path.ends_with("README.rst"))
+        .map(|r| r.path.clone())
+        .collect();
+    assert_eq!(lookups, vec![
+        "/repos/octo-org/big-repo/contents/CONTRIBUTING.md",
+        "/repos/octo-org/big-repo/contents/.github/CONTRIBUTING.md",
+        "/repos/octo-org/big-repo/contents/docs/CONTRIBUTING.md",
+    ]);
+}
+
+#[actix_web::test]
+async fn test_batch_readmes_and_issues() {
+    let server = start().await;
+    let url = format!("{}/github/repositories/batch/top-readme", server.base_url);
+
+    let res = server.http.post(&url)
+        .json(&json!({"repositories": ["octo-org/octo-repo", "octo-org"]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 422);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["errors"][0]["field"], "repositories[1]");
+
+    let res = server.http.post(&url)
+        .json(&json!({"repositories": ["octo-org/octo-repo", "octo-org/missing", "octo-org/octo-repo", "octo-org/big-repo"]}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    let results = body.as_array().unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["repository"], "octo-org/octo-repo");
+    assert!(results[0]["readme"]["content"].as_str().unwrap().starts_with("# Octo Repo"));
+    assert_eq!(results[1]["repository"], "octo-org/missing");
+    assert!(results[1].get("readme").is_none());
+    assert!(results[1]["error"].is_string());
+    assert_eq!(results[2]["readme"], results[0]["readme"]);
+    assert_eq!(results[0]["readme"]["format"], "markdown");
+    assert!(results[3]["readme"]["content"].as_str().unwrap().starts_with("Big Repo"), "{}", results[3]);
+    // One GraphQL query answers what it can; the missing repository and the README too large
+    // to come back whole are fetched on their own.
+    let paths: Vec<String> = server.stub.requests().iter().map(|r| r.path.clone()).collect();
+    assert_eq!(paths.iter().filter(|p| *p == "/graphql").count(), 1);
+    assert!(!paths.contains(&"/repos/octo-org/octo-repo/readme".to_string()), "{:?}", paths);
+    assert!(paths.contains(&"/repos/octo-org/missing/readme".to_string()), "{:?}", paths);
+    assert!(paths.contains(&"/repos/octo-org/big-repo/readme".to_string()), "{:?}", paths);
+
+    let res = server.http.post(format!("{}/github/repositories/batch/issues", server.base_url))
+        .json(&json!({"repositories": ["octo-org/octo-repo"], "state": "open"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body[0]["repository"], "octo-org/octo-repo");
+    assert_eq!(body[0]["issues"].as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn test_slow_upstream() {
+    let server = start().await;
+    server.stub.set_scenario(Scenario::Slow(Duration::from_millis(300)));
+
+    let started = Instant::now();
+    let res = server.search().await;
+
+    assert_eq!(res.status(), 200);
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[actix_web::test]
+async fn test_stub_paginates_search_results() {
+    let server = start().await;
+
+    let res = server.http.get(format!("{}/search/repositories?q=stars:>1&per_page=2", server.stub.base_url))
+        .send()
+        .await
+        .unwrap();
+    let link = res.headers()["link"].to_str().unwrap().to_string();
+    let body: Value = res.json().await.unwrap();
+
+    assert_eq!(body["items"].as_array().unwrap().len(), 2);
+    assert!(link.contains("page=2>; rel=\"next\""), "{}", link);
+
+    let res = server.http.get(format!("{}/search/repositories?q=stars:>1&per_page=2&page=2", server.stub.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert!(!res.headers().contains_key("link"));
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn test_record_then_replay_github_fixtures() {
+    let server = start().await;
+    let dir = std::env::temp_dir().join(format!("fynoss-e2e-fixtures-{}", std::process::id()));
+
+    let recording = GithubClient::new("secret-token".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_fixtures(HttpFixtures::new(FixtureMode::Record, dir.clone()));
+    let readme = GithubRepositoryClient::new(recording).fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    assert!(readme.found);
+
+    // Anything reaching the stub from now on would fail, so a successful replay proves it stayed offline.
+    server.stub.set_scenario(Scenario::NotFound);
+    let replaying = GithubClient::new("".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_fixtures(HttpFixtures::new(FixtureMode::Replay, dir.clone()));
+    let replaying = GithubRepositoryClient::new(replaying);
+
+    let replayed = replaying.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    assert_eq!(replayed.content, readme.content);
+    assert_eq!(server.stub.requests().len(), 1);
+
+    let err = replaying.fetch_top_readme(None, "octo-org", "unknown").await.unwrap_err();
+    assert!(err.to_string().starts_with("No recorded fixture for GET"), "{}", err);
+
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        assert!(!std::fs::read_to_string(entry.unwrap().path()).unwrap().contains("secret-token"));
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_conditional_requests_are_served_from_cache() {
+    let server = start().await;
+    let dir = std::env::temp_dir().join(format!("fynoss-e2e-cache-{}", std::process::id()));
+
+    let cache = Arc::new(ResponseCache::new(10, Some(CacheTier::Disk(dir.clone()))));
+    let client = GithubClient::new("token".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_cache(cache.clone());
+    let client = GithubRepositoryClient::new(client);
+
+    let first = client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    let second = client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    assert_eq!(first.content, second.content);
+
+    let requests = server.stub.requests();
+    assert!(!requests[0].headers.contains_key("if-none-match"));
+    assert!(requests[1].headers["if-none-match"].starts_with("W/"));
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.memory_entries), (1, 1, 1));
+
+    // A fresh process only has the disk tier to revalidate against.
+    let restarted = Arc::new(ResponseCache::new(10, Some(CacheTier::Disk(dir.clone()))));
+    let client = GithubClient::new("token".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_cache(restarted.clone());
+    let third = GithubRepositoryClient::new(client).fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    assert_eq!(third.content, first.content);
+    let stats = restarted.stats();
+    assert_eq!((stats.hits, stats.tier_hits), (1, 1));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[actix_web::test]
+async fn test_readmes_within_their_ttl_are_not_revalidated() {
+    let server = start().await;
+
+    let cache = Arc::new(ResponseCache::new(10, None).with_ttls(CacheTtls {
+        readme: Duration::from_secs(60),
+        issues: Duration::ZERO,
+    }));
+    let client = GithubClient::new("token".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_cache(cache.clone());
+    let client = GithubRepositoryClient::new(client);
+
+    let first = client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    let second = client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    assert_eq!(first.content, second.content);
+
+    assert_eq!(server.stub.requests().len(), 1);
+    let stats = cache.stats();
+    assert_eq!((stats.fresh, stats.hits, stats.misses), (1, 0, 1));
+}
+
+#[actix_web::test]
+async fn test_revoked_token_is_quarantined_and_skipped() {
+    let server = start_with(&[("GITHUB_TOKENS", "revoked-2222,good-1111")]).await;
+    server.stub.revoke_tokens(&["revoked-2222"]);
+
+    assert_eq!(server.search().await.status(), 200);
+    assert_eq!(server.search().await.status(), 200);
+
+    let authorizations: Vec<String> = server.stub.requests().iter()
+        .map(|r| r.headers["authorization"].clone())
+        .collect();
+    assert_eq!(authorizations, ["token revoked-2222", "token good-1111", "token good-1111"]);
+
+    // Token suffixes and quota are for admins only.
+    assert_eq!(server.get("/admin/github/tokens").await.status(), 401);
+    let res = server.admin("/admin/github/tokens").await;
+    assert_eq!(res.status(), 200);
+    let body: Value = res.json().await.unwrap();
+    assert_eq!(body[0]["healthy"], 1);
+    assert_eq!(body[0]["tokens"][0]["label"], "…2222");
+    assert_eq!(body[0]["tokens"][0]["quarantined"], true);
+    assert_eq!(body[0]["tokens"][1]["quarantined"], false);
+    assert_eq!(body[0]["tokens"][1]["search_remaining"], 4999);
+}
+
+#[actix_web::test]
+async fn test_openapi_document_lists_the_served_operations() {
+    let server = start().await;
+
+    let res = server.get("/openapi.json").await;
+    assert_eq!(res.status(), 200);
+    let doc: Value = res.json().await.unwrap();
+
+    let operations: Vec<(String, String)> = doc["paths"].as_object().unwrap().iter()
+        .flat_map(|(path, item)| item.as_object().unwrap().keys().map(move |method| (method.clone(), path.clone())))
+        .collect();
+    for (method, path) in [
+        ("post", "/github/repositories/search-list"),
+        ("get", "/github/repositories/{owner_name}/{repo_name}/issues"),
+        ("delete", "/ai/chat/sessions/{session_id}"),
+        ("get", "/admin/ai/prompts"),
+        ("get", "/admin/github/tokens"),
+    ] {
+        assert!(operations.contains(&(method.to_string(), path.to_string())), "{} {} is missing", method, path);
+    }
+    assert_eq!(operations.len(), 17);
+
+    // Every schema the operations refer to has to be among the components.
+    let text = doc.to_string();
+    for reference in text.split("\"$ref\":\"#/components/schemas/").skip(1) {
+        let name = &reference[..reference.find('"').unwrap()];
+        assert!(doc["components"]["schemas"].get(name).is_some(), "{} is not a component", name);
+    }
+}
+
+fn app_auth(stub: &StubHandle) -> Arc<GithubAppAuth> {
+    let key = std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/github/app/test-only-private-key.pem")).unwrap();
+    Arc::new(GithubAppAuth::new("12345".to_string(), 42, &key, &stub.base_url, reqwest::Client::new()).unwrap())
+}
+
+#[actix_web::test]
+async fn test_github_app_installation_tokens() {
+    let server = start().await;
+    let client = GithubClient::new("".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_app_auth(app_auth(&server.stub));
+    let client = GithubRepositoryClient::new(client);
+
+    client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+
+    let requests = server.stub.requests();
+    let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+    assert_eq!(paths, ["/app/installations/42/access_tokens", "/repos/octo-org/octo-repo/readme", "/repos/octo-org/octo-repo/readme"]);
+    assert!(requests[0].headers["authorization"].starts_with("Bearer ey"));
+    assert_eq!(requests[1].headers["authorization"], "token ghs_stub_42_1");
+    assert_eq!(requests[2].headers["authorization"], "token ghs_stub_42_1");
+
+    // A revoked installation token is exchanged for a new one and the request retried.
+    server.stub.revoke_tokens(&["ghs_stub_42_1"]);
+    client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    let requests = server.stub.requests();
+    assert_eq!(requests[4].path, "/app/installations/42/access_tokens");
+    assert_eq!(requests[5].headers["authorization"], "token ghs_stub_42_2");
+}
+
+#[actix_web::test]
+async fn test_github_app_tokens_are_refreshed_before_expiry() {
+    let server = start().await;
+    server.stub.set_installation_token_ttl(Duration::from_secs(120));
+    let client = GithubClient::new("".to_string(), &server.stub.base_url, reqwest::Client::new())
+        .unwrap()
+        .with_app_auth(app_auth(&server.stub));
+    let client = GithubRepositoryClient::new(client);
+
+    client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+    client.fetch_top_readme(None, "octo-org", "octo-repo").await.unwrap();
+
+    let exchanges = server.stub.requests().iter().filter(|r| r.path.ends_with("/access_tokens")).count();
+    assert_eq!(exchanges, 2);
+}
@@ -0,0 +1,3 @@
+mod github_e2e;
+mod gitea_e2e;
+mod ai_e2e;
\ No newline at end of file
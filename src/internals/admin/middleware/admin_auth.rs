@@ -0,0 +1,76 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse};
+
+/// Every scope under this prefix is an admin scope.
+const ADMIN_PREFIX: &str = "/admin/";
+
+/// The bearer token admin requests must carry. Without one, the admin endpoints are disabled.
+#[derive(Clone)]
+pub struct AdminToken(pub Option<String>);
+
+/// Lets requests under `/admin` through only with `Authorization: Bearer <token>`; other
+/// requests pass untouched.
+pub async fn require_admin_token(
+    token: web::Data<AdminToken>,
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    if !req.path().starts_with(ADMIN_PREFIX) {
+        return Ok(next.call(req).await?.map_into_left_body());
+    }
+    let given = req.headers().get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let message = match (&token.0, given) {
+        (Some(token), Some(given)) if same_token(token, given) => {
+            return Ok(next.call(req).await?.map_into_left_body());
+        }
+        (Some(_), _) => "Missing or wrong admin token",
+        (None, _) => "The admin API is disabled; set admin.token to enable it",
+    };
+    let response = HttpResponse::Unauthorized()
+        .insert_header((WWW_AUTHENTICATE, "Bearer"))
+        .body(message);
+    Ok(req.into_response(response).map_into_right_body())
+}
+
+/// Compares every byte rather than stopping at the first difference, so the time taken does not
+/// tell how much of the token a guess got right.
+fn same_token(token: &str, given: &str) -> bool {
+    token.len() == given.len() && token.bytes().zip(given.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use crate::internals::admin::middleware::admin_auth::{require_admin_token, AdminToken};
+
+    async fn status(token: Option<&str>, uri: &str, authorization: Option<&str>) -> StatusCode {
+        let app = init_service(App::new()
+            .app_data(web::Data::new(AdminToken(token.map(str::to_string))))
+            .route("/admin/things", web::get().to(HttpResponse::Ok))
+            .route("/things", web::get().to(HttpResponse::Ok))
+            .wrap(from_fn(require_admin_token))).await;
+        let mut req = TestRequest::get().uri(uri);
+        if let Some(authorization) = authorization {
+            req = req.insert_header(("Authorization", authorization));
+        }
+        call_service(&app, req.to_request()).await.status()
+    }
+
+    #[actix_web::test]
+    async fn test_admin_paths_need_the_token() {
+        assert_eq!(status(Some("secret"), "/admin/things", Some("Bearer secret")).await, StatusCode::OK);
+        assert_eq!(status(Some("secret"), "/admin/things", Some("Bearer secreT")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some("secret"), "/admin/things", Some("secret")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some("secret"), "/admin/things", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(None, "/admin/things", Some("Bearer ")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(None, "/things", None).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod middleware;
+pub mod routers;
\ No newline at end of file
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use actix_web::{web, HttpResponse, Responder};
+use crate::internals::openapi::documented_scope::DocumentedScope;
+use crate::pkg::github::client::cache::{CacheStats, ResponseCache};
+use crate::pkg::github::client::token_pool::{TokenPool, TokenPoolHealth};
+
+pub trait AdminRouter {
+    fn admin_scope(&self) -> DocumentedScope;
+}
+
+#[derive(Clone)]
+pub struct GithubAdminRouter {
+    cache: Arc<ResponseCache>,
+    /// One pool per configured GitHub host, keyed by host
+    token_pools: Vec<(String, Arc<TokenPool>)>,
+}
+
+impl GithubAdminRouter {
+    pub fn new(cache: Arc<ResponseCache>, token_pools: Vec<(String, Arc<TokenPool>)>) -> Self {
+        Self { cache, token_pools }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/github/cache",
+    responses(
+        (status = 200, description = "Hit and miss counts of the GitHub response cache", body = CacheStats),
+        (status = 401, description = "Missing or wrong admin token", body = String),
+    )
+)]
+async fn github_cache_stats(router: web::Data<GithubAdminRouter>) -> impl Responder {
+    HttpResponse::Ok().json(router.cache.stats())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/github/tokens",
+    responses(
+        (status = 200, description = "Remaining quota and quarantine state of every GitHub token, per host", body = [TokenPoolHealth]),
+        (status = 401, description = "Missing or wrong admin token", body = String),
+    )
+)]
+async fn github_token_health(router: web::Data<GithubAdminRouter>) -> impl Responder {
+    let health: Vec<TokenPoolHealth> = router.token_pools.iter()
+        .map(|(host, pool)| pool.health(host))
+        .collect();
+    HttpResponse::Ok().json(health)
+}
+
+impl AdminRouter for GithubAdminRouter {
+    fn admin_scope(&self) -> DocumentedScope {
+        DocumentedScope::new("/admin/github", "admin")
+            .route(__path_github_cache_stats, github_cache_stats)
+            .route(__path_github_token_health, github_token_health)
+    }
+}
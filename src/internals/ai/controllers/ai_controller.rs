@@ -1,9 +1,19 @@
 use std::error::Error;
-use crate::internals::ai::models::dto::AIInquiryResponse;
+use futures::channel::mpsc::UnboundedSender;
+use crate::internals::ai::models::dto::{AIInquiryQueries, AIInquiryResponse, AnswerInvalidation, ChatQuestion, ChatReply, ChatSession, ChatSessionRequest, PromptPreview, PromptPreviewQueries};
 use crate::internals::ai::usecases::ai_usecase::AIUseCase;
+use crate::pkg::ai::prompt::template::PromptTemplate;
 
 pub trait AIController {
-    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    async fn ask_how_to_contribute_stream(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries, deltas: &UnboundedSender<String>) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    fn prompt_templates(&self) -> Vec<PromptTemplate>;
+    async fn preview_prompt(&self, name: &str, version: u32, query: PromptPreviewQueries) -> Result<Option<PromptPreview>, Box<dyn Error>>;
+    async fn invalidate_answers(&self, owner_name: &str, repository_name: &str) -> Result<AnswerInvalidation, Box<dyn Error>>;
+    async fn start_chat(&self, req: ChatSessionRequest) -> Result<ChatSession, Box<dyn Error>>;
+    async fn chat_session(&self, session_id: &str) -> Result<Option<ChatSession>, Box<dyn Error>>;
+    async fn ask_chat(&self, session_id: &str, question: ChatQuestion) -> Result<Option<ChatReply>, Box<dyn Error>>;
+    async fn end_chat(&self, session_id: &str) -> Result<bool, Box<dyn Error>>;
 }
 
 #[derive(Clone)]
@@ -20,7 +30,39 @@ impl<U: AIUseCase> OpenAIController<U> {
 }
 
 impl<U: AIUseCase> AIController for OpenAIController<U> {
-    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str) -> Result<AIInquiryResponse, Box<dyn Error>> {
-        self.usecase.ask_how_to_contribute(owner_name, repository_name).await
+    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries) -> Result<AIInquiryResponse, Box<dyn Error>> {
+        self.usecase.ask_how_to_contribute(owner_name, repository_name, query).await
+    }
+
+    async fn ask_how_to_contribute_stream(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries, deltas: &UnboundedSender<String>) -> Result<AIInquiryResponse, Box<dyn Error>> {
+        self.usecase.ask_how_to_contribute_stream(owner_name, repository_name, query, deltas).await
+    }
+
+    fn prompt_templates(&self) -> Vec<PromptTemplate> {
+        self.usecase.prompt_templates()
+    }
+
+    async fn preview_prompt(&self, name: &str, version: u32, query: PromptPreviewQueries) -> Result<Option<PromptPreview>, Box<dyn Error>> {
+        self.usecase.preview_prompt(name, version, query).await
+    }
+
+    async fn invalidate_answers(&self, owner_name: &str, repository_name: &str) -> Result<AnswerInvalidation, Box<dyn Error>> {
+        self.usecase.invalidate_answers(owner_name, repository_name).await
+    }
+
+    async fn start_chat(&self, req: ChatSessionRequest) -> Result<ChatSession, Box<dyn Error>> {
+        self.usecase.start_chat(req).await
+    }
+
+    async fn chat_session(&self, session_id: &str) -> Result<Option<ChatSession>, Box<dyn Error>> {
+        self.usecase.chat_session(session_id).await
+    }
+
+    async fn ask_chat(&self, session_id: &str, question: ChatQuestion) -> Result<Option<ChatReply>, Box<dyn Error>> {
+        self.usecase.ask_chat(session_id, question).await
+    }
+
+    async fn end_chat(&self, session_id: &str) -> Result<bool, Box<dyn Error>> {
+        self.usecase.end_chat(session_id).await
     }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 pub mod services;
 pub mod models;
+pub mod repositories;
 pub mod usecases;
 pub mod controllers;
 pub mod routers;
@@ -1,14 +1,300 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
+use crate::pkg::ai::budget::budget::{PromptReport, RenderedPrompt};
+use crate::pkg::ai::model::model::{AIProvider, Usage};
+use crate::pkg::ai::model::openai::Role;
+use crate::pkg::ai::prompt::template::TemplateRef;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Status {
-    StatusCode(u16),
+    Answered,
     ReadmeNotFound
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct AIInquiryResponse {
     pub status: Status,
+    /// The answer; only set when `status` is `answered`
+    pub guide: Option<ContributionGuide>,
+    /// Model that answered
+    pub model: Option<String>,
+    /// Why generation stopped, e.g. `stop`, or `length` when the answer was cut off
+    pub finish_reason: Option<String>,
+    pub usage: Option<Usage>,
+    /// How the README, issues and other context were fitted into the prompt
+    pub context: Option<PromptReport>,
+    /// Prompt template the question was asked with
+    pub template: Option<TemplateRef>,
+    /// Whether the answer was stored from an earlier request about the same content
+    #[serde(default)]
+    pub cached: bool,
+}
+
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AIInquiryQueries {
+    /// Host the repository is on, as in `Repository::host`; the first configured host when unset
+    pub host: Option<String>,
+    /// Configured provider to ask instead of the default one
+    pub provider: Option<AIProvider>,
+    /// Experience of the reader, e.g. `beginner`; the template's default when unset
+    pub skill_level: Option<String>,
+    /// Version of the prompt template; the latest when unset
+    pub template_version: Option<u32>,
+    /// Asks the model even when an answer about the same content is stored
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PromptPreviewQueries {
+    /// Provider whose token budget the prompt is fitted into; the default one when unset
+    pub provider: Option<AIProvider>,
+    pub skill_level: Option<String>,
+    /// Repository to render against, together with `repo`; a built-in sample when unset
+    pub owner: Option<String>,
+    pub repo: Option<String>,
+    /// Host `owner/repo` is on; the first configured host when unset
+    pub host: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AnswerInvalidation {
+    /// Stored answers dropped
+    pub deleted: u64,
+}
+
+/// `data` of a `delta` event: the next piece of the answer's text, which is the guide's JSON
+/// as the model writes it.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AnswerDelta {
     pub text: String,
 }
+
+/// Repository a chat session is about, and who is asking.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChatSessionRequest {
+    pub owner: String,
+    pub repo: String,
+    /// Host the repository is on, as in `Repository::host`; the first configured host when unset
+    pub host: Option<String>,
+    /// Configured provider to chat with instead of the default one
+    pub provider: Option<AIProvider>,
+    /// Experience of the reader, e.g. `beginner`; the template's default when unset
+    pub skill_level: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChatSession {
+    pub id: String,
+    /// As `owner/repo`
+    pub repository: String,
+    /// `provider/model` the session was started with
+    pub model: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    /// Unless another question is asked before
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+    /// How the repository's context was fitted into the prompt; only set when the session starts
+    pub context: Option<PromptReport>,
+    /// Oldest first
+    pub messages: Vec<ChatMessage>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChatMessage {
+    /// `user` or `assistant`
+    pub role: Role,
+    pub content: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChatQuestion {
+    pub content: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChatReply {
+    pub message: ChatMessage,
+    /// Model that answered
+    pub model: String,
+    /// Why generation stopped, e.g. `stop`, or `length` when the answer was cut off
+    pub finish_reason: Option<String>,
+    pub usage: Usage,
+    /// Earliest messages of the session left out of the prompt to fit the budget
+    pub omitted_messages: usize,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A template rendered the way it would be sent, without asking the model.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PromptPreview {
+    pub template: TemplateRef,
+    /// Repository the variables were filled from, as `owner/repo`
+    pub repository: String,
+    pub prompt: RenderedPrompt,
+    pub context: PromptReport,
+}
+
+
+/// How to make a first contribution, in the shape the model is asked to answer in.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ContributionGuide {
+    /// What to study before contributing
+    pub skills: Vec<Skill>,
+    pub recommended_issues: Vec<RecommendedIssue>,
+    /// Files or directories worth reading first
+    pub files: Vec<FileHint>,
+    /// Steps to build and test the project locally, in order
+    pub setup_steps: Vec<String>,
+    /// Things that commonly trip up newcomers
+    pub caveats: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Skill {
+    pub name: String,
+    pub why: String,
+    /// Documentation or tutorials, as absolute URLs
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RecommendedIssue {
+    pub url: String,
+    pub why: String,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FileHint {
+    pub path: String,
+    pub why: String,
+}
+
+impl ContributionGuide {
+    pub const SCHEMA_NAME: &'static str = "contribution_guide";
+
+    /// JSON Schema sent to the provider. Every property is required and no others are allowed,
+    /// as OpenAI's strict mode demands.
+    pub fn schema() -> Value {
+        fn object(properties: Value) -> Value {
+            let required: Vec<&String> = properties.as_object().unwrap().keys().collect();
+            json!({"type": "object", "properties": properties, "required": required, "additionalProperties": false})
+        }
+        let string = json!({"type": "string"});
+        let strings = json!({"type": "array", "items": string});
+        object(json!({
+            "skills": {"type": "array", "items": object(json!({
+                "name": string,
+                "why": string,
+                "links": strings,
+            }))},
+            "recommended_issues": {"type": "array", "items": object(json!({
+                "url": string,
+                "why": string,
+                "difficulty": {"type": "string", "enum": ["easy", "medium", "hard"]},
+            }))},
+            "files": {"type": "array", "items": object(json!({
+                "path": string,
+                "why": string,
+            }))},
+            "setup_steps": strings,
+            "caveats": strings,
+        }))
+    }
+
+    /// Parses and validates an answer, tolerating a Markdown code fence around the JSON. The
+    /// error lists every problem, worded so it can be handed back to the model.
+    pub fn parse(answer: &str) -> Result<Self, String> {
+        let json = answer.trim();
+        let json = json.strip_prefix("```json").or_else(|| json.strip_prefix("```"))
+            .and_then(|rest| rest.strip_suffix("```"))
+            .unwrap_or(json);
+        let guide: Self = serde_json::from_str(json).map_err(|e| format!("invalid JSON: {e}"))?;
+
+        let mut problems = vec![];
+        let is_url = |s: &str| s.starts_with("https://") || s.starts_with("http://");
+        for (i, skill) in guide.skills.iter().enumerate() {
+            if skill.name.trim().is_empty() {
+                problems.push(format!("skills[{i}].name is empty"));
+            }
+            for (j, link) in skill.links.iter().enumerate() {
+                if !is_url(link) {
+                    problems.push(format!("skills[{i}].links[{j}] is not an absolute URL"));
+                }
+            }
+        }
+        for (i, issue) in guide.recommended_issues.iter().enumerate() {
+            if !is_url(&issue.url) {
+                problems.push(format!("recommended_issues[{i}].url is not an absolute URL"));
+            }
+        }
+        for (i, file) in guide.files.iter().enumerate() {
+            if file.path.trim().is_empty() {
+                problems.push(format!("files[{i}].path is empty"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(guide)
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::internals::ai::models::dto::{ContributionGuide, Difficulty};
+
+    #[test]
+    fn test_parse_contribution_guide() {
+        let answer = r#"```json
+        {
+            "skills": [{"name": "Rust", "why": "The server is written in it", "links": ["https://doc.rust-lang.org/book/"]}],
+            "recommended_issues": [{"url": "https://github.com/octo-org/octo-repo/issues/1", "why": "Small", "difficulty": "easy"}],
+            "files": [{"path": "src/", "why": "All the code"}],
+            "setup_steps": ["cargo test"],
+            "caveats": []
+        }
+        ```"#;
+        let guide = ContributionGuide::parse(answer).unwrap();
+        assert_eq!(guide.recommended_issues[0].difficulty, Difficulty::Easy);
+        assert_eq!(guide.setup_steps, vec!["cargo test"]);
+
+        let err = ContributionGuide::parse(r#"{"skills": []}"#).unwrap_err();
+        assert!(err.starts_with("invalid JSON: missing field `recommended_issues`"), "{err}");
+
+        let err = ContributionGuide::parse(r#"{
+            "skills": [{"name": " ", "why": "", "links": ["the book"]}],
+            "recommended_issues": [{"url": "issues/1", "why": "", "difficulty": "easy"}],
+            "files": [], "setup_steps": [], "caveats": []
+        }"#).unwrap_err();
+        assert_eq!(err, "skills[0].name is empty; skills[0].links[0] is not an absolute URL; recommended_issues[0].url is not an absolute URL");
+
+        let schema = ContributionGuide::schema();
+        assert_eq!(schema["required"].as_array().unwrap().len(), 5);
+        assert_eq!(schema["properties"]["files"]["items"]["additionalProperties"], false);
+    }
+}
\ No newline at end of file
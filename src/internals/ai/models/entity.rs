@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+use sha2::{Digest, Sha256};
+use crate::pkg::ai::prompt::template::TemplateRef;
+
+/// A stored `AIInquiryResponse`, as JSON.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct CachedAnswer {
+    pub content_hash: String,
+    pub answer: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// What an answer is looked up by: the repository, asked about with the same template, model
+/// and request variables. Answers are dropped when the repository's content changes, so it is
+/// known before any of it is fetched.
+#[derive(Debug, Clone)]
+pub struct AnswerKey {
+    /// As in `Repository::host`
+    pub host: String,
+    /// Lowercase, as forges match names case-insensitively
+    pub owner_name: String,
+    pub repo_name: String,
+    pub template: TemplateRef,
+    /// `provider/model`
+    pub model: String,
+    pub skill_level: Option<String>,
+}
+
+impl AnswerKey {
+    pub fn cache_key(&self) -> String {
+        digest(&[
+            &self.host,
+            &self.owner_name,
+            &self.repo_name,
+            &self.template.name,
+            &self.template.version.to_string(),
+            &self.model,
+            self.skill_level.as_deref().unwrap_or_default(),
+        ])
+    }
+}
+
+/// A chat session and the prompt it started from.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ChatSessionRecord {
+    pub id: String,
+    pub owner_name: String,
+    pub repo_name: String,
+    /// Provider named when the session started; the default one when unset
+    pub provider: Option<String>,
+    /// `provider/model` when the session started
+    pub model: String,
+    /// Instructions followed by the repository's context, sent as the system message
+    pub system_prompt: String,
+    pub created_at: NaiveDateTime,
+    /// When the last question was asked; the session expires a while after
+    pub updated_at: NaiveDateTime,
+}
+
+impl ChatSessionRecord {
+    /// 128 random bits in hex, as the ID is all it takes to read and continue a session.
+    pub fn new_id() -> String {
+        rand::random::<[u8; 16]>().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ChatMessageRecord {
+    /// `user` or `assistant`
+    pub role: String,
+    pub content: String,
+    pub created_at: NaiveDateTime,
+}
+
+/// Hex SHA-256 of `parts`, separated so that moving text between parts changes the hash.
+pub fn digest<S: AsRef<str>>(parts: &[S]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_ref().as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
\ No newline at end of file
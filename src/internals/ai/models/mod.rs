@@ -1,2 +1,3 @@
 pub mod entity;
-pub mod dto;
\ No newline at end of file
+pub mod dto;
+pub mod sea_query;
\ No newline at end of file
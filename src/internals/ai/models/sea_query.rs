@@ -0,0 +1,93 @@
+use std::fmt::Write;
+use sea_query::Iden;
+
+pub enum AIAnswerCache {
+    Table,
+    CacheKey,
+    Host,
+    OwnerName,
+    RepoName,
+    TemplateName,
+    TemplateVersion,
+    Model,
+    ContentHash,
+    Answer,
+    CreatedAt,
+}
+
+impl Iden for AIAnswerCache {
+    fn unquoted(&self, s: &mut dyn Write) {
+        write!(
+            s,
+            "{}", match self {
+                Self::Table => "ai_answer_cache",
+                Self::CacheKey => "cache_key",
+                Self::Host => "host",
+                Self::OwnerName => "owner_name",
+                Self::RepoName => "repo_name",
+                Self::TemplateName => "template_name",
+                Self::TemplateVersion => "template_version",
+                Self::Model => "model",
+                Self::ContentHash => "content_hash",
+                Self::Answer => "answer",
+                Self::CreatedAt => "created_at",
+            }
+        ).unwrap();
+    }
+}
+
+pub enum AIChatSessions {
+    Table,
+    Id,
+    OwnerName,
+    RepoName,
+    Provider,
+    Model,
+    SystemPrompt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl Iden for AIChatSessions {
+    fn unquoted(&self, s: &mut dyn Write) {
+        write!(
+            s,
+            "{}", match self {
+                Self::Table => "ai_chat_sessions",
+                Self::Id => "id",
+                Self::OwnerName => "owner_name",
+                Self::RepoName => "repo_name",
+                Self::Provider => "provider",
+                Self::Model => "model",
+                Self::SystemPrompt => "system_prompt",
+                Self::CreatedAt => "created_at",
+                Self::UpdatedAt => "updated_at",
+            }
+        ).unwrap();
+    }
+}
+
+pub enum AIChatMessages {
+    Table,
+    Id,
+    SessionId,
+    Role,
+    Content,
+    CreatedAt,
+}
+
+impl Iden for AIChatMessages {
+    fn unquoted(&self, s: &mut dyn Write) {
+        write!(
+            s,
+            "{}", match self {
+                Self::Table => "ai_chat_messages",
+                Self::Id => "id",
+                Self::SessionId => "session_id",
+                Self::Role => "role",
+                Self::Content => "content",
+                Self::CreatedAt => "created_at",
+            }
+        ).unwrap();
+    }
+}
\ No newline at end of file
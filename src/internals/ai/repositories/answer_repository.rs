@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::Utc;
+use sea_query::{Cond, Expr, MysqlQueryBuilder, OnConflict, Query};
+use sea_query_binder::SqlxBinder;
+use sqlx::{MySql, Pool};
+use crate::internals::ai::models::dto::AIInquiryResponse;
+use crate::internals::ai::models::entity::{AnswerKey, CachedAnswer};
+use crate::internals::ai::models::sea_query::AIAnswerCache;
+use crate::internals::github::services::repository_service::ContentListener;
+
+pub trait AnswerRepository {
+    /// The answer stored under `key`, unless it is older than the TTL or was drawn from content
+    /// other than the one hashed to `content_hash`.
+    async fn find(&self, key: &AnswerKey, content_hash: &str) -> Result<Option<AIInquiryResponse>, Box<dyn Error>>;
+    /// Stores `answer` under `key`, dropping the repository's answers about content other than
+    /// the one hashed to `content_hash`.
+    async fn save(&self, key: &AnswerKey, content_hash: &str, answer: &AIInquiryResponse) -> Result<(), Box<dyn Error>>;
+    /// Drops every answer about the repository, returning how many there were.
+    async fn invalidate(&self, owner_name: &str, repository_name: &str) -> Result<u64, Box<dyn Error>>;
+}
+
+/// Where answers are kept.
+#[derive(Clone)]
+pub enum AnswerStore {
+    None,
+    /// Lost on restart and not shared between instances
+    Memory(Arc<Mutex<HashMap<String, (AnswerKey, CachedAnswer)>>>),
+    MySql(Pool<MySql>),
+}
+
+#[derive(Clone)]
+pub struct AIAnswerRepository {
+    store: AnswerStore,
+    ttl: Duration,
+}
+
+impl AIAnswerRepository {
+    pub fn new(store: AnswerStore, ttl: Duration) -> Self {
+        Self {
+            store,
+            ttl,
+        }
+    }
+
+    /// Drops the repository's answers on `host`, or on every host when `None`.
+    async fn delete(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<u64, Box<dyn Error>> {
+        let (owner_name, repository_name) = (owner_name.to_lowercase(), repository_name.to_lowercase());
+        match &self.store {
+            AnswerStore::None => Ok(0),
+            AnswerStore::Memory(answers) => {
+                let mut answers = answers.lock().unwrap();
+                let before = answers.len();
+                answers.retain(|_, (key, _)| {
+                    (&key.owner_name, &key.repo_name) != (&owner_name, &repository_name) || host.is_some_and(|host| key.host != host)
+                });
+                Ok((before - answers.len()) as u64)
+            }
+            AnswerStore::MySql(pool) => {
+                let (q, args) = Query::delete()
+                    .from_table(AIAnswerCache::Table)
+                    .and_where_option(host.map(|host| Expr::col(AIAnswerCache::Host).eq(host)))
+                    .and_where(Expr::col(AIAnswerCache::OwnerName).eq(owner_name))
+                    .and_where(Expr::col(AIAnswerCache::RepoName).eq(repository_name))
+                    .build_sqlx(MysqlQueryBuilder);
+                Ok(sqlx::query_with(&q, args).execute(pool).await?.rows_affected())
+            }
+        }
+    }
+}
+
+impl AnswerRepository for AIAnswerRepository {
+    async fn find(&self, key: &AnswerKey, content_hash: &str) -> Result<Option<AIInquiryResponse>, Box<dyn Error>> {
+        let oldest = Utc::now().naive_utc() - chrono::Duration::from_std(self.ttl)?;
+        let row = match &self.store {
+            AnswerStore::None => None,
+            AnswerStore::Memory(answers) => answers.lock().unwrap().get(&key.cache_key()).map(|(_, row)| row.clone()),
+            AnswerStore::MySql(pool) => {
+                let (q, args) = Query::select()
+                    .columns([AIAnswerCache::ContentHash, AIAnswerCache::Answer, AIAnswerCache::CreatedAt])
+                    .from(AIAnswerCache::Table)
+                    .and_where(Expr::col(AIAnswerCache::CacheKey).eq(key.cache_key()))
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_as_with::<_, CachedAnswer, _>(&q, args).fetch_optional(pool).await?
+            }
+        };
+        match row {
+            Some(row) if row.created_at >= oldest && row.content_hash == content_hash => Ok(Some(serde_json::from_str(&row.answer)?)),
+            _ => Ok(None),
+        }
+    }
+
+    async fn save(&self, key: &AnswerKey, content_hash: &str, answer: &AIInquiryResponse) -> Result<(), Box<dyn Error>> {
+        let row = CachedAnswer {
+            content_hash: content_hash.to_string(),
+            answer: serde_json::to_string(answer)?,
+            created_at: Utc::now().naive_utc(),
+        };
+        match &self.store {
+            AnswerStore::None => {}
+            AnswerStore::Memory(answers) => {
+                let mut answers = answers.lock().unwrap();
+                answers.retain(|_, (other, stored)| {
+                    (&other.host, &other.owner_name, &other.repo_name) != (&key.host, &key.owner_name, &key.repo_name) || stored.content_hash == content_hash
+                });
+                answers.insert(key.cache_key(), (key.clone(), row));
+            }
+            AnswerStore::MySql(pool) => {
+                let (q, args) = Query::insert()
+                    .into_table(AIAnswerCache::Table)
+                    .columns([
+                        AIAnswerCache::CacheKey,
+                        AIAnswerCache::Host,
+                        AIAnswerCache::OwnerName,
+                        AIAnswerCache::RepoName,
+                        AIAnswerCache::TemplateName,
+                        AIAnswerCache::TemplateVersion,
+                        AIAnswerCache::Model,
+                        AIAnswerCache::ContentHash,
+                        AIAnswerCache::Answer,
+                        AIAnswerCache::CreatedAt,
+                    ])
+                    .values([
+                        key.cache_key().into(),
+                        key.host.clone().into(),
+                        key.owner_name.clone().into(),
+                        key.repo_name.clone().into(),
+                        key.template.name.clone().into(),
+                        key.template.version.into(),
+                        key.model.clone().into(),
+                        row.content_hash.clone().into(),
+                        row.answer.into(),
+                        row.created_at.into(),
+                    ])?
+                    .on_conflict(
+                        OnConflict::column(AIAnswerCache::CacheKey)
+                            .update_columns([AIAnswerCache::ContentHash, AIAnswerCache::Answer, AIAnswerCache::CreatedAt])
+                            .to_owned(),
+                    )
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_with(&q, args).execute(pool).await?;
+
+                let (q, args) = Query::delete()
+                    .from_table(AIAnswerCache::Table)
+                    .cond_where(
+                        Cond::all()
+                            .add(Expr::col(AIAnswerCache::Host).eq(key.host.clone()))
+                            .add(Expr::col(AIAnswerCache::OwnerName).eq(key.owner_name.clone()))
+                            .add(Expr::col(AIAnswerCache::RepoName).eq(key.repo_name.clone()))
+                            .add(Expr::col(AIAnswerCache::ContentHash).ne(row.content_hash)),
+                    )
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_with(&q, args).execute(pool).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn invalidate(&self, owner_name: &str, repository_name: &str) -> Result<u64, Box<dyn Error>> {
+        self.delete(None, owner_name, repository_name).await
+    }
+}
+
+impl ContentListener for AIAnswerRepository {
+    async fn content_changed(&self, host: &str, owner_name: &str, repository_name: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(Some(host), owner_name, repository_name).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::internals::ai::models::dto::AIInquiryResponse;
+    use crate::internals::ai::models::dto::Status::Answered;
+    use crate::internals::ai::models::entity::AnswerKey;
+    use crate::internals::ai::repositories::answer_repository::{AIAnswerRepository, AnswerRepository, AnswerStore};
+    use crate::internals::github::services::repository_service::ContentListener;
+    use crate::pkg::ai::prompt::template::TemplateRef;
+
+    fn key(skill_level: &str) -> AnswerKey {
+        AnswerKey {
+            host: "github.com".to_string(),
+            owner_name: "octo-org".to_string(),
+            repo_name: "octo-repo".to_string(),
+            template: TemplateRef { name: "how_to_contribute".to_string(), version: 1 },
+            model: "openai/gpt-4o-mini".to_string(),
+            skill_level: Some(skill_level.to_string()),
+        }
+    }
+
+    fn answer(model: &str) -> AIInquiryResponse {
+        AIInquiryResponse {
+            status: Answered,
+            guide: None,
+            model: Some(model.to_string()),
+            finish_reason: None,
+            usage: None,
+            context: None,
+            template: None,
+            cached: false,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_new_content_replaces_answers_about_the_old() {
+        let repository = AIAnswerRepository::new(AnswerStore::Memory(Default::default()), Duration::from_secs(60));
+        repository.save(&key("beginner"), "readme-1", &answer("first")).await.unwrap();
+        repository.save(&key("advanced"), "readme-1", &answer("second")).await.unwrap();
+        assert_eq!(repository.find(&key("beginner"), "readme-1").await.unwrap().unwrap().model.as_deref(), Some("first"));
+
+        // An answer about other content is never served, even while it is stored.
+        assert!(repository.find(&key("beginner"), "readme-2").await.unwrap().is_none());
+
+        // The README changed, so both answers about the old one go.
+        repository.save(&key("beginner"), "readme-2", &answer("third")).await.unwrap();
+        assert!(repository.find(&key("advanced"), "readme-1").await.unwrap().is_none());
+        assert_eq!(repository.find(&key("beginner"), "readme-2").await.unwrap().unwrap().model.as_deref(), Some("third"));
+        assert_eq!(repository.invalidate("Octo-Org", "Octo-Repo").await.unwrap(), 1);
+
+        let expired = AIAnswerRepository::new(AnswerStore::Memory(Default::default()), Duration::ZERO);
+        expired.save(&key("beginner"), "readme-1", &answer("first")).await.unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(expired.find(&key("beginner"), "readme-1").await.unwrap().is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_content_changes_drop_answers_on_that_host() {
+        let repository = AIAnswerRepository::new(AnswerStore::Memory(Default::default()), Duration::from_secs(60));
+        repository.save(&key("beginner"), "readme-1", &answer("first")).await.unwrap();
+        let elsewhere = AnswerKey { host: "gitea.com".to_string(), ..key("beginner") };
+        repository.save(&elsewhere, "readme-1", &answer("second")).await.unwrap();
+
+        repository.content_changed("github.com", "Octo-Org", "octo-repo").await.unwrap();
+        assert!(repository.find(&key("beginner"), "readme-1").await.unwrap().is_none());
+        assert!(repository.find(&elsewhere, "readme-1").await.unwrap().is_some());
+    }
+}
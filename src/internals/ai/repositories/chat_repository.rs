@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{NaiveDateTime, Utc};
+use sea_query::{Expr, MysqlQueryBuilder, Order, Query};
+use sea_query_binder::SqlxBinder;
+use sqlx::{MySql, Pool};
+use crate::internals::ai::models::entity::{ChatMessageRecord, ChatSessionRecord};
+use crate::internals::ai::models::sea_query::{AIChatMessages, AIChatSessions};
+
+pub trait ChatRepository {
+    /// Stores a new session, dropping the sessions that expired.
+    async fn create(&self, session: &ChatSessionRecord) -> Result<(), Box<dyn Error>>;
+    /// The session, unless it went longer than the TTL without a question.
+    async fn find(&self, id: &str) -> Result<Option<ChatSessionRecord>, Box<dyn Error>>;
+    /// The session's messages, oldest first.
+    async fn messages(&self, id: &str) -> Result<Vec<ChatMessageRecord>, Box<dyn Error>>;
+    /// Adds `messages` to the session and restarts its TTL, unless the session was asked about
+    /// or dropped since it was last asked about at `updated_at`. Returns whether it was.
+    async fn append(&self, id: &str, updated_at: NaiveDateTime, messages: &[ChatMessageRecord]) -> Result<bool, Box<dyn Error>>;
+    /// Drops the session with its messages, returning whether there was one.
+    async fn delete(&self, id: &str) -> Result<bool, Box<dyn Error>>;
+    /// When a session last asked about at `updated_at` expires.
+    fn expires_at(&self, updated_at: NaiveDateTime) -> NaiveDateTime;
+}
+
+type MemorySessions = HashMap<String, (ChatSessionRecord, Vec<ChatMessageRecord>)>;
+
+/// Where chat sessions are kept.
+#[derive(Clone)]
+pub enum ChatStore {
+    /// Lost on restart and not shared between instances
+    Memory(Arc<Mutex<MemorySessions>>),
+    MySql(Pool<MySql>),
+}
+
+#[derive(Clone)]
+pub struct AIChatRepository {
+    store: ChatStore,
+    ttl: Duration,
+}
+
+impl AIChatRepository {
+    pub fn new(store: ChatStore, ttl: Duration) -> Self {
+        Self {
+            store,
+            ttl,
+        }
+    }
+
+    fn oldest(&self) -> Result<NaiveDateTime, Box<dyn Error>> {
+        Ok(Utc::now().naive_utc() - chrono::Duration::from_std(self.ttl)?)
+    }
+}
+
+impl ChatRepository for AIChatRepository {
+    async fn create(&self, session: &ChatSessionRecord) -> Result<(), Box<dyn Error>> {
+        let oldest = self.oldest()?;
+        match &self.store {
+            ChatStore::Memory(sessions) => {
+                let mut sessions = sessions.lock().unwrap();
+                sessions.retain(|_, (s, _)| s.updated_at >= oldest);
+                sessions.insert(session.id.clone(), (session.clone(), vec![]));
+            }
+            ChatStore::MySql(pool) => {
+                // Messages go with their session, through the foreign key.
+                let (q, args) = Query::delete()
+                    .from_table(AIChatSessions::Table)
+                    .and_where(Expr::col(AIChatSessions::UpdatedAt).lt(oldest))
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_with(&q, args).execute(pool).await?;
+
+                let (q, args) = Query::insert()
+                    .into_table(AIChatSessions::Table)
+                    .columns([
+                        AIChatSessions::Id,
+                        AIChatSessions::OwnerName,
+                        AIChatSessions::RepoName,
+                        AIChatSessions::Provider,
+                        AIChatSessions::Model,
+                        AIChatSessions::SystemPrompt,
+                        AIChatSessions::CreatedAt,
+                        AIChatSessions::UpdatedAt,
+                    ])
+                    .values([
+                        session.id.clone().into(),
+                        session.owner_name.clone().into(),
+                        session.repo_name.clone().into(),
+                        session.provider.clone().into(),
+                        session.model.clone().into(),
+                        session.system_prompt.clone().into(),
+                        session.created_at.into(),
+                        session.updated_at.into(),
+                    ])?
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_with(&q, args).execute(pool).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> Result<Option<ChatSessionRecord>, Box<dyn Error>> {
+        let oldest = self.oldest()?;
+        let session = match &self.store {
+            ChatStore::Memory(sessions) => sessions.lock().unwrap().get(id).map(|(s, _)| s.clone()),
+            ChatStore::MySql(pool) => {
+                let (q, args) = Query::select()
+                    .columns([
+                        AIChatSessions::Id,
+                        AIChatSessions::OwnerName,
+                        AIChatSessions::RepoName,
+                        AIChatSessions::Provider,
+                        AIChatSessions::Model,
+                        AIChatSessions::SystemPrompt,
+                        AIChatSessions::CreatedAt,
+                        AIChatSessions::UpdatedAt,
+                    ])
+                    .from(AIChatSessions::Table)
+                    .and_where(Expr::col(AIChatSessions::Id).eq(id))
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_as_with::<_, ChatSessionRecord, _>(&q, args).fetch_optional(pool).await?
+            }
+        };
+        Ok(session.filter(|s| s.updated_at >= oldest))
+    }
+
+    async fn messages(&self, id: &str) -> Result<Vec<ChatMessageRecord>, Box<dyn Error>> {
+        match &self.store {
+            ChatStore::Memory(sessions) => Ok(sessions.lock().unwrap().get(id).map(|(_, m)| m.clone()).unwrap_or_default()),
+            ChatStore::MySql(pool) => {
+                let (q, args) = Query::select()
+                    .columns([AIChatMessages::Role, AIChatMessages::Content, AIChatMessages::CreatedAt])
+                    .from(AIChatMessages::Table)
+                    .and_where(Expr::col(AIChatMessages::SessionId).eq(id))
+                    .order_by(AIChatMessages::Id, Order::Asc)
+                    .build_sqlx(MysqlQueryBuilder);
+                Ok(sqlx::query_as_with::<_, ChatMessageRecord, _>(&q, args).fetch_all(pool).await?)
+            }
+        }
+    }
+
+    async fn append(&self, id: &str, updated_at: NaiveDateTime, messages: &[ChatMessageRecord]) -> Result<bool, Box<dyn Error>> {
+        let now = Utc::now().naive_utc();
+        match &self.store {
+            ChatStore::Memory(sessions) => {
+                let mut sessions = sessions.lock().unwrap();
+                let Some((session, stored)) = sessions.get_mut(id).filter(|(s, _)| s.updated_at == updated_at) else {
+                    return Ok(false);
+                };
+                stored.extend_from_slice(messages);
+                session.updated_at = now;
+            }
+            ChatStore::MySql(pool) => {
+                // Claiming the session first makes a concurrent append of another question fail
+                // rather than interleave with this one.
+                let mut tx = pool.begin().await?;
+                let (q, args) = Query::update()
+                    .table(AIChatSessions::Table)
+                    .value(AIChatSessions::UpdatedAt, now)
+                    .and_where(Expr::col(AIChatSessions::Id).eq(id))
+                    .and_where(Expr::col(AIChatSessions::UpdatedAt).eq(updated_at))
+                    .build_sqlx(MysqlQueryBuilder);
+                if sqlx::query_with(&q, args).execute(&mut *tx).await?.rows_affected() == 0 {
+                    return Ok(false);
+                }
+
+                let mut insert = Query::insert();
+                insert
+                    .into_table(AIChatMessages::Table)
+                    .columns([AIChatMessages::SessionId, AIChatMessages::Role, AIChatMessages::Content, AIChatMessages::CreatedAt]);
+                for message in messages {
+                    insert.values([
+                        id.into(),
+                        message.role.clone().into(),
+                        message.content.clone().into(),
+                        message.created_at.into(),
+                    ])?;
+                }
+                let (q, args) = insert.build_sqlx(MysqlQueryBuilder);
+                sqlx::query_with(&q, args).execute(&mut *tx).await?;
+                tx.commit().await?;
+            }
+        }
+        Ok(true)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool, Box<dyn Error>> {
+        match &self.store {
+            ChatStore::Memory(sessions) => Ok(sessions.lock().unwrap().remove(id).is_some()),
+            ChatStore::MySql(pool) => {
+                let (q, args) = Query::delete()
+                    .from_table(AIChatSessions::Table)
+                    .and_where(Expr::col(AIChatSessions::Id).eq(id))
+                    .build_sqlx(MysqlQueryBuilder);
+                Ok(sqlx::query_with(&q, args).execute(pool).await?.rows_affected() > 0)
+            }
+        }
+    }
+
+    fn expires_at(&self, updated_at: NaiveDateTime) -> NaiveDateTime {
+        chrono::Duration::from_std(self.ttl).ok()
+            .and_then(|ttl| updated_at.checked_add_signed(ttl))
+            .unwrap_or(NaiveDateTime::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use chrono::Utc;
+    use crate::internals::ai::models::entity::{ChatMessageRecord, ChatSessionRecord};
+    use crate::internals::ai::repositories::chat_repository::{AIChatRepository, ChatRepository, ChatStore};
+
+    fn session(id: &str) -> ChatSessionRecord {
+        let now = Utc::now().naive_utc();
+        ChatSessionRecord {
+            id: id.to_string(),
+            owner_name: "octo-org".to_string(),
+            repo_name: "octo-repo".to_string(),
+            provider: None,
+            model: "openai/gpt-4o-mini".to_string(),
+            system_prompt: "You answer questions.".to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn message(role: &str, content: &str) -> ChatMessageRecord {
+        ChatMessageRecord {
+            role: role.to_string(),
+            content: content.to_string(),
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_sessions_keep_their_messages_until_they_expire() {
+        let store = ChatStore::Memory(Default::default());
+        let repository = AIChatRepository::new(store.clone(), Duration::from_millis(200));
+        let created = session("a");
+        repository.create(&created).await.unwrap();
+        assert!(repository.append("a", created.updated_at, &[message("user", "How do I test?"), message("assistant", "cargo test")]).await.unwrap());
+        let updated_at = repository.find("a").await.unwrap().unwrap().updated_at;
+        assert!(repository.append("a", updated_at, &[message("user", "On Windows?")]).await.unwrap());
+        // Asked about since, so this answer was given without the latest question.
+        assert!(!repository.append("a", updated_at, &[message("user", "On Linux?")]).await.unwrap());
+        let contents: Vec<String> = repository.messages("a").await.unwrap().into_iter().map(|m| m.content).collect();
+        assert_eq!(contents, vec!["How do I test?", "cargo test", "On Windows?"]);
+
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(repository.find("a").await.unwrap().is_none());
+        // Starting another session clears out the expired one.
+        repository.create(&session("b")).await.unwrap();
+        let ChatStore::Memory(sessions) = &store else { unreachable!() };
+        assert_eq!(sessions.lock().unwrap().keys().collect::<Vec<_>>(), vec!["b"]);
+
+        assert!(repository.find("b").await.unwrap().is_some());
+        assert!(repository.delete("b").await.unwrap());
+        assert!(!repository.delete("b").await.unwrap());
+    }
+}
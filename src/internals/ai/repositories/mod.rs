@@ -0,0 +1,2 @@
+pub mod answer_repository;
+pub mod chat_repository;
\ No newline at end of file
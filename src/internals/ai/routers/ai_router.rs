@@ -1,8 +1,23 @@
-use actix_web::{web, HttpResponse, Responder, Scope};
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse, Responder};
+use futures::channel::mpsc;
+use futures::{stream, FutureExt, StreamExt};
+use serde::Serialize;
 use crate::internals::ai::controllers::ai_controller::AIController;
+use crate::internals::ai::models::dto::{AIInquiryQueries, AIInquiryResponse, AnswerDelta, AnswerInvalidation, ChatQuestion, ChatReply, ChatSession, ChatSessionRequest, PromptPreview, PromptPreviewQueries};
+use crate::internals::openapi::documented_scope::DocumentedScope;
+use crate::pkg::ai::model::model::ProviderError;
+use crate::pkg::ai::prompt::template::PromptTemplate;
+use crate::pkg::utils::validation::validation::ValidationErrors;
 
 pub trait AIRouter {
-    fn ai_scope(&self) -> Scope;
+    fn ai_scope(&self) -> DocumentedScope;
+    /// Prompt template and stored answer administration, under `/admin/ai`.
+    fn ai_admin_scope(&self) -> DocumentedScope;
 }
 
 #[derive(Clone)]
@@ -14,23 +29,256 @@ impl<C: AIController> OpenAIRouter<C> {
     pub fn new(controller: C) -> Self {
         Self { controller }
     }
+}
+
+#[utoipa::path(
+    get,
+    path = "/ai/inquiry/how-to-contribute/{owner_name}/{repo_name}",
+    params(
+        ("owner_name" = String, Path, description = "Repository owner"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        AIInquiryQueries,
+    ),
+    responses(
+        (status = 200, description = "Structured contribution guidance generated from the README, or stored from an earlier request about the same content", body = AIInquiryResponse),
+        (status = 422, description = "Requested provider is not configured, or no such template version or skill level", body = ValidationErrors),
+        (status = 502, description = "The AI provider answered with an error", body = ProviderError),
+        (status = 500, description = "Upstream or internal error, or an answer that still did not follow the schema after a retry", body = String),
+    )
+)]
+async fn ask_how_to_contribute<C: AIController>(router: web::Data<OpenAIRouter<C>>, path: web::Path<(String, String)>, query: web::Query<AIInquiryQueries>) -> impl Responder {
+    let (owner_name, repo_name) = path.into_inner();
+    match router.controller.ask_how_to_contribute(&owner_name, &repo_name, query.into_inner()).await {
+        Ok(inquiry_res) => HttpResponse::Ok().json(inquiry_res),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Relays the answer as server-sent events: a `delta` per piece of text, then one `done`
+/// with the whole response, or `error` with what the JSON endpoint would have answered.
+/// When the client goes away, the stream is dropped and the request to the provider with it.
+#[utoipa::path(
+    get,
+    path = "/ai/inquiry/how-to-contribute/{owner_name}/{repo_name}/stream",
+    params(
+        ("owner_name" = String, Path, description = "Repository owner"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        AIInquiryQueries,
+    ),
+    responses(
+        (status = 200, description = "Server-sent events: `delta` events with pieces of the answer as the model writes it (none for stored answers), \
+            then either `done` with the AIInquiryResponse, or `error` with what the JSON endpoint would answer with (ValidationErrors, ProviderError or `{\"message\": ...}`)",
+            body = AnswerDelta, content_type = "text/event-stream"),
+    )
+)]
+async fn ask_how_to_contribute_stream<C: AIController + 'static>(router: web::Data<OpenAIRouter<C>>, path: web::Path<(String, String)>, query: web::Query<AIInquiryQueries>) -> impl Responder {
+    let (owner_name, repo_name) = path.into_inner();
+    let (deltas, received) = mpsc::unbounded();
+    let result = Rc::new(RefCell::new(None));
 
-    async fn ask_how_to_contribute(router: web::Data<OpenAIRouter<C>>, path: web::Path<(String, String)>) -> impl Responder {
-        let (owner_name, repo_name) = path.into_inner();
-        match router.controller.ask_how_to_contribute(&owner_name, &repo_name).await {
-            Ok(inquiry_res) => HttpResponse::Ok().json(inquiry_res),
-            Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    let slot = result.clone();
+    let answer = async move {
+        let res = router.controller.ask_how_to_contribute_stream(&owner_name, &repo_name, query.into_inner(), &deltas).await;
+        *slot.borrow_mut() = Some(res);
+        // `deltas` is dropped here, which ends `received` once it is drained.
+    };
+    // Both sides have to end before the last event, so no delta can follow it.
+    let events = stream::select(
+        received.map(|text| sse_event("delta", &AnswerDelta { text })),
+        answer.into_stream().filter_map(|_| async { None }),
+    ).chain(stream::once(async move {
+        match result.borrow_mut().take() {
+            Some(Ok(res)) => sse_event("done", &res),
+            Some(Err(e)) => error_event(e),
+            None => error_event("the answer ended without a result".into()),
         }
+    }));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .streaming(events.map(Ok::<_, actix_web::Error>))
+}
+
+#[utoipa::path(
+    post,
+    path = "/ai/chat/sessions",
+    request_body = ChatSessionRequest,
+    responses(
+        (status = 201, description = "Session started from the repository's README, open issues, contribution guidelines and file tree", body = ChatSession),
+        (status = 422, description = "Requested provider is not configured, no such skill level, or the repository has no README", body = ValidationErrors),
+        (status = 500, description = "Upstream or internal error", body = String),
+    )
+)]
+async fn start_chat<C: AIController>(router: web::Data<OpenAIRouter<C>>, body: web::Json<ChatSessionRequest>) -> impl Responder {
+    match router.controller.start_chat(body.into_inner()).await {
+        Ok(session) => HttpResponse::Created().json(session),
+        Err(e) => error_response(e),
     }
 }
 
-impl<C: AIController + 'static> OpenAIRouter<C> {
-    pub fn ai_scope(&self) -> Scope {
-        web::scope("/ai")
-            .service(
-                web::scope("/inquiry")
-                    .route("/how-to-contribute/{owner_name}/{repo_name}", web::get().to(Self::ask_how_to_contribute))
-            )
+#[utoipa::path(
+    get,
+    path = "/ai/chat/sessions/{session_id}",
+    params(("session_id" = String, Path, description = "ID returned when the session started")),
+    responses(
+        (status = 200, description = "The session with its messages", body = ChatSession),
+        (status = 404, description = "No such session, or it expired", body = String),
+    )
+)]
+async fn chat_session<C: AIController>(router: web::Data<OpenAIRouter<C>>, path: web::Path<String>) -> impl Responder {
+    let session_id = path.into_inner();
+    match router.controller.chat_session(&session_id).await {
+        Ok(Some(session)) => HttpResponse::Ok().json(session),
+        Ok(None) => chat_not_found(&session_id),
+        Err(e) => error_response(e),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/ai/chat/sessions/{session_id}/messages",
+    params(("session_id" = String, Path, description = "ID returned when the session started")),
+    request_body = ChatQuestion,
+    responses(
+        (status = 200, description = "The answer, given the earlier messages that fit the prompt budget; both are added to the session", body = ChatReply),
+        (status = 404, description = "No such session, or it expired", body = String),
+        (status = 422, description = "The question is empty, or another was answered at the same time", body = ValidationErrors),
+        (status = 502, description = "The AI provider answered with an error", body = ProviderError),
+        (status = 500, description = "Upstream or internal error", body = String),
+    )
+)]
+async fn ask_chat<C: AIController>(router: web::Data<OpenAIRouter<C>>, path: web::Path<String>, body: web::Json<ChatQuestion>) -> impl Responder {
+    let session_id = path.into_inner();
+    match router.controller.ask_chat(&session_id, body.into_inner()).await {
+        Ok(Some(reply)) => HttpResponse::Ok().json(reply),
+        Ok(None) => chat_not_found(&session_id),
+        Err(e) => error_response(e),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/ai/chat/sessions/{session_id}",
+    params(("session_id" = String, Path, description = "ID returned when the session started")),
+    responses(
+        (status = 204, description = "The session and its messages were dropped"),
+        (status = 404, description = "No such session, or it expired", body = String),
+    )
+)]
+async fn end_chat<C: AIController>(router: web::Data<OpenAIRouter<C>>, path: web::Path<String>) -> impl Responder {
+    let session_id = path.into_inner();
+    match router.controller.end_chat(&session_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => chat_not_found(&session_id),
+        Err(e) => error_response(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/ai/prompts",
+    responses(
+        (status = 200, description = "Every loaded prompt template, by name and version", body = [PromptTemplate]),
+        (status = 401, description = "Missing or wrong admin token", body = String),
+    )
+)]
+async fn prompt_templates<C: AIController>(router: web::Data<OpenAIRouter<C>>) -> impl Responder {
+    HttpResponse::Ok().json(router.controller.prompt_templates())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/ai/prompts/{name}/{version}/preview",
+    params(
+        ("name" = String, Path, description = "Template name"),
+        ("version" = u32, Path, description = "Template version"),
+        PromptPreviewQueries,
+    ),
+    responses(
+        (status = 200, description = "The template rendered against a sample or the given repository, without asking the model", body = PromptPreview),
+        (status = 401, description = "Missing or wrong admin token", body = String),
+        (status = 404, description = "No such template version", body = String),
+        (status = 422, description = "Invalid variable value, unknown provider, or a repository without a README", body = ValidationErrors),
+        (status = 500, description = "Upstream or internal error", body = String),
+    )
+)]
+async fn preview_prompt<C: AIController>(router: web::Data<OpenAIRouter<C>>, path: web::Path<(String, u32)>, query: web::Query<PromptPreviewQueries>) -> impl Responder {
+    let (name, version) = path.into_inner();
+    match router.controller.preview_prompt(&name, version, query.into_inner()).await {
+        Ok(Some(preview)) => HttpResponse::Ok().json(preview),
+        Ok(None) => HttpResponse::NotFound().body(format!("No prompt template {} v{}", name, version)),
+        Err(e) => error_response(e),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/admin/ai/answers/{owner_name}/{repo_name}",
+    params(
+        ("owner_name" = String, Path, description = "Repository owner"),
+        ("repo_name" = String, Path, description = "Repository name"),
+    ),
+    responses(
+        (status = 200, description = "Stored answers about the repository were dropped, e.g. after its README or issues changed", body = AnswerInvalidation),
+        (status = 401, description = "Missing or wrong admin token", body = String),
+        (status = 500, description = "The answer store failed", body = String),
+    )
+)]
+async fn invalidate_answers<C: AIController>(router: web::Data<OpenAIRouter<C>>, path: web::Path<(String, String)>) -> impl Responder {
+    let (owner_name, repo_name) = path.into_inner();
+    match router.controller.invalidate_answers(&owner_name, &repo_name).await {
+        Ok(invalidation) => HttpResponse::Ok().json(invalidation),
+        Err(e) => error_response(e),
+    }
+}
+
+fn error_response(e: Box<dyn Error>) -> HttpResponse {
+    if let Some(errors) = e.downcast_ref::<ValidationErrors>() {
+        return HttpResponse::UnprocessableEntity().json(errors);
+    }
+    match e.downcast_ref::<ProviderError>() {
+        Some(provider_error) => HttpResponse::BadGateway().json(provider_error),
+        None => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+fn chat_not_found(session_id: &str) -> HttpResponse {
+    HttpResponse::NotFound().body(format!("No chat session {}, or it expired", session_id))
+}
+
+fn sse_event(event: &str, data: &impl Serialize) -> Bytes {
+    // serde_json writes no raw newlines, so the data always fits on its one `data:` line.
+    let data = serde_json::to_string(data).unwrap_or_else(|e| serde_json::json!({ "message": format!("Error: {}", e) }).to_string());
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event, data))
+}
+
+/// `error_response` as an event, with other errors as `{"message": ...}`.
+fn error_event(e: Box<dyn Error>) -> Bytes {
+    if let Some(errors) = e.downcast_ref::<ValidationErrors>() {
+        return sse_event("error", errors);
+    }
+    match e.downcast_ref::<ProviderError>() {
+        Some(provider_error) => sse_event("error", provider_error),
+        None => sse_event("error", &serde_json::json!({ "message": format!("Error: {}", e) })),
+    }
+}
+
+impl<C: AIController + 'static> AIRouter for OpenAIRouter<C> {
+    fn ai_scope(&self) -> DocumentedScope {
+        DocumentedScope::new("/ai", "ai")
+            .route(__path_ask_how_to_contribute, ask_how_to_contribute::<C>)
+            .route(__path_ask_how_to_contribute_stream, ask_how_to_contribute_stream::<C>)
+            .route(__path_start_chat, start_chat::<C>)
+            .route(__path_chat_session, chat_session::<C>)
+            .route(__path_end_chat, end_chat::<C>)
+            .route(__path_ask_chat, ask_chat::<C>)
+    }
+
+    fn ai_admin_scope(&self) -> DocumentedScope {
+        DocumentedScope::new("/admin/ai", "admin")
+            .route(__path_prompt_templates, prompt_templates::<C>)
+            .route(__path_preview_prompt, preview_prompt::<C>)
+            .route(__path_invalidate_answers, invalidate_answers::<C>)
+    }
+}
\ No newline at end of file
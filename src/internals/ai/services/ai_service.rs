@@ -1,10 +1,31 @@
 use std::error::Error;
-use crate::internals::ai::models::dto::AIInquiryResponse;
-use crate::internals::ai::models::dto::Status::StatusCode;
+use futures::channel::mpsc::UnboundedSender;
+use crate::internals::ai::models::dto::{AIInquiryResponse, ContributionGuide};
+use crate::internals::ai::models::dto::Status::Answered;
+use crate::pkg::ai::budget::budget::{Prompt, PromptReport, RenderedPrompt};
 use crate::pkg::ai::client::client::GenAIClient;
+use crate::pkg::ai::model::model::{AIProvider, Completion, InquiryRequest, ResponseSchema, Usage};
+use crate::pkg::ai::model::openai::Message;
+
+/// Percent of the prompt budget a chat's repository context may take; the conversation gets the rest.
+const CHAT_CONTEXT_PERCENT: usize = 60;
 
 pub trait AIService {
-    async fn inquiry(&self, content: String) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    /// `prompt` as `inquiry` would send it to `provider`, asking for a `ContributionGuide`.
+    fn render(&self, provider: Option<AIProvider>, prompt: Prompt) -> Result<(RenderedPrompt, PromptReport), Box<dyn Error>>;
+    /// `provider/model` that `inquiry` would ask.
+    fn model(&self, provider: Option<AIProvider>) -> Result<String, Box<dyn Error>>;
+
+    /// Asks `provider`, or the default provider when `None`, for a `ContributionGuide`, with
+    /// `prompt` fitted into the provider's token budget. With `deltas`, the text of the answer
+    /// is sent there while it is generated.
+    async fn inquiry(&self, provider: Option<AIProvider>, prompt: Prompt, deltas: Option<&UnboundedSender<String>>) -> Result<AIInquiryResponse, Box<dyn Error>>;
+
+    /// `prompt` fitted into part of `provider`'s budget, as the system message a chat starts from.
+    fn render_chat(&self, provider: Option<AIProvider>, prompt: Prompt) -> Result<(String, PromptReport), Box<dyn Error>>;
+    /// Asks `question` in a chat started from `system`, after as much of `history` as fits the
+    /// budget, latest turns first. Also returns how many earlier messages were left out.
+    async fn chat(&self, provider: Option<AIProvider>, system: &str, history: Vec<Message>, question: &str) -> Result<(Completion, usize), Box<dyn Error>>;
 }
 
 #[derive(Clone)]
@@ -21,11 +42,183 @@ impl<C: GenAIClient> OpenAIService<C> {
 }
 
 impl<C: GenAIClient> AIService for OpenAIService<C> {
-    async fn inquiry(&self, content: String) -> Result<AIInquiryResponse, Box<dyn Error>> {
-        let gpt_res = self.client.inquire(content).await?;
+    fn render(&self, provider: Option<AIProvider>, prompt: Prompt) -> Result<(RenderedPrompt, PromptReport), Box<dyn Error>> {
+        // Also spelled out in the prompt for servers that ignore the response format.
+        let prompt = Prompt {
+            instructions: format!("{}\n\nAnswer with a JSON object that follows this JSON Schema:\n{}", prompt.instructions, ContributionGuide::schema()),
+            ..prompt
+        };
+        let (prompt, report) = self.client.prompt_budget(provider)?.fit(prompt);
+        let omitted: Vec<String> = report.sections.iter()
+            .filter(|s| !s.omitted.is_empty())
+            .map(|s| format!("{} ({} of {} tokens kept)", s.name, s.tokens, s.original_tokens))
+            .collect();
+        if !omitted.is_empty() {
+            log::info!("Shortened prompt sections to fit {} tokens: {}", report.budget_tokens, omitted.join(", "));
+        }
+        Ok((prompt, report))
+    }
+
+    fn model(&self, provider: Option<AIProvider>) -> Result<String, Box<dyn Error>> {
+        let (provider, model) = self.client.model(provider)?;
+        Ok(format!("{}/{}", provider, model))
+    }
+
+    /// Models do not always follow the schema, even when the API enforces it, so an answer that
+    /// fails validation is sent back once with the problems found, refitted so the context makes
+    /// room for them. Only the first answer is streamed; the corrected one arrives with the result.
+    async fn inquiry(&self, provider: Option<AIProvider>, prompt: Prompt, deltas: Option<&UnboundedSender<String>>) -> Result<AIInquiryResponse, Box<dyn Error>> {
+        let schema = ContributionGuide::schema();
+        let (rendered, report) = self.render(provider, prompt.clone())?;
+        let request = |content: String| InquiryRequest {
+            provider,
+            system: rendered.system.clone(),
+            history: vec![],
+            content,
+            response_schema: Some(ResponseSchema {
+                name: ContributionGuide::SCHEMA_NAME.to_string(),
+                schema: schema.clone(),
+            }),
+        };
+
+        let first = match deltas {
+            Some(deltas) => self.client.inquire_stream(request(rendered.user.clone()), deltas).await?,
+            None => self.client.inquire(request(rendered.user.clone())).await?,
+        };
+        let (guide, completion, usage) = match ContributionGuide::parse(&first.content) {
+            Ok(guide) => (guide, first.clone(), first.usage),
+            Err(problems) => {
+                let repair = Prompt {
+                    instructions: format!(
+                        "{}\n\nYour previous answer was:\n{}\n\nIt does not follow the schema: {}\n\
+                        Answer again with the corrected JSON object only.",
+                        prompt.instructions, first.content, problems
+                    ),
+                    ..prompt
+                };
+                let (repair, _) = self.render(provider, repair)?;
+                let second = self.client.inquire(request(repair.user)).await?;
+                let guide = ContributionGuide::parse(&second.content)
+                    .map_err(|problems| format!("answer does not follow the schema after a retry: {}", problems))?;
+                let usage = Usage {
+                    prompt_tokens: first.usage.prompt_tokens + second.usage.prompt_tokens,
+                    completion_tokens: first.usage.completion_tokens + second.usage.completion_tokens,
+                };
+                (guide, second, usage)
+            }
+        };
+
         Ok(AIInquiryResponse {
-            status: StatusCode(gpt_res.status),
-            text: gpt_res.text,
+            status: Answered,
+            guide: Some(guide),
+            model: Some(completion.model),
+            finish_reason: completion.finish_reason,
+            usage: Some(usage),
+            context: Some(report),
+            template: None,
+            cached: false,
         })
     }
-}
\ No newline at end of file
+
+    fn render_chat(&self, provider: Option<AIProvider>, prompt: Prompt) -> Result<(String, PromptReport), Box<dyn Error>> {
+        let (prompt, report) = self.client.prompt_budget(provider)?.share(CHAT_CONTEXT_PERCENT).fit(prompt);
+        let system = prompt.system.into_iter().chain([prompt.user]).collect::<Vec<_>>().join("\n\n");
+        Ok((system, report))
+    }
+
+    async fn chat(&self, provider: Option<AIProvider>, system: &str, history: Vec<Message>, question: &str) -> Result<(Completion, usize), Box<dyn Error>> {
+        let (history, omitted) = self.client.prompt_budget(provider)?.fit_history(&[system, question], history);
+        if omitted > 0 {
+            log::info!("Left the first {} messages of a chat out of the prompt", omitted);
+        }
+        let completion = self.client.inquire(InquiryRequest {
+            provider,
+            system: Some(system.to_string()),
+            history,
+            content: question.to_string(),
+            response_schema: None,
+        }).await?;
+        Ok((completion, omitted))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use futures::channel::mpsc::UnboundedSender;
+    use crate::internals::ai::services::ai_service::{AIService, OpenAIService};
+    use crate::pkg::ai::budget::budget::{Prompt, PromptBudget, PromptSection, SectionShape};
+    use crate::pkg::ai::budget::tokenizer::Tokenizer;
+    use crate::pkg::ai::client::client::GenAIClient;
+    use crate::pkg::ai::model::model::{AIProvider, Completion, InquiryRequest, Usage};
+    use crate::pkg::ai::stub::stub_server::DEFAULT_ANSWER;
+
+    const BUDGET: usize = 2000;
+
+    #[derive(Clone)]
+    struct MockClient {
+        answers: Arc<Mutex<Vec<String>>>,
+        requests: Arc<Mutex<Vec<InquiryRequest>>>,
+    }
+
+    impl GenAIClient for MockClient {
+        fn provider(&self) -> AIProvider {
+            AIProvider::OpenAI
+        }
+
+        async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>> {
+            self.requests.lock().unwrap().push(req);
+            Ok(Completion {
+                content: self.answers.lock().unwrap().remove(0),
+                finish_reason: Some("stop".to_string()),
+                model: "gpt-4o-mini".to_string(),
+                usage: Usage::default(),
+            })
+        }
+
+        async fn inquire_stream(&self, req: InquiryRequest, _deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>> {
+            self.inquire(req).await
+        }
+
+        fn prompt_budget(&self, _provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>> {
+            Ok(PromptBudget::new(tokenizer(), BUDGET))
+        }
+
+        fn model(&self, _provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>> {
+            Ok((AIProvider::OpenAI, "gpt-4o-mini".to_string()))
+        }
+    }
+
+    fn tokenizer() -> Tokenizer {
+        Tokenizer::for_model(AIProvider::OpenAI, "gpt-4o-mini")
+    }
+
+    #[actix_web::test]
+    async fn test_repair_fits_a_prompt_already_at_the_budget() {
+        let invalid = DEFAULT_ANSWER.replace("\"easy\"", "\"trivial\"");
+        let client = MockClient {
+            answers: Arc::new(Mutex::new(vec![invalid, DEFAULT_ANSWER.to_string()])),
+            requests: Arc::new(Mutex::new(vec![])),
+        };
+        let readme = (1..=200).map(|i| format!("# Section {}\n\nRun `cargo test` before sending a change.\n", i)).collect::<String>();
+        let prompt = Prompt {
+            system: Some("You help newcomers contribute.".to_string()),
+            instructions: "What do I have to study to contribute?".to_string(),
+            sections: vec![PromptSection { name: "readme".to_string(), text: readme, shape: SectionShape::Document, weight: 1 }],
+        };
+
+        let response = OpenAIService::new(client.clone()).inquiry(None, prompt, None).await.unwrap();
+        assert_eq!(response.guide.unwrap().recommended_issues[0].url, "https://github.com/octo-org/octo-repo/issues/12");
+
+        let tokenizer = tokenizer();
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in requests.iter() {
+            let tokens = tokenizer.count(request.system.as_deref().unwrap()) + tokenizer.count(&request.content);
+            assert!(tokens <= BUDGET, "{} tokens", tokens);
+        }
+        assert!(tokenizer.count(&requests[0].content) > BUDGET * 9 / 10);
+        assert!(requests[1].content.contains("\"trivial\""));
+        assert!(requests[1].content.contains("It does not follow the schema"));
+    }
+}
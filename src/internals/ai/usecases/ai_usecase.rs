@@ -1,46 +1,430 @@
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use crate::internals::ai::models::dto::AIInquiryResponse;
+use std::sync::Arc;
+use chrono::Utc;
+use futures::channel::mpsc::UnboundedSender;
+use crate::internals::ai::models::dto::{AIInquiryQueries, AIInquiryResponse, AnswerInvalidation, ChatMessage, ChatQuestion, ChatReply, ChatSession, ChatSessionRequest, PromptPreview, PromptPreviewQueries};
 use crate::internals::ai::models::dto::Status::ReadmeNotFound;
+use crate::internals::ai::models::entity::{digest, AnswerKey, ChatMessageRecord, ChatSessionRecord};
+use crate::internals::ai::repositories::answer_repository::AnswerRepository;
+use crate::internals::ai::repositories::chat_repository::ChatRepository;
 use crate::internals::ai::services::ai_service::AIService;
+use crate::internals::github::models::dto::{FileTree, Issue};
+use crate::internals::github::models::entity::{IssueState, SearchIssuesRequest, SearchIssuesSortKey, SortOrder};
 use crate::internals::github::services::repository_service::RepositoryService;
+use crate::pkg::ai::budget::budget::PromptReport;
+use crate::pkg::ai::model::model::AIProvider;
+use crate::pkg::ai::model::openai::{Message, Role};
+use crate::pkg::ai::prompt::template::{PromptTemplate, PromptTemplates};
+use crate::pkg::utils::keyed_lock::keyed_lock::KeyedLock;
+use crate::pkg::utils::validation::validation::ValidationErrors;
+
+/// Template the how-to-contribute question is asked with.
+pub const HOW_TO_CONTRIBUTE_TEMPLATE: &str = "how_to_contribute";
+/// Template chat sessions start from, in its latest version.
+pub const CHAT_TEMPLATE: &str = "contribution_chat";
+/// Templates shipped with the server; `ai.prompts_dir` can add more versions.
+pub const BUILTIN_TEMPLATES: &[&str] = &[
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/prompts/how_to_contribute/v1.toml")),
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/prompts/contribution_chat/v1.toml")),
+];
+
+/// Labels of the issues offered to the model, each searched on its own.
+const ISSUE_LABELS: &[&str] = &["good first issue", "help wanted"];
+/// Issues listed in the prompt, most recently updated first.
+const MAX_PROMPT_ISSUES: usize = 20;
+/// Characters of an issue body quoted in the prompt.
+const ISSUE_BODY_CHARS: usize = 300;
+/// Values that come from the repository itself, as opposed to the request.
+const CONTENT_VARIABLES: &[&str] = &["readme", "open_issues", "contributing", "file_tree"];
 
 pub trait AIUseCase {
-    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    /// Like `ask_how_to_contribute`, sending the answer's text to `deltas` while the model
+    /// writes it. Stored answers are returned without any.
+    async fn ask_how_to_contribute_stream(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries, deltas: &UnboundedSender<String>) -> Result<AIInquiryResponse, Box<dyn Error>>;
+    fn prompt_templates(&self) -> Vec<PromptTemplate>;
+    /// `None` when there is no such template.
+    async fn preview_prompt(&self, name: &str, version: u32, query: PromptPreviewQueries) -> Result<Option<PromptPreview>, Box<dyn Error>>;
+    async fn invalidate_answers(&self, owner_name: &str, repository_name: &str) -> Result<AnswerInvalidation, Box<dyn Error>>;
+    /// Starts a chat about the repository from the same context as `ask_how_to_contribute`.
+    async fn start_chat(&self, req: ChatSessionRequest) -> Result<ChatSession, Box<dyn Error>>;
+    /// `None` when there is no such session or it expired, as for the other chat methods.
+    async fn chat_session(&self, session_id: &str) -> Result<Option<ChatSession>, Box<dyn Error>>;
+    async fn ask_chat(&self, session_id: &str, question: ChatQuestion) -> Result<Option<ChatReply>, Box<dyn Error>>;
+    /// Whether there was such a session.
+    async fn end_chat(&self, session_id: &str) -> Result<bool, Box<dyn Error>>;
 }
 
 #[derive(Clone)]
-pub struct OpenAIUseCase<AS: AIService, RS: RepositoryService> {
+pub struct OpenAIUseCase<AS: AIService, RS: RepositoryService, AR: AnswerRepository, CR: ChatRepository> {
     ai_service: AS,
     repository_service: RS,
+    answer_repository: AR,
+    chat_repository: CR,
+    templates: Arc<PromptTemplates>,
+    /// Shared between clones so that questions in one session are answered one at a time
+    chat_turns: Arc<KeyedLock<String>>,
+}
+
+/// What a prompt is filled with.
+struct RepositoryContext {
+    owner_name: String,
+    repository_name: String,
+    readme: String,
+    issues: Vec<Issue>,
+    contributing: Option<String>,
+    tree: Option<FileTree>,
 }
 
-impl<AS: AIService, RS: RepositoryService> OpenAIUseCase<AS, RS> {
-    pub fn new(ais: AS, rs: RS) -> Self {
+impl RepositoryContext {
+    /// Stands in for a real repository when previewing templates.
+    fn sample() -> Self {
+        let issue = |number: u32, title: &str, body: &str| Issue {
+            html_url: format!("https://github.com/octo-org/octo-repo/issues/{}", number),
+            title: title.to_string(),
+            body: Some(body.to_string()),
+            body_html: None,
+        };
         Self {
-            ai_service: ais,
-            repository_service: rs
+            owner_name: "octo-org".to_string(),
+            repository_name: "octo-repo".to_string(),
+            readme: "# octo-repo\n\nA command line tool that tidies up Markdown files.\n\n\
+                ## Installation\n\n```sh\ncargo install octo-repo\n```\n\n\
+                ## Usage\n\n```sh\nocto-repo fmt README.md\n```".to_string(),
+            issues: vec![
+                issue(12, "Support tables in the formatter", "Tables are left untouched; align their columns."),
+                issue(15, "Typo in --help output", "\"formated\" should be \"formatted\"."),
+            ],
+            contributing: Some("# Contributing\n\nRun `cargo test` before opening a pull request.".to_string()),
+            tree: Some(FileTree {
+                paths: ["Cargo.toml", "README.md", "src/main.rs", "src/format/mod.rs", "src/format/table.rs", "tests/format.rs"]
+                    .map(String::from).to_vec(),
+                truncated: false,
+            }),
+        }
+    }
+
+    fn values(&self, skill_level: Option<String>) -> HashMap<&'static str, String> {
+        let issues: Vec<String> = self.issues.iter()
+            .map(|issue| {
+                let body: String = issue.body.as_deref().unwrap_or_default().chars().take(ISSUE_BODY_CHARS).collect();
+                let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+                match body.is_empty() {
+                    true => format!("- {} {}", issue.html_url, issue.title),
+                    false => format!("- {} {}\n  {}", issue.html_url, issue.title, body),
+                }
+            })
+            .collect();
+        let mut values = HashMap::from([
+            ("owner", self.owner_name.clone()),
+            ("repo", self.repository_name.clone()),
+            ("readme", self.readme.clone()),
+            ("open_issues", issues.join("\n")),
+        ]);
+        if let Some(skill_level) = skill_level {
+            values.insert("skill_level", skill_level);
+        }
+        if let Some(contributing) = &self.contributing {
+            values.insert("contributing", contributing.clone());
         }
+        if let Some(tree) = &self.tree {
+            values.insert("file_tree", tree.summary());
+        }
+        values
     }
 }
 
-impl<AS: AIService, RS: RepositoryService> AIUseCase for OpenAIUseCase<AS, RS> {
-    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str) -> Result<AIInquiryResponse, Box<dyn Error>> {
-        let top_readme = self.repository_service.fetch_top_readme(owner_name, repository_name).await?;
+impl<AS: AIService, RS: RepositoryService, AR: AnswerRepository, CR: ChatRepository> OpenAIUseCase<AS, RS, AR, CR> {
+    pub fn new(ais: AS, rs: RS, ar: AR, cr: CR, templates: PromptTemplates) -> Self {
+        Self {
+            ai_service: ais,
+            repository_service: rs,
+            answer_repository: ar,
+            chat_repository: cr,
+            templates: Arc::new(templates),
+            chat_turns: Arc::new(KeyedLock::new()),
+        }
+    }
 
+    /// `None` when the repository has no README to go on.
+    async fn context(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<Option<RepositoryContext>, Box<dyn Error>> {
+        let top_readme = self.repository_service.fetch_top_readme(host, owner_name, repository_name).await?;
         if !top_readme.found {
+            return Ok(None);
+        }
+
+        // Only the README is essential; the rest improves the answer but is left out on failure.
+        let (issues, contributing, tree) = futures::join!(
+            self.open_issues(host, owner_name, repository_name),
+            self.repository_service.fetch_contributing(host, owner_name, repository_name),
+            self.repository_service.fetch_file_tree(host, owner_name, repository_name),
+        );
+        let issues = issues.unwrap_or_else(|e| {
+            log::warn!("Could not fetch issues of {}/{} for the prompt: {}", owner_name, repository_name, e);
+            vec![]
+        });
+        let contributing = contributing.map(|c| c.text.filter(|_| c.found)).unwrap_or_else(|e| {
+            log::warn!("Could not fetch the contribution guidelines of {}/{} for the prompt: {}", owner_name, repository_name, e);
+            None
+        });
+        let tree = tree.map(|t| Some(t).filter(|t| !t.paths.is_empty())).unwrap_or_else(|e| {
+            log::warn!("Could not fetch the file tree of {}/{} for the prompt: {}", owner_name, repository_name, e);
+            None
+        });
+        Ok(Some(RepositoryContext {
+            owner_name: owner_name.to_string(),
+            repository_name: repository_name.to_string(),
+            readme: top_readme.text.unwrap_or_default(),
+            issues,
+            contributing,
+            tree,
+        }))
+    }
+
+    /// Unassigned open issues carrying any of `ISSUE_LABELS`, without duplicates.
+    async fn open_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let mut seen = HashSet::new();
+        let mut issues = vec![];
+        for label in ISSUE_LABELS {
+            let req = SearchIssuesRequest {
+                state: IssueState::Open,
+                assignee: "none".to_string(),
+                labels: vec![label.to_string()],
+                sort_key: SearchIssuesSortKey::Updated,
+                sort_order: SortOrder::Desc,
+            };
+            for issue in self.repository_service.fetch_issues(host, owner_name, repository_name, req).await? {
+                if seen.insert(issue.html_url.clone()) {
+                    issues.push(issue);
+                }
+            }
+        }
+        issues.truncate(MAX_PROMPT_ISSUES);
+        Ok(issues)
+    }
+
+    fn chat_session_response(&self, session: ChatSessionRecord, context: Option<PromptReport>, messages: Vec<ChatMessageRecord>) -> ChatSession {
+        ChatSession {
+            repository: format!("{}/{}", session.owner_name, session.repo_name),
+            model: session.model,
+            created_at: session.created_at.and_utc(),
+            expires_at: self.chat_repository.expires_at(session.updated_at).and_utc(),
+            context,
+            messages: messages.into_iter().map(chat_message).collect(),
+            id: session.id,
+        }
+    }
+
+    async fn ask(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries, deltas: Option<&UnboundedSender<String>>) -> Result<AIInquiryResponse, Box<dyn Error>> {
+        let Some(template) = self.templates.get(HOW_TO_CONTRIBUTE_TEMPLATE, query.template_version) else {
+            let mut errors = ValidationErrors::new();
+            errors.add("template_version", format!("{} has no such version", HOW_TO_CONTRIBUTE_TEMPLATE));
+            return Err(errors.into());
+        };
+        // Resolved first, so answers about the default host are stored under its name.
+        let host = self.repository_service.host(query.host.as_deref())?;
+        let key = AnswerKey {
+            host,
+            owner_name: owner_name.to_lowercase(),
+            repo_name: repository_name.to_lowercase(),
+            template: template.id(),
+            model: self.ai_service.model(query.provider)?,
+            skill_level: query.skill_level.clone(),
+        };
+        let Some(context) = self.context(Some(&key.host), owner_name, repository_name).await? else {
             return Ok(AIInquiryResponse {
                 status: ReadmeNotFound,
-                text: "".to_string(),
+                guide: None,
+                model: None,
+                finish_reason: None,
+                usage: None,
+                context: None,
+                template: None,
+                cached: false,
             })
+        };
+
+        let values = context.values(query.skill_level);
+        let prompt = template.render(&values)?;
+        let content_hash = digest(&CONTENT_VARIABLES.iter().map(|name| values.get(name).map_or("", |v| v.as_str())).collect::<Vec<_>>());
+        // Only an answer drawn from the content just fetched is reused; dropping answers when a
+        // fetch notices a change merely frees their space early. The cache only saves money, so
+        // failures are logged rather than failing the request.
+        if !query.refresh {
+            match self.answer_repository.find(&key, &content_hash).await {
+                Ok(Some(cached)) => return Ok(AIInquiryResponse { cached: true, ..cached }),
+                Ok(None) => {}
+                Err(e) => log::warn!("Could not look up a stored answer about {}/{}: {}", owner_name, repository_name, e),
+            }
         }
 
-        let prompt = format!(
-            "What do I have to study to contribute to {}/{}?\n
-            Which currently-opened issues are good to contribute first and which codes in which files to change?\n
-            Readme of the repository is below\n{:?}",
-            owner_name, repository_name, top_readme.content
-        );
+        let mut response = self.ai_service.inquiry(query.provider, prompt, deltas).await?;
+        // Drop issues the model made up despite the instructions.
+        let known: HashSet<&str> = context.issues.iter().map(|i| i.html_url.as_str()).collect();
+        if let Some(guide) = response.guide.as_mut() {
+            guide.recommended_issues.retain(|issue| known.contains(issue.url.as_str()));
+        }
+        response.template = Some(template.id());
+        if let Err(e) = self.answer_repository.save(&key, &content_hash, &response).await {
+            log::warn!("Could not store the answer about {}/{}: {}", owner_name, repository_name, e);
+        }
+        Ok(response)
+    }
+}
+
+fn chat_message(record: ChatMessageRecord) -> ChatMessage {
+    ChatMessage {
+        role: if record.role == "assistant" { Role::Assistant } else { Role::User },
+        content: record.content,
+        created_at: record.created_at.and_utc(),
+    }
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+    }
+}
+
+impl<AS: AIService, RS: RepositoryService, AR: AnswerRepository, CR: ChatRepository> AIUseCase for OpenAIUseCase<AS, RS, AR, CR> {
+    async fn ask_how_to_contribute(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries) -> Result<AIInquiryResponse, Box<dyn Error>> {
+        self.ask(owner_name, repository_name, query, None).await
+    }
+
+    async fn ask_how_to_contribute_stream(&self, owner_name: &str, repository_name: &str, query: AIInquiryQueries, deltas: &UnboundedSender<String>) -> Result<AIInquiryResponse, Box<dyn Error>> {
+        self.ask(owner_name, repository_name, query, Some(deltas)).await
+    }
+
+    fn prompt_templates(&self) -> Vec<PromptTemplate> {
+        self.templates.list().to_vec()
+    }
+
+    async fn preview_prompt(&self, name: &str, version: u32, query: PromptPreviewQueries) -> Result<Option<PromptPreview>, Box<dyn Error>> {
+        let Some(template) = self.templates.get(name, Some(version)) else {
+            return Ok(None);
+        };
+        let context = match (query.owner.as_deref(), query.repo.as_deref()) {
+            (None, None) => RepositoryContext::sample(),
+            (Some(owner_name), Some(repository_name)) => match self.context(query.host.as_deref(), owner_name, repository_name).await? {
+                Some(context) => context,
+                None => {
+                    let mut errors = ValidationErrors::new();
+                    errors.add("repo", format!("{}/{} has no README", owner_name, repository_name));
+                    return Err(errors.into());
+                }
+            },
+            _ => {
+                let mut errors = ValidationErrors::new();
+                errors.add("repo", "owner and repo go together");
+                return Err(errors.into());
+            }
+        };
+
+        let prompt = template.render(&context.values(query.skill_level))?;
+        let (prompt, report) = self.ai_service.render(query.provider, prompt)?;
+        Ok(Some(PromptPreview {
+            template: template.id(),
+            repository: format!("{}/{}", context.owner_name, context.repository_name),
+            prompt,
+            context: report,
+        }))
+    }
+
+    async fn invalidate_answers(&self, owner_name: &str, repository_name: &str) -> Result<AnswerInvalidation, Box<dyn Error>> {
+        let deleted = self.answer_repository.invalidate(owner_name, repository_name).await?;
+        Ok(AnswerInvalidation { deleted })
+    }
+
+    async fn start_chat(&self, req: ChatSessionRequest) -> Result<ChatSession, Box<dyn Error>> {
+        let Some(template) = self.templates.get(CHAT_TEMPLATE, None) else {
+            return Err(format!("no {} prompt template", CHAT_TEMPLATE).into());
+        };
+        // Fails on providers that are not configured before anything is fetched.
+        let model = self.ai_service.model(req.provider)?;
+        let Some(context) = self.context(req.host.as_deref(), &req.owner, &req.repo).await? else {
+            let mut errors = ValidationErrors::new();
+            errors.add("repo", format!("{}/{} has no README", req.owner, req.repo));
+            return Err(errors.into());
+        };
+
+        let prompt = template.render(&context.values(req.skill_level))?;
+        let (system_prompt, report) = self.ai_service.render_chat(req.provider, prompt)?;
+        let now = Utc::now().naive_utc();
+        let session = ChatSessionRecord {
+            id: ChatSessionRecord::new_id(),
+            owner_name: context.owner_name,
+            repo_name: context.repository_name,
+            provider: req.provider.map(|p| p.to_string()),
+            model,
+            system_prompt,
+            created_at: now,
+            updated_at: now,
+        };
+        self.chat_repository.create(&session).await?;
+        Ok(self.chat_session_response(session, Some(report), vec![]))
+    }
+
+    async fn chat_session(&self, session_id: &str) -> Result<Option<ChatSession>, Box<dyn Error>> {
+        let Some(session) = self.chat_repository.find(session_id).await? else {
+            return Ok(None);
+        };
+        let messages = self.chat_repository.messages(session_id).await?;
+        Ok(Some(self.chat_session_response(session, None, messages)))
+    }
+
+    async fn ask_chat(&self, session_id: &str, question: ChatQuestion) -> Result<Option<ChatReply>, Box<dyn Error>> {
+        if question.content.trim().is_empty() {
+            let mut errors = ValidationErrors::new();
+            errors.add("content", "must not be empty");
+            return Err(errors.into());
+        }
+        // Each answer is given the session's earlier messages, so a question waits for the one
+        // before it.
+        let _turn = self.chat_turns.lock(session_id.to_string()).await;
+        let Some(session) = self.chat_repository.find(session_id).await? else {
+            return Ok(None);
+        };
+        let provider = session.provider.as_deref().map(str::parse::<AIProvider>).transpose()?;
+        let history = self.chat_repository.messages(session_id).await?.into_iter()
+            .map(|m| {
+                let message = chat_message(m);
+                Message { role: message.role, content: message.content }
+            })
+            .collect();
+
+        let asked_at = Utc::now().naive_utc();
+        let (completion, omitted_messages) = self.ai_service.chat(provider, &session.system_prompt, history, &question.content).await?;
+        let answer = ChatMessageRecord {
+            role: role_name(&Role::Assistant).to_string(),
+            content: completion.content,
+            created_at: Utc::now().naive_utc(),
+        };
+        let asked = ChatMessageRecord {
+            role: role_name(&Role::User).to_string(),
+            content: question.content,
+            created_at: asked_at,
+        };
+        // Another instance may have answered a question in the meantime.
+        if !self.chat_repository.append(session_id, session.updated_at, &[asked, answer.clone()]).await? {
+            let mut errors = ValidationErrors::new();
+            errors.add("session_id", "was asked another question meanwhile; ask again");
+            return Err(errors.into());
+        }
+
+        Ok(Some(ChatReply {
+            expires_at: self.chat_repository.expires_at(answer.created_at).and_utc(),
+            message: chat_message(answer),
+            model: completion.model,
+            finish_reason: completion.finish_reason,
+            usage: completion.usage,
+            omitted_messages,
+        }))
+    }
 
-        self.ai_service.inquiry(prompt).await
+    async fn end_chat(&self, session_id: &str) -> Result<bool, Box<dyn Error>> {
+        self.chat_repository.delete(session_id).await
     }
 }
\ No newline at end of file
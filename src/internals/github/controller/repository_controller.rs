@@ -1,13 +1,16 @@
 use std::error::Error;
 use actix_web::{web};
-use crate::internals::github::models::dto::{Issue, ReadmeResponse, Repositories, SearchRepositoriesRequest};
+use crate::internals::github::models::dto::{BatchIssuesRequest, BatchIssuesResult, BatchReadmeResult, BatchReadmesRequest, Issue, OnboardingResponse, ReadmeResponse, Repositories, SearchRepositoriesRequest};
 use crate::internals::github::models::entity::SearchIssuesRequest;
 use crate::internals::github::usecases::repository_usecase::RepositoryUseCase;
 
 pub trait RepositoryController {
     async fn fetch_repositories(&self, req: web::Json<SearchRepositoriesRequest>) -> Result<Repositories, Box<dyn Error>>;
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    async fn fetch_top_readmes(&self, req: web::Json<BatchReadmesRequest>) -> Result<Vec<BatchReadmeResult>, Box<dyn Error>>;
+    async fn fetch_issues_batch(&self, req: web::Json<BatchIssuesRequest>) -> Result<Vec<BatchIssuesResult>, Box<dyn Error>>;
+    async fn fetch_onboarding(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<OnboardingResponse, Box<dyn Error>>;
 }
 
 #[derive(Clone)]
@@ -32,15 +35,31 @@ impl<U: RepositoryUseCase> RepositoryController for GithubRepositoryController<U
             language: req.language.clone(),
             good_first_issues_count: req.good_first_issues_count,
             help_wanted_count: req.help_wanted_count,
-        };
+            forge: req.forge,
+        }.validate()?;
         self.usecase.fetch_repositories(search_req).await
     }
 
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
-        self.usecase.fetch_top_readme(owner_name, repository_name).await
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        self.usecase.fetch_top_readme(host, owner_name, repository_name).await
     }
 
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
-        self.usecase.fetch_issues(owner_name, repository_name, req).await
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        self.usecase.fetch_issues(host, owner_name, repository_name, req).await
+    }
+
+    async fn fetch_top_readmes(&self, req: web::Json<BatchReadmesRequest>) -> Result<Vec<BatchReadmeResult>, Box<dyn Error>> {
+        let repositories = req.validate()?;
+        self.usecase.fetch_top_readmes(req.host.as_deref(), repositories).await
+    }
+
+    async fn fetch_issues_batch(&self, req: web::Json<BatchIssuesRequest>) -> Result<Vec<BatchIssuesResult>, Box<dyn Error>> {
+        let repositories = req.validate()?;
+        let req = req.into_inner();
+        self.usecase.fetch_issues_batch(req.host.as_deref(), repositories, req.query.into()).await
+    }
+
+    async fn fetch_onboarding(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<OnboardingResponse, Box<dyn Error>> {
+        self.usecase.fetch_onboarding(host, owner_name, repository_name).await
     }
 }
\ No newline at end of file
@@ -1,7 +1,21 @@
+use std::collections::BTreeMap;
+use chrono::{Days, Months, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::github::model::language::normalize_language;
 use crate::pkg::github::model::model as pkg_model;
+use crate::pkg::github::model::readme::ReadmeFormat;
+use crate::pkg::markdown::markdown::{render_html, to_plain_text, LinkBase};
+use crate::pkg::markdown::sections::{extract_sections, SectionKind};
+use crate::internals::github::models::entity::SearchIssuesRequestQueries;
+use crate::pkg::utils::validation::validation::ValidationErrors;
 
-#[derive(Deserialize)]
+const MAX_STARS: u32 = 10_000_000;
+const MAX_ISSUES_THRESHOLD: u32 = 1_000;
+const MAX_BATCH_REPOSITORIES: usize = 100;
+
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct SearchRepositoriesRequest {
     pub min_stars: u32,
     pub max_stars: Option<u32>,
@@ -9,9 +23,89 @@ pub struct SearchRepositoriesRequest {
     pub language: String,
     pub good_first_issues_count: u32,
     pub help_wanted_count: u32,
+    /// Only return repositories hosted on this forge; all configured forges when omitted
+    #[serde(default)]
+    pub forge: Option<Forge>,
+}
+
+impl SearchRepositoriesRequest {
+    /// Checks every field and returns the request with `language` resolved to its linguist name
+    /// and `last_pushed` resolved to an ISO date, or all the field errors found.
+    pub fn validate(self) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if self.min_stars > MAX_STARS {
+            errors.add("min_stars", format!("must be at most {}", MAX_STARS));
+        }
+        if let Some(max_stars) = self.max_stars {
+            if max_stars > MAX_STARS {
+                errors.add("max_stars", format!("must be at most {}", MAX_STARS));
+            }
+            if self.min_stars > max_stars {
+                errors.add("max_stars", "must be greater than or equal to min_stars");
+            }
+        }
+        if self.good_first_issues_count > MAX_ISSUES_THRESHOLD {
+            errors.add("good_first_issues_count", format!("must be at most {}", MAX_ISSUES_THRESHOLD));
+        }
+        if self.help_wanted_count > MAX_ISSUES_THRESHOLD {
+            errors.add("help_wanted_count", format!("must be at most {}", MAX_ISSUES_THRESHOLD));
+        }
+
+        let language = match normalize_language(&self.language) {
+            Some(language) => language.to_string(),
+            None => {
+                errors.add("language", format!("unknown language: {:?}", self.language));
+                self.language
+            }
+        };
+
+        let last_pushed = match parse_last_pushed(&self.last_pushed, Utc::now().date_naive()) {
+            Ok(date) => date.format("%Y-%m-%d").to_string(),
+            Err(message) => {
+                errors.add("last_pushed", message);
+                self.last_pushed
+            }
+        };
+
+        errors.into_result(Self {
+            language,
+            last_pushed,
+            ..self
+        })
+    }
 }
 
-#[derive(sqlx::FromRow, Serialize, Debug)]
+/// Parses either an ISO date (`2024-09-07`) or a relative form counted back from `today`
+/// (`30d`, `2w`, `6m`, `1y`).
+pub fn parse_last_pushed(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        if date > today {
+            return Err("must not be in the future".to_string());
+        }
+        return Ok(date);
+    }
+
+    let invalid = || format!("must be an ISO date (YYYY-MM-DD) or a relative period like 30d, 2w, 6m or 1y, got {:?}", value);
+    if value.len() < 2 || !value.is_ascii() {
+        return Err(invalid());
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount: u32 = amount.parse().map_err(|_| invalid())?;
+
+    let date = match unit {
+        "d" => today.checked_sub_days(Days::new(amount as u64)),
+        "w" => today.checked_sub_days(Days::new(amount as u64 * 7)),
+        "m" => today.checked_sub_months(Months::new(amount)),
+        "y" => amount.checked_mul(12).and_then(|months| today.checked_sub_months(Months::new(months))),
+        _ => return Err(invalid()),
+    };
+
+    date.ok_or_else(|| format!("{:?} is too far in the past", value))
+}
+
+#[derive(sqlx::FromRow, Serialize, Debug, ToSchema)]
 pub struct Owner {
     pub name: String,
     pub avatar_url: String,
@@ -26,7 +120,7 @@ impl From<pkg_model::Owner> for Owner {
     }
 }
 
-#[derive(sqlx::FromRow, Debug, Serialize)]
+#[derive(sqlx::FromRow, Debug, Serialize, ToSchema)]
 pub struct Repository {
     pub id: u32,
     pub name: String,
@@ -35,6 +129,8 @@ pub struct Repository {
     pub url: String,
     pub description: String,
     pub owner: Owner,
+    pub host: String,
+    pub forge: Forge,
 }
 
 impl From<pkg_model::Repository> for Repository {
@@ -47,11 +143,13 @@ impl From<pkg_model::Repository> for Repository {
             description: value.description,
             url: value.html_url,
             owner: value.owner.into(),
+            host: value.host,
+            forge: value.forge,
         }
     }
 }
 
-#[derive(sqlx::FromRow, Serialize, Debug)]
+#[derive(sqlx::FromRow, Serialize, Debug, ToSchema)]
 pub struct Repositories {
     pub total_count: u32,
     pub items: Vec<Repository>,
@@ -66,34 +164,341 @@ impl From<pkg_model::Repositories> for Repositories {
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Clone, Serialize, ToSchema)]
 pub struct ReadmeResponse {
     pub found: bool,
     pub content: Option<String>,
+    /// Markup the README is written in, from its file name or, failing that, its content
+    pub format: Option<ReadmeFormat>,
+    /// Sanitized HTML rendering of a Markdown README, with relative links resolved
+    pub html: Option<String>,
+    /// `content` reduced to plain text for prompts
+    #[serde(skip)]
+    pub text: Option<String>,
 }
 
 impl From<pkg_model::ReadmeResponse> for ReadmeResponse {
+    /// Renders Markdown READMEs to HTML and every README to plain text.
     fn from(value: pkg_model::ReadmeResponse) -> Self {
+        let markdown = value.format == Some(ReadmeFormat::Markdown);
+        let html = value.content.as_deref()
+            .filter(|_| markdown)
+            .map(|content| render_html(content, &value.links));
+        let text = value.content.as_deref().map(|content| match markdown {
+            true => to_plain_text(content, &value.links),
+            false => content.trim().to_string(),
+        });
         Self {
             found: value.found,
-            content: value.content
+            content: value.content,
+            format: value.format,
+            html,
+            text,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Issue {
     pub html_url: String,
     pub title: String,
     pub body: Option<String>,
+    /// Sanitized HTML rendering of the Markdown `body`
+    pub body_html: Option<String>,
 }
 
 impl From<pkg_model::Issue> for Issue {
     fn from(value: pkg_model::Issue) -> Self {
+        let body_html = value.body.as_deref().map(|body| render_html(body, &LinkBase::for_page(&value.html_url)));
         Self {
             html_url: value.html_url,
             title: value.title,
-            body: value.body
+            body: value.body,
+            body_html,
+        }
+    }
+}
+
+/// Directory levels listed in a file tree summary.
+const TREE_SUMMARY_DEPTH: usize = 2;
+/// Lines a file tree summary is cut down to.
+const TREE_SUMMARY_LINES: usize = 80;
+
+/// Files on a repository's default branch.
+#[derive(Debug, Clone, Default)]
+pub struct FileTree {
+    pub paths: Vec<String>,
+    /// Whether the forge listed only part of the repository
+    pub truncated: bool,
+}
+
+impl From<pkg_model::FileTree> for FileTree {
+    fn from(value: pkg_model::FileTree) -> Self {
+        Self {
+            paths: value.paths,
+            truncated: value.truncated,
+        }
+    }
+}
+
+impl FileTree {
+    /// Lists the directories `TREE_SUMMARY_DEPTH` levels deep with how many files each holds,
+    /// then the top-level files, which is enough for a model to find its way around.
+    pub fn summary(&self) -> String {
+        let mut directories: BTreeMap<Vec<&str>, usize> = BTreeMap::new();
+        let mut top_level_files = vec![];
+        for path in &self.paths {
+            let parts: Vec<&str> = path.split('/').collect();
+            if parts.len() == 1 {
+                top_level_files.push(path.clone());
+            }
+            for depth in 1..parts.len().min(TREE_SUMMARY_DEPTH + 1) {
+                *directories.entry(parts[..depth].to_vec()).or_default() += 1;
+            }
+        }
+
+        let mut lines: Vec<String> = directories.iter()
+            .map(|(parts, count)| format!(
+                "{}{}/ ({} file{})",
+                "  ".repeat(parts.len() - 1), parts.join("/"), count, if *count == 1 { "" } else { "s" },
+            ))
+            .chain(top_level_files)
+            .collect();
+        if lines.len() > TREE_SUMMARY_LINES {
+            let more = lines.len() - TREE_SUMMARY_LINES;
+            lines.truncate(TREE_SUMMARY_LINES);
+            lines.push(format!("... and {} more", more));
+        }
+        if self.truncated {
+            lines.push("(the repository is too large to list in full)".to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Splits `owner/repo` names into `(owner, repo)` pairs, reporting malformed entries and lists
+/// that are empty or longer than `MAX_BATCH_REPOSITORIES`. The owner may be a nested GitLab
+/// group, as in `group/subgroup/repo`.
+fn parse_repositories(repositories: &[String]) -> Result<Vec<(String, String)>, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    if repositories.is_empty() {
+        errors.add("repositories", "must not be empty");
+    }
+    if repositories.len() > MAX_BATCH_REPOSITORIES {
+        errors.add("repositories", format!("must contain at most {} entries", MAX_BATCH_REPOSITORIES));
+    }
+
+    let mut pairs = Vec::with_capacity(repositories.len());
+    for (i, repository) in repositories.iter().enumerate() {
+        match repository.rsplit_once('/') {
+            Some((owner, name)) if !name.is_empty() && !owner.split('/').any(str::is_empty) => {
+                pairs.push((owner.to_string(), name.to_string()));
+            }
+            _ => errors.add(&format!("repositories[{}]", i), format!("must be owner/repo, got {:?}", repository)),
+        }
+    }
+
+    errors.into_result(pairs)
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BatchReadmesRequest {
+    /// Repositories as `owner/repo`
+    pub repositories: Vec<String>,
+    /// Host the repositories are on, as in `Repository::host`; the first configured host when omitted
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+impl BatchReadmesRequest {
+    pub fn validate(&self) -> Result<Vec<(String, String)>, ValidationErrors> {
+        parse_repositories(&self.repositories)
+    }
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct BatchIssuesRequest {
+    /// Repositories as `owner/repo`
+    pub repositories: Vec<String>,
+    /// Host the repositories are on, as in `Repository::host`; the first configured host when omitted
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Filters applied to every repository, as in the single-repository issues query
+    #[serde(flatten)]
+    pub query: SearchIssuesRequestQueries,
+}
+
+impl BatchIssuesRequest {
+    pub fn validate(&self) -> Result<Vec<(String, String)>, ValidationErrors> {
+        parse_repositories(&self.repositories)
+    }
+}
+
+/// Outcome for one repository of a batch; exactly one of `readme` and `error` is set.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BatchReadmeResult {
+    pub repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readme: Option<ReadmeResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome for one repository of a batch; exactly one of `issues` and `error` is set.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BatchIssuesResult {
+    pub repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issues: Option<Vec<Issue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// File an onboarding section was taken from.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OnboardingSource {
+    Readme,
+    Contributing,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OnboardingSection {
+    pub kind: SectionKind,
+    pub heading: String,
+    pub source: OnboardingSource,
+    /// Markdown of the section, without its heading
+    pub content: String,
+    /// Shell commands from the code blocks of installation, build and test sections
+    pub commands: Vec<String>,
+}
+
+/// What a newcomer needs to get going, gathered from the README and CONTRIBUTING guide.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct OnboardingResponse {
+    pub readme_found: bool,
+    pub contributing_found: bool,
+    /// Sections in document order, README first
+    pub sections: Vec<OnboardingSection>,
+}
+
+impl OnboardingResponse {
+    /// Only Markdown is split into sections; other formats count as found but add none.
+    pub fn new(readme: pkg_model::ReadmeResponse, contributing: pkg_model::ReadmeResponse) -> Self {
+        let mut sections = vec![];
+        for (file, source) in [(&readme, OnboardingSource::Readme), (&contributing, OnboardingSource::Contributing)] {
+            let Some(content) = file.content.as_deref().filter(|_| file.format == Some(ReadmeFormat::Markdown)) else {
+                continue;
+            };
+            sections.extend(extract_sections(content).into_iter().map(|section| OnboardingSection {
+                kind: section.kind,
+                heading: section.heading,
+                source,
+                content: section.content,
+                commands: section.commands,
+            }));
+        }
+
+        Self {
+            readme_found: readme.found,
+            contributing_found: contributing.found,
+            sections,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use crate::internals::github::models::dto::{parse_last_pushed, BatchReadmesRequest, FileTree, SearchRepositoriesRequest};
+
+    fn request() -> SearchRepositoriesRequest {
+        SearchRepositoriesRequest {
+            min_stars: 100,
+            max_stars: Some(1000),
+            last_pushed: "2024-09-07".to_string(),
+            language: "golang".to_string(),
+            good_first_issues_count: 1,
+            help_wanted_count: 1,
+            forge: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_ok_normalizes_language() {
+        let req = request().validate().unwrap();
+
+        assert_eq!(req.language, "Go");
+        assert_eq!(req.last_pushed, "2024-09-07");
+    }
+
+    #[test]
+    fn test_validate_ng_reports_every_field() {
+        let req = SearchRepositoriesRequest {
+            min_stars: 2000,
+            max_stars: Some(1000),
+            last_pushed: "yesterday".to_string(),
+            language: "visual basic 6".to_string(),
+            good_first_issues_count: 5000,
+            help_wanted_count: 1,
+            forge: None,
+        };
+
+        let errors = req.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["max_stars", "good_first_issues_count", "language", "last_pushed"]);
+    }
+
+    #[test]
+    fn test_parse_last_pushed() {
+        let today = NaiveDate::from_ymd_opt(2024, 9, 30).unwrap();
+
+        assert_eq!(parse_last_pushed("2024-09-07", today).unwrap(), NaiveDate::from_ymd_opt(2024, 9, 7).unwrap());
+        assert_eq!(parse_last_pushed("30d", today).unwrap(), NaiveDate::from_ymd_opt(2024, 8, 31).unwrap());
+        assert_eq!(parse_last_pushed("2w", today).unwrap(), NaiveDate::from_ymd_opt(2024, 9, 16).unwrap());
+        assert_eq!(parse_last_pushed("6m", today).unwrap(), NaiveDate::from_ymd_opt(2024, 3, 30).unwrap());
+        assert_eq!(parse_last_pushed("1y", today).unwrap(), NaiveDate::from_ymd_opt(2023, 9, 30).unwrap());
+        assert!(parse_last_pushed("2024-10-01", today).is_err());
+        assert!(parse_last_pushed("2024/09/07", today).is_err());
+        assert!(parse_last_pushed("30x", today).is_err());
+        assert!(parse_last_pushed("", today).is_err());
+    }
+
+    #[test]
+    fn test_batch_repositories_validation() {
+        let req = BatchReadmesRequest {
+            repositories: vec!["octo-org/octo-repo".to_string(), "octo-org".to_string(), "/repo".to_string(), "a//c".to_string()],
+            host: None,
+        };
+
+        let errors = req.validate().unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["repositories[1]", "repositories[2]", "repositories[3]"]);
+
+        let req = BatchReadmesRequest { repositories: vec!["octo-org/octo-repo".to_string(), "gnome/core/mid".to_string()], host: None };
+        assert_eq!(req.validate().unwrap(), vec![
+            ("octo-org".to_string(), "octo-repo".to_string()),
+            ("gnome/core".to_string(), "mid".to_string()),
+        ]);
+        assert!(BatchReadmesRequest { repositories: vec![], host: None }.validate().is_err());
+    }
+
+    #[test]
+    fn test_file_tree_summary() {
+        let tree = FileTree {
+            paths: ["Cargo.toml", "src/main.rs", "src/config/config.rs", "src/config/mod.rs", "src/pkg/ai/client.rs", "src-gen/a.rs"]
+                .iter().map(|p| p.to_string()).collect(),
+            truncated: true,
+        };
+
+        assert_eq!(tree.summary(), "\
+src/ (4 files)
+  src/config/ (2 files)
+  src/pkg/ (1 file)
+src-gen/ (1 file)
+Cargo.toml
+(the repository is too large to list in full)");
+    }
+}
@@ -1,8 +1,10 @@
 use std::fmt::{Debug, Formatter};
 use chrono::NaiveDateTime;
 use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
 use crate::pkg::github::model::model::{IssueState as PkgIssueState, SearchIssuesSortKey as PkgSearchIssuesSortKey, SortOrder as PkgSortOrder};
 
+#[allow(dead_code)]
 #[derive(sqlx::FromRow, Debug)]
 pub struct GithubRepository {
     pub id: u32,
@@ -12,10 +14,14 @@ pub struct GithubRepository {
     pub url: String,
     pub description: String,
     pub readme: String,
+    pub host: String,
+    /// `github`, `gitlab` or `gitea`
+    pub forge: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
 
+#[derive(Clone)]
 pub enum IssueState {
     Open,
     Closed,
@@ -53,6 +59,7 @@ impl Debug for IssueState {
     }
 }
 
+#[derive(Clone)]
 pub enum SearchIssuesSortKey {
     Created,
     Updated,
@@ -91,6 +98,7 @@ impl Debug for SearchIssuesSortKey {
     }
 }
 
+#[derive(Clone)]
 pub enum SortOrder {
     Asc,
     Desc,
@@ -125,6 +133,7 @@ impl Debug for SortOrder {
     }
 }
 
+#[derive(Clone)]
 pub struct SearchIssuesRequest {
     pub state: IssueState,
     pub assignee: String,
@@ -147,7 +156,16 @@ impl From<SearchIssuesRequestQueries> for SearchIssuesRequest {
     }
 }
 
-#[derive(Deserialize, Debug)]
+/// Which configured host a repository is looked up on.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RepositoryHostQueries {
+    /// Host as in `Repository::host`, e.g. `gitlab.com`; the first configured host when omitted
+    pub host: Option<String>,
+}
+
+#[derive(Deserialize, Debug, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
 pub struct SearchIssuesRequestQueries {
     pub state: Option<String>,
     pub assignee: Option<String>,
@@ -1,6 +1,7 @@
 use std::fmt::Write;
 use sea_query::Iden;
 
+#[allow(dead_code)]
 pub enum GHRepo {
     Table,
     ID,
@@ -11,6 +12,8 @@ pub enum GHRepo {
     URL,
     Description,
     Readme,
+    Host,
+    Forge,
     CreatedAt,
     UpdatedAt,
 }
@@ -29,6 +32,8 @@ impl Iden for GHRepo {
                 Self::URL => "url",
                 Self::Description => "description",
                 Self::Readme => "readme",
+                Self::Host => "host",
+                Self::Forge => "forge",
                 Self::CreatedAt => "created_at",
                 Self::UpdatedAt => "updated_at",
             }
@@ -6,6 +6,7 @@ use crate::internals::github::models::dto::{SearchRepositoriesRequest};
 use crate::internals::github::models::entity::GithubRepository;
 use crate::internals::github::models::sea_query::GHRepo;
 
+#[allow(dead_code)]
 pub trait RepositoryRepository {
     async fn find_list(&self, req: SearchRepositoriesRequest) -> Result<Vec<GithubRepository>, Box<dyn Error>>;
     async fn bulk_insert(&self, repos: Vec<GithubRepository>) -> Result<(), Box<dyn Error>>;
@@ -13,6 +14,7 @@ pub trait RepositoryRepository {
 
 #[derive(Clone)]
 pub struct GithubRepositoryRepository {
+    #[allow(dead_code)]
     pool: Pool<MySql>
 }
 
@@ -36,6 +38,8 @@ impl RepositoryRepository for GithubRepositoryRepository {
                 GHRepo::URL,
                 GHRepo::Description,
                 GHRepo::Readme,
+                GHRepo::Host,
+                GHRepo::Forge,
                 GHRepo::CreatedAt,
                 GHRepo::UpdatedAt,
             ])
@@ -43,6 +47,7 @@ impl RepositoryRepository for GithubRepositoryRepository {
             .and_where(Expr::col(GHRepo::Lang).eq(req.language))
             .and_where(Expr::col(GHRepo::Stars).gte(req.min_stars))
             .and_where(Expr::col(GHRepo::Stars).lte(req.max_stars))
+            .and_where_option(req.forge.map(|forge| Expr::col(GHRepo::Forge).eq(forge.as_str())))
             .order_by(GHRepo::Stars, Order::Desc)
             .build_sqlx(MysqlQueryBuilder);
 
@@ -66,6 +71,8 @@ impl RepositoryRepository for GithubRepositoryRepository {
                 GHRepo::URL,
                 GHRepo::Description,
                 GHRepo::Readme,
+                GHRepo::Host,
+                GHRepo::Forge,
                 GHRepo::CreatedAt,
                 GHRepo::UpdatedAt,
             ]).to_owned();
@@ -79,6 +86,8 @@ impl RepositoryRepository for GithubRepositoryRepository {
                 repo.url.into(),
                 repo.description.into(),
                 repo.readme.into(),
+                repo.host.into(),
+                repo.forge.into(),
                 repo.created_at.into(),
                 repo.updated_at.into()
             ])?;
@@ -1,11 +1,13 @@
-use actix_web::{web, HttpResponse, Responder, Scope};
-use log::info;
+use std::error::Error;
+use actix_web::{web, HttpResponse, Responder};
 use crate::internals::github::controller::repository_controller::{RepositoryController};
-use crate::internals::github::models::dto::SearchRepositoriesRequest;
-use crate::internals::github::models::entity::SearchIssuesRequestQueries;
+use crate::internals::github::models::dto::{BatchIssuesRequest, BatchIssuesResult, BatchReadmeResult, BatchReadmesRequest, Issue, OnboardingResponse, ReadmeResponse, Repositories, SearchRepositoriesRequest};
+use crate::internals::github::models::entity::{RepositoryHostQueries, SearchIssuesRequestQueries};
+use crate::internals::openapi::documented_scope::DocumentedScope;
+use crate::pkg::utils::validation::validation::ValidationErrors;
 
 pub trait RepositoryRouter {
-    fn repository_scope(&self) -> Scope;
+    fn repository_scope(&self) -> DocumentedScope;
 }
 
 #[derive(Clone)]
@@ -17,41 +19,140 @@ impl<C: RepositoryController> GithubRepositoryRouter<C> {
     pub fn new(controller: C) -> Self {
         Self { controller }
     }
+}
+
+#[utoipa::path(
+    post,
+    path = "/github/repositories/search-list",
+    request_body = SearchRepositoriesRequest,
+    responses(
+        (status = 200, description = "Repositories matching the search", body = Repositories),
+        (status = 422, description = "Invalid search fields", body = ValidationErrors),
+        (status = 500, description = "Upstream or internal error", body = String),
+    )
+)]
+async fn search_repositories<C: RepositoryController>(router: web::Data<GithubRepositoryRouter<C>>, req: web::Json<SearchRepositoriesRequest>) -> impl Responder {
+    match router.controller.fetch_repositories(req).await {
+        Ok(repositories) => HttpResponse::Ok().json(repositories),
+        Err(e) => error_response(e),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/github/repositories/{owner_name}/{repo_name}/top-readme",
+    params(
+        ("owner_name" = String, Path, description = "Repository owner"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        RepositoryHostQueries,
+    ),
+    responses(
+        (status = 200, description = "Decoded README of the repository", body = ReadmeResponse),
+        (status = 422, description = "The host is not configured", body = ValidationErrors),
+        (status = 500, description = "README not found or upstream error", body = String),
+    )
+)]
+async fn search_repository_top_readme<C: RepositoryController>(router: web::Data<GithubRepositoryRouter<C>>, path: web::Path<(String, String)>, host: web::Query<RepositoryHostQueries>) -> impl Responder {
+    let (owner_name, repo_name) = path.into_inner();
+    match router.controller.fetch_top_readme(host.host.as_deref(), &owner_name, &repo_name).await {
+        Ok(top_readme) => HttpResponse::Ok().json(top_readme),
+        Err(e) => error_response(e),
+    }
+}
 
-    async fn search_repositories_handler(router: web::Data<GithubRepositoryRouter<C>>, req: web::Json<SearchRepositoriesRequest>) -> impl Responder {
-        match router.controller.fetch_repositories(req).await {
-            Ok(repositories) => HttpResponse::Ok().json(repositories),
-            Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-        }
+#[utoipa::path(
+    get,
+    path = "/github/repositories/{owner_name}/{repo_name}/issues",
+    params(
+        ("owner_name" = String, Path, description = "Repository owner"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        RepositoryHostQueries,
+        SearchIssuesRequestQueries,
+    ),
+    responses(
+        (status = 200, description = "Unassigned issues of the repository", body = Vec<Issue>),
+        (status = 422, description = "The host is not configured", body = ValidationErrors),
+        (status = 500, description = "Upstream or internal error", body = String),
+    )
+)]
+async fn search_repository_issues<C: RepositoryController>(router: web::Data<GithubRepositoryRouter<C>>, path: web::Path<(String, String)>, host: web::Query<RepositoryHostQueries>, query: web::Query<SearchIssuesRequestQueries>) -> impl Responder {
+    let (owner_name, repo_name) = path.into_inner();
+    let query_params = query.into_inner();
+    match router.controller.fetch_issues(host.host.as_deref(), &owner_name, &repo_name, query_params.into()).await {
+        Ok(issues) => HttpResponse::Ok().json(issues),
+        Err(e) => error_response(e),
     }
+}
 
-    async fn search_repository_top_readme_handler(router: web::Data<GithubRepositoryRouter<C>>, path: web::Path<(String, String)>) -> impl Responder {
-        let (owner_name, repo_name) = path.into_inner();
-        match router.controller.fetch_top_readme(&owner_name, &repo_name).await {
-            Ok(top_readme) => HttpResponse::Ok().json(top_readme),
-            Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-        }
+#[utoipa::path(
+    get,
+    path = "/github/repositories/{owner_name}/{repo_name}/onboarding",
+    params(
+        ("owner_name" = String, Path, description = "Repository owner"),
+        ("repo_name" = String, Path, description = "Repository name"),
+        RepositoryHostQueries,
+    ),
+    responses(
+        (status = 200, description = "Installation, build, test and contribution sections of the README and CONTRIBUTING guide", body = OnboardingResponse),
+        (status = 422, description = "The host is not configured", body = ValidationErrors),
+        (status = 500, description = "Upstream or internal error", body = String),
+    )
+)]
+async fn repository_onboarding<C: RepositoryController>(router: web::Data<GithubRepositoryRouter<C>>, path: web::Path<(String, String)>, host: web::Query<RepositoryHostQueries>) -> impl Responder {
+    let (owner_name, repo_name) = path.into_inner();
+    match router.controller.fetch_onboarding(host.host.as_deref(), &owner_name, &repo_name).await {
+        Ok(onboarding) => HttpResponse::Ok().json(onboarding),
+        Err(e) => error_response(e),
     }
+}
 
-    async fn search_repository_issues_handler(router: web::Data<GithubRepositoryRouter<C>>, path: web::Path<(String, String)>, query: web::Query<SearchIssuesRequestQueries>) -> impl Responder {
-        let (owner_name, repo_name) = path.into_inner();
-        let query_params = query.into_inner();
-        match router.controller.fetch_issues(&owner_name, &repo_name, query_params.into()).await {
-            Ok(issues) => HttpResponse::Ok().json(issues),
-            Err(e) => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
-        }
+#[utoipa::path(
+    post,
+    path = "/github/repositories/batch/top-readme",
+    request_body = BatchReadmesRequest,
+    responses(
+        (status = 200, description = "README or error of every repository, in request order", body = [BatchReadmeResult]),
+        (status = 422, description = "Empty, oversized or malformed repository list, or a host that is not configured", body = ValidationErrors),
+    )
+)]
+async fn batch_top_readmes<C: RepositoryController>(router: web::Data<GithubRepositoryRouter<C>>, req: web::Json<BatchReadmesRequest>) -> impl Responder {
+    match router.controller.fetch_top_readmes(req).await {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => error_response(e),
     }
 }
 
-impl<C: RepositoryController + 'static> GithubRepositoryRouter<C> {
-    pub fn repository_scope(&self) -> Scope {
-        web::scope("/github")
-            .service(
-                web::scope("/repositories")
-                    .route("/search-list", web::post().to(Self::search_repositories_handler))
-                    .route("/{owner_name}/{repo_name}/top-readme", web::get().to(Self::search_repository_top_readme_handler))
-                    .route("/{owner_name}/{repo_name}/issues", web::get().to(Self::search_repository_issues_handler))
-            )
+#[utoipa::path(
+    post,
+    path = "/github/repositories/batch/issues",
+    request_body = BatchIssuesRequest,
+    responses(
+        (status = 200, description = "Unassigned issues or error of every repository, in request order", body = [BatchIssuesResult]),
+        (status = 422, description = "Empty, oversized or malformed repository list, or a host that is not configured", body = ValidationErrors),
+    )
+)]
+async fn batch_issues<C: RepositoryController>(router: web::Data<GithubRepositoryRouter<C>>, req: web::Json<BatchIssuesRequest>) -> impl Responder {
+    match router.controller.fetch_issues_batch(req).await {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => error_response(e),
     }
 }
 
+fn error_response(e: Box<dyn Error>) -> HttpResponse {
+    match e.downcast_ref::<ValidationErrors>() {
+        Some(errors) => HttpResponse::UnprocessableEntity().json(errors),
+        None => HttpResponse::InternalServerError().body(format!("Error: {}", e)),
+    }
+}
+
+impl<C: RepositoryController + 'static> RepositoryRouter for GithubRepositoryRouter<C> {
+    fn repository_scope(&self) -> DocumentedScope {
+        DocumentedScope::new("/github/repositories", "github")
+            .route(__path_search_repositories, search_repositories::<C>)
+            .route(__path_batch_top_readmes, batch_top_readmes::<C>)
+            .route(__path_batch_issues, batch_issues::<C>)
+            .route(__path_search_repository_top_readme, search_repository_top_readme::<C>)
+            .route(__path_search_repository_issues, search_repository_issues::<C>)
+            .route(__path_repository_onboarding, repository_onboarding::<C>)
+    }
+}
@@ -1,32 +1,139 @@
 use std::error::Error;
-use crate::internals::github::models::dto::{Repositories, SearchRepositoriesRequest, ReadmeResponse, Issue};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use futures::stream::{self, StreamExt};
+use lru::LruCache;
+use crate::internals::github::models::dto::{Repositories, SearchRepositoriesRequest, ReadmeResponse, Issue, BatchReadmeResult, BatchIssuesResult, OnboardingResponse, FileTree};
 use crate::internals::github::models::entity::{SearchIssuesRequest};
 use crate::internals::github::repositories::repository_repository::RepositoryRepository;
 use crate::pkg::github::model::model as p_model;
 use crate::pkg::github::repositories::{RepositoryClient};
+use crate::pkg::utils::inflight::inflight::InFlight;
 
+/// READMEs and issue lists whose last version is remembered to notice changes.
+const SEEN_CONTENT_CAPACITY: usize = 10_000;
+
+/// Repositories are looked up on `host`, or on the default host when `None`; a host that is
+/// not configured is a validation error.
 pub trait RepositoryService {
+    /// Host that `host`, or the default host when `None`, resolves to.
+    fn host(&self, host: Option<&str>) -> Result<String, Box<dyn Error>>;
     async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>>;
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    /// Fetches the README of every `(owner, repo)` pair, in request order, failing per item.
+    async fn fetch_top_readmes(&self, host: Option<&str>, repositories: Vec<(String, String)>) -> Result<Vec<BatchReadmeResult>, Box<dyn Error>>;
+    /// Fetches the issues of every `(owner, repo)` pair, in request order, failing per item.
+    async fn fetch_issues_batch(&self, host: Option<&str>, repositories: Vec<(String, String)>, req: SearchIssuesRequest) -> Result<Vec<BatchIssuesResult>, Box<dyn Error>>;
+    /// Splits the README and CONTRIBUTING guide into the sections a newcomer looks for.
+    async fn fetch_onboarding(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<OnboardingResponse, Box<dyn Error>>;
+    /// Contribution guidelines; `found` is false when the repository has none.
+    async fn fetch_contributing(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
+    async fn fetch_file_tree(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>>;
+}
+
+/// Told when a fetch finds a repository's README or open issues differ from the last fetch,
+/// so that whatever was derived from them can be dropped.
+pub trait ContentListener {
+    async fn content_changed(&self, host: &str, owner_name: &str, repository_name: &str) -> Result<(), Box<dyn Error>>;
+}
+
+impl ContentListener for () {
+    async fn content_changed(&self, _host: &str, _owner_name: &str, _repository_name: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
-pub struct GithubRepositoryService<C: RepositoryClient + Clone, R: RepositoryRepository + Clone> {
+pub struct GithubRepositoryService<C: RepositoryClient + Clone, R: RepositoryRepository + Clone, L: ContentListener + Clone> {
     client: C,
+    #[allow(dead_code)]
     repository: R,
+    listener: L,
+    batch_concurrency: usize,
+    /// Shared between clones so every handler deduplicates against the same calls
+    readmes_in_flight: Arc<InFlight<(String, String, String), ReadmeResponse>>,
+    issues_in_flight: Arc<InFlight<String, Vec<Issue>>>,
+    /// Fingerprint of the last version fetched, by README or issue query
+    seen: Arc<Mutex<LruCache<String, u64>>>,
 }
 
-impl<C: RepositoryClient + Clone, R: RepositoryRepository + Clone> GithubRepositoryService<C, R> {
-    pub fn new(client: C, repository: R) -> Self {
+impl<C: RepositoryClient + Clone, R: RepositoryRepository + Clone, L: ContentListener + Clone> GithubRepositoryService<C, R, L> {
+    /// `batch_concurrency` caps how many upstream calls a batch request runs at once.
+    pub fn new(client: C, repository: R, listener: L, batch_concurrency: usize) -> Self {
         Self {
             client,
-            repository
+            repository,
+            listener,
+            batch_concurrency: batch_concurrency.max(1),
+            readmes_in_flight: Arc::new(InFlight::new()),
+            issues_in_flight: Arc::new(InFlight::new()),
+            seen: Arc::new(Mutex::new(LruCache::new(NonZeroUsize::new(SEEN_CONTENT_CAPACITY).unwrap()))),
+        }
+    }
+
+    /// Tells the listener when `content` differs from what was last seen under `key`. The first
+    /// fetch since startup is not a change.
+    async fn observe<T: Hash>(&self, key: String, content: T, host: &str, owner_name: &str, repository_name: &str) {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let previous = self.seen.lock().unwrap().put(key.to_lowercase(), hasher.finish());
+        if previous.is_none_or(|previous| previous == hasher.finish()) {
+            return;
         }
+        if let Err(e) = self.listener.content_changed(host, owner_name, repository_name).await {
+            log::warn!("Could not drop what was derived from the old content of {}/{}: {}", owner_name, repository_name, e);
+        }
+    }
+
+    async fn observe_readme(&self, host: &str, owner_name: &str, repository_name: &str, readme: &p_model::ReadmeResponse) {
+        let key = format!("readme {}/{}/{}", host, owner_name, repository_name);
+        self.observe(key, &readme.content, host, owner_name, repository_name).await;
+    }
+
+    /// `host` is resolved already, so that calls naming the default host and calls naming none
+    /// share one upstream request.
+    async fn top_readme(&self, host: &str, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, String> {
+        let key = (host.to_string(), owner_name.to_string(), repository_name.to_string());
+        self.readmes_in_flight.run(key, || async {
+            let res = self.client.fetch_top_readme(Some(host), owner_name, repository_name).await.map_err(|e| e.to_string())?;
+            self.observe_readme(host, owner_name, repository_name, &res).await;
+            if res.found {
+                return Ok(res.into())
+            }
+            Err("Not found".to_string())
+        }).await
+    }
+
+    async fn issues(&self, host: &str, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, String> {
+        let key = format!(
+            "{}/{}/{}?state={:?}&assignee={}&labels={}&sort={:?}&order={:?}",
+            host, owner_name, repository_name, req.state, req.assignee, req.labels.join(","), req.sort_key, req.sort_order,
+        );
+        self.issues_in_flight.run(key.clone(), || async {
+            let issues_req = p_model::SearchIssuesRequest {
+                state: req.state.into(),
+                assignee: req.assignee,
+                labels: req.labels,
+                sort_key: req.sort_key.into(),
+                sort_order: req.sort_order.into(),
+            };
+            let res = self.client.fetch_issues(Some(host), owner_name, repository_name, issues_req).await.map_err(|e| e.to_string())?;
+            // Only which issues are listed counts, not the order they are listed in.
+            let mut urls: Vec<&str> = res.iter().map(|i| i.html_url.as_str()).collect();
+            urls.sort_unstable();
+            self.observe(format!("issues {}", key), urls, host, owner_name, repository_name).await;
+            Ok(res.into_iter().map(|i| i.into()).collect())
+        }).await
     }
 }
 
-impl<C: RepositoryClient + Clone, R: RepositoryRepository + Clone> RepositoryService for GithubRepositoryService<C, R> {
+impl<C: RepositoryClient + Clone, R: RepositoryRepository + Clone, L: ContentListener + Clone> RepositoryService for GithubRepositoryService<C, R, L> {
+    fn host(&self, host: Option<&str>) -> Result<String, Box<dyn Error>> {
+        self.client.host(host)
+    }
+
     async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>> {
         let client_req = p_model::SearchRepositoriesRequest {
             min_stars: req.min_stars,
@@ -35,43 +142,106 @@ impl<C: RepositoryClient + Clone, R: RepositoryRepository + Clone> RepositorySer
             language: req.language,
             good_first_issues_count: req.good_first_issues_count,
             help_wanted_count: req.help_wanted_count,
+            forge: req.forge,
         };
 
         let client_res = self.client.fetch_repositories(client_req).await?;
         Ok(client_res.into())
     }
 
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
-        let res = self.client.fetch_top_readme(owner_name, repository_name).await?;
-        if res.found {
-            return Ok(res.into())
-        }
-        Err("Not found".into())
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        let host = self.client.host(host)?;
+        Ok(self.top_readme(&host, owner_name, repository_name).await?)
+    }
+
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let host = self.client.host(host)?;
+        Ok(self.issues(&host, owner_name, repository_name, req).await?)
     }
 
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
-        let issues_req = p_model::SearchIssuesRequest {
-            state: req.state.into(),
-            assignee: req.assignee.into(),
-            labels: req.labels,
-            sort_key: req.sort_key.into(),
-            sort_order: req.sort_order.into(),
+    async fn fetch_top_readmes(&self, host: Option<&str>, repositories: Vec<(String, String)>) -> Result<Vec<BatchReadmeResult>, Box<dyn Error>> {
+        let host = &self.client.host(host)?;
+        // Forges with a batch API answer most READMEs at once; the rest are fetched one by one.
+        let batched = match self.client.fetch_top_readmes(Some(host), &repositories).await {
+            Ok(readmes) => readmes.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Fetching READMEs from {} in a batch failed, fetching them one by one: {}", host, e);
+                vec![]
+            }
         };
-        let res = self.client.fetch_issues(owner_name, repository_name, issues_req).await?;
-        Ok(res.into_iter().map(|i| i.into()).collect())
+        let mut batched = batched.into_iter();
+        let repositories = repositories.into_iter().map(|repository| (repository, batched.next().flatten()));
+
+        Ok(stream::iter(repositories)
+            .map(|((owner_name, repository_name), readme)| async move {
+                let result = match readme {
+                    Some(readme) => {
+                        self.observe_readme(host, &owner_name, &repository_name, &readme).await;
+                        Ok(readme.into())
+                    }
+                    None => self.top_readme(host, &owner_name, &repository_name).await,
+                };
+                BatchReadmeResult {
+                    repository: format!("{}/{}", owner_name, repository_name),
+                    readme: result.as_ref().ok().cloned(),
+                    error: result.err(),
+                }
+            })
+            .buffered(self.batch_concurrency)
+            .collect()
+            .await)
+    }
+
+    async fn fetch_issues_batch(&self, host: Option<&str>, repositories: Vec<(String, String)>, req: SearchIssuesRequest) -> Result<Vec<BatchIssuesResult>, Box<dyn Error>> {
+        let host = &self.client.host(host)?;
+        Ok(stream::iter(repositories)
+            .map(|(owner_name, repository_name)| {
+                let req = req.clone();
+                async move {
+                    let result = self.issues(host, &owner_name, &repository_name, req).await;
+                    BatchIssuesResult {
+                        repository: format!("{}/{}", owner_name, repository_name),
+                        issues: result.as_ref().ok().cloned(),
+                        error: result.err(),
+                    }
+                }
+            })
+            .buffered(self.batch_concurrency)
+            .collect()
+            .await)
+    }
+
+    async fn fetch_onboarding(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<OnboardingResponse, Box<dyn Error>> {
+        let (readme, contributing) = futures::try_join!(
+            self.client.fetch_top_readme(host, owner_name, repository_name),
+            self.client.fetch_contributing(host, owner_name, repository_name),
+        )?;
+        Ok(OnboardingResponse::new(readme, contributing))
+    }
+
+    async fn fetch_contributing(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        Ok(self.client.fetch_contributing(host, owner_name, repository_name).await?.into())
+    }
+
+    async fn fetch_file_tree(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>> {
+        Ok(self.client.fetch_file_tree(host, owner_name, repository_name).await?.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::error::Error;
-    use crate::internals::github::services::repository_service::{GithubRepositoryService, RepositoryService};
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::internals::github::services::repository_service::{ContentListener, GithubRepositoryService, RepositoryService};
     use crate::internals::github::models::dto as i_model;
     use crate::internals::github::models::dto::SearchRepositoriesRequest;
     use crate::internals::github::models::entity::GithubRepository;
     use crate::internals::github::repositories::repository_repository::RepositoryRepository;
+    use crate::pkg::forge::forge::Forge;
     use crate::pkg::github::model::model as p_model;
     use crate::pkg::github::model::model::{Issue, ReadmeResponse, SearchIssuesRequest};
+    use crate::pkg::github::model::readme::ReadmeFormat;
     use crate::pkg::github::repositories::RepositoryClient;
 
     #[derive(Clone)]
@@ -80,6 +250,10 @@ mod tests {
     }
 
     impl RepositoryClient for MockClient {
+        fn host(&self, _host: Option<&str>) -> Result<String, Box<dyn Error>> {
+            Ok("github.com".to_string())
+        }
+
         async fn fetch_repositories(&self, _req: p_model::SearchRepositoriesRequest) -> Result<p_model::Repositories, Box<dyn Error>> {
             if self.should_fail {
                 Err("Failed to fetch repositories".into())
@@ -94,16 +268,37 @@ mod tests {
                         html_url: "html_url".to_string(),
                         description: "description".to_string(),
                         owner: p_model::Owner { login: "owner_name".to_string(), avatar_url: "avatar_url".to_string() },
+                        host: "github.com".to_string(),
+                        forge: Forge::Github,
                     }],
                 })
             }
         }
 
-        async fn fetch_top_readme(&self, _owner_name: &str, _repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        async fn fetch_top_readme(&self, _host: Option<&str>, _owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+            static REVISION: AtomicUsize = AtomicUsize::new(0);
+            match repository_name {
+                "broken" => Err("GitHub API responded 502".into()),
+                "changing" => Ok(ReadmeResponse {
+                    found: true,
+                    content: Some(format!("# changing, revision {}", REVISION.fetch_add(1, Ordering::SeqCst) / 2)),
+                    format: Some(ReadmeFormat::Markdown),
+                    links: Default::default(),
+                }),
+                "missing" => Ok(ReadmeResponse { found: false, content: None, format: None, links: Default::default() }),
+                _ => Ok(ReadmeResponse { found: true, content: Some(format!("# {}", repository_name)), format: Some(ReadmeFormat::Markdown), links: Default::default() }),
+            }
+        }
+
+        async fn fetch_issues(&self, _host: Option<&str>, _owner_name: &str, _repository_name: &str, _req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
             todo!()
         }
 
-        async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        async fn fetch_contributing(&self, _host: Option<&str>, _owner_name: &str, _repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+            todo!()
+        }
+
+        async fn fetch_file_tree(&self, _host: Option<&str>, _owner_name: &str, _repository_name: &str) -> Result<p_model::FileTree, Box<dyn Error>> {
             todo!()
         }
     }
@@ -111,12 +306,23 @@ mod tests {
     #[derive(Clone)]
     struct MockRepository;
 
+    /// Records the repositories it is told about.
+    #[derive(Clone, Default)]
+    struct RecordingListener(Arc<Mutex<Vec<String>>>);
+
+    impl ContentListener for RecordingListener {
+        async fn content_changed(&self, host: &str, owner_name: &str, repository_name: &str) -> Result<(), Box<dyn Error>> {
+            self.0.lock().unwrap().push(format!("{}/{}/{}", host, owner_name, repository_name));
+            Ok(())
+        }
+    }
+
     impl RepositoryRepository for MockRepository {
-        async fn find_list(&self, req: SearchRepositoriesRequest) -> Result<Vec<GithubRepository>, Box<dyn Error>> {
+        async fn find_list(&self, _req: SearchRepositoriesRequest) -> Result<Vec<GithubRepository>, Box<dyn Error>> {
             todo!()
         }
 
-        async fn bulk_insert(&self, repos: Vec<GithubRepository>) -> Result<(), Box<dyn Error>> {
+        async fn bulk_insert(&self, _repos: Vec<GithubRepository>) -> Result<(), Box<dyn Error>> {
             todo!()
         }
     }
@@ -125,7 +331,7 @@ mod tests {
     async fn test_fetch_repositories_ok() {
         let client = MockClient {should_fail: false};
         let repo_repo = MockRepository;
-        let repository_info_service = GithubRepositoryService::new(client, repo_repo);
+        let repository_info_service = GithubRepositoryService::new(client, repo_repo, (), 8);
         let res = repository_info_service.fetch_repositories(i_model::SearchRepositoriesRequest {
             min_stars: 0,
             max_stars: None,
@@ -133,6 +339,7 @@ mod tests {
             language: "".to_string(),
             good_first_issues_count: 0,
             help_wanted_count: 0,
+            forge: None,
         }).await.unwrap();
 
         assert_eq!(res.total_count, 1);
@@ -145,13 +352,15 @@ mod tests {
         assert_eq!(res.items[0].description, "description");
         assert_eq!(res.items[0].owner.name, "owner_name");
         assert_eq!(res.items[0].owner.avatar_url, "avatar_url");
+        assert_eq!(res.items[0].host, "github.com");
+        assert_eq!(res.items[0].forge, Forge::Github);
     }
 
     #[tokio::test]
     async fn test_fetch_repositories_ng() {
         let client = MockClient { should_fail: true };
         let repo_repo = MockRepository;
-        let repository_info_service = GithubRepositoryService::new(client, repo_repo);
+        let repository_info_service = GithubRepositoryService::new(client, repo_repo, (), 8);
 
         let res = repository_info_service.fetch_repositories(i_model::SearchRepositoriesRequest {
             min_stars: 0,
@@ -160,6 +369,7 @@ mod tests {
             language: "".to_string(),
             good_first_issues_count: 0,
             help_wanted_count: 0,
+            forge: None,
         }).await;
 
         assert!(res.is_err());
@@ -168,4 +378,42 @@ mod tests {
             assert_eq!(e.to_string(), "Failed to fetch repositories");
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_fetch_top_readmes_reports_each_item() {
+        let service = GithubRepositoryService::new(MockClient { should_fail: false }, MockRepository, (), 2);
+        let repositories = ["first", "broken", "missing", "last"].iter()
+            .map(|name| ("octo-org".to_string(), name.to_string()))
+            .collect();
+
+        let results = service.fetch_top_readmes(None, repositories).await.unwrap();
+
+        let summary: Vec<(&str, Option<&str>, Option<&str>)> = results.iter()
+            .map(|r| (
+                r.repository.as_str(),
+                r.readme.as_ref().and_then(|readme| readme.content.as_deref()),
+                r.error.as_deref(),
+            ))
+            .collect();
+        assert_eq!(summary, vec![
+            ("octo-org/first", Some("# first"), None),
+            ("octo-org/broken", None, Some("GitHub API responded 502")),
+            ("octo-org/missing", None, Some("Not found")),
+            ("octo-org/last", Some("# last"), None),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_changed_readmes_are_reported() {
+        let listener = RecordingListener::default();
+        let service = GithubRepositoryService::new(MockClient { should_fail: false }, MockRepository, listener.clone(), 2);
+
+        // The first fetch has nothing to compare with, and the README changes every other fetch.
+        for _ in 0..4 {
+            service.fetch_top_readme(None, "octo-org", "changing").await.unwrap();
+            service.fetch_top_readme(None, "octo-org", "steady").await.unwrap();
+        }
+
+        assert_eq!(*listener.0.lock().unwrap(), vec!["github.com/octo-org/changing"]);
+    }
+}
@@ -1,12 +1,15 @@
 use std::error::Error;
-use crate::internals::github::models::dto::{Issue, ReadmeResponse, Repositories, SearchRepositoriesRequest};
+use crate::internals::github::models::dto::{BatchIssuesResult, BatchReadmeResult, Issue, OnboardingResponse, ReadmeResponse, Repositories, SearchRepositoriesRequest};
 use crate::internals::github::models::entity::SearchIssuesRequest;
 use crate::internals::github::services::repository_service::RepositoryService;
 
 pub trait RepositoryUseCase {
     async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>>;
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    async fn fetch_top_readmes(&self, host: Option<&str>, repositories: Vec<(String, String)>) -> Result<Vec<BatchReadmeResult>, Box<dyn Error>>;
+    async fn fetch_issues_batch(&self, host: Option<&str>, repositories: Vec<(String, String)>, req: SearchIssuesRequest) -> Result<Vec<BatchIssuesResult>, Box<dyn Error>>;
+    async fn fetch_onboarding(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<OnboardingResponse, Box<dyn Error>>;
 }
 
 #[derive(Clone)]
@@ -27,12 +30,24 @@ impl<S: RepositoryService> RepositoryUseCase for GithubRepositoryUseCase<S> {
         self.service.fetch_repositories(req).await
     }
 
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
-        self.service.fetch_top_readme(owner_name, repository_name).await
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        self.service.fetch_top_readme(host, owner_name, repository_name).await
     }
 
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
-        self.service.fetch_issues(owner_name, repository_name, req).await
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        self.service.fetch_issues(host, owner_name, repository_name, req).await
+    }
+
+    async fn fetch_top_readmes(&self, host: Option<&str>, repositories: Vec<(String, String)>) -> Result<Vec<BatchReadmeResult>, Box<dyn Error>> {
+        self.service.fetch_top_readmes(host, repositories).await
+    }
+
+    async fn fetch_issues_batch(&self, host: Option<&str>, repositories: Vec<(String, String)>, req: SearchIssuesRequest) -> Result<Vec<BatchIssuesResult>, Box<dyn Error>> {
+        self.service.fetch_issues_batch(host, repositories, req).await
+    }
+
+    async fn fetch_onboarding(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<OnboardingResponse, Box<dyn Error>> {
+        self.service.fetch_onboarding(host, owner_name, repository_name).await
     }
 }
 
@@ -1,2 +1,4 @@
 pub mod github;
-pub mod ai;
\ No newline at end of file
+pub mod ai;
+pub mod admin;
+pub mod openapi;
\ No newline at end of file
@@ -0,0 +1,85 @@
+use utoipa::OpenApi;
+use crate::internals::openapi::documented_scope::DocumentedScope;
+use crate::internals::ai::models::dto::{AIInquiryResponse, AnswerDelta, AnswerInvalidation, ChatMessage, ChatQuestion, ChatReply, ChatSession, ChatSessionRequest, ContributionGuide, Difficulty, FileHint, PromptPreview, RecommendedIssue, Skill, Status};
+use crate::pkg::ai::budget::budget::{PromptReport, RenderedPrompt, SectionReport};
+use crate::pkg::ai::model::model::{AIProvider, ProviderError, Usage};
+use crate::pkg::ai::model::openai::Role;
+use crate::pkg::ai::prompt::template::{PromptTemplate, TemplateRef, TemplateVariable, VariableKind};
+use crate::internals::github::models::dto::{BatchIssuesRequest, BatchIssuesResult, BatchReadmeResult, BatchReadmesRequest, Issue, OnboardingResponse, OnboardingSection, OnboardingSource, Owner, ReadmeResponse, Repositories, Repository, SearchRepositoriesRequest};
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::github::client::cache::CacheStats;
+use crate::pkg::github::client::token_pool::{TokenHealth, TokenPoolHealth};
+use crate::pkg::github::model::readme::ReadmeFormat;
+use crate::pkg::markdown::sections::SectionKind;
+use crate::pkg::utils::validation::validation::{FieldError, ValidationErrors};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "fynoss", description = "Find your next OSS to contribute"),
+    components(schemas(
+        SearchRepositoriesRequest,
+        Repositories,
+        Repository,
+        Owner,
+        ReadmeResponse,
+        ReadmeFormat,
+        Issue,
+        BatchReadmesRequest,
+        BatchIssuesRequest,
+        BatchReadmeResult,
+        BatchIssuesResult,
+        OnboardingResponse,
+        OnboardingSection,
+        OnboardingSource,
+        SectionKind,
+        AIInquiryResponse,
+        Status,
+        ContributionGuide,
+        Skill,
+        RecommendedIssue,
+        Difficulty,
+        FileHint,
+        AIProvider,
+        Usage,
+        PromptReport,
+        SectionReport,
+        TemplateRef,
+        PromptTemplate,
+        TemplateVariable,
+        VariableKind,
+        PromptPreview,
+        RenderedPrompt,
+        AnswerInvalidation,
+        AnswerDelta,
+        ChatSessionRequest,
+        ChatSession,
+        ChatMessage,
+        ChatQuestion,
+        ChatReply,
+        Role,
+        ProviderError,
+        ValidationErrors,
+        FieldError,
+        CacheStats,
+        Forge,
+        TokenPoolHealth,
+        TokenHealth,
+    )),
+    tags(
+        (name = "github", description = "Repository search, READMEs and issues"),
+        (name = "ai", description = "AI generated contribution guidance"),
+        (name = "admin", description = "Operational statistics, prompt templates and stored answers"),
+    )
+)]
+pub struct ApiDoc;
+
+impl ApiDoc {
+    /// The document for the operations `scopes` serve, which are registered from it.
+    pub fn with_scopes(scopes: impl IntoIterator<Item = DocumentedScope>) -> utoipa::openapi::OpenApi {
+        let mut doc = ApiDoc::openapi();
+        for scope in scopes {
+            doc.paths.merge(scope.into_paths());
+        }
+        doc
+    }
+}
@@ -0,0 +1,110 @@
+use actix_web::dev::{AppService, HttpServiceFactory};
+use actix_web::http::Method;
+use actix_web::{web, FromRequest, Handler, Responder, Scope};
+use utoipa::openapi::path::{HttpMethod, Paths};
+
+/// A scope whose routes are registered from the `#[utoipa::path]` of their handlers, so what
+/// is served and what the OpenAPI document describes are the same list.
+pub struct DocumentedScope {
+    prefix: String,
+    tag: &'static str,
+    scope: Scope,
+    paths: Paths,
+}
+
+impl DocumentedScope {
+    /// Operations under `prefix` are listed under `tag` in the document.
+    pub fn new(prefix: &str, tag: &'static str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            tag,
+            scope: web::scope(prefix),
+            paths: Paths::new(),
+        }
+    }
+
+    /// Serves `handler` at the path and methods `doc`, the struct `#[utoipa::path]` generates
+    /// for it, describes. Panics when that path lies outside this scope.
+    pub fn route<P, F, Args>(mut self, _doc: P, handler: F) -> Self
+    where
+        P: utoipa::Path,
+        F: Handler<Args>,
+        Args: FromRequest + 'static,
+        F::Output: Responder + 'static,
+    {
+        let path = P::path();
+        let relative = path.strip_prefix(&self.prefix)
+            .unwrap_or_else(|| panic!("{} is documented outside of the {} scope", path, self.prefix));
+        for method in P::methods() {
+            self.scope = self.scope.route(relative, web::method(actix_method(&method)).to(handler.clone()));
+        }
+
+        let mut operation = P::operation();
+        operation.tags = Some(vec![self.tag.to_string()]);
+        self.paths.add_path_operation(&path, P::methods(), operation);
+        self
+    }
+
+    /// The operations this scope serves, for the OpenAPI document.
+    pub fn into_paths(self) -> Paths {
+        self.paths
+    }
+}
+
+impl HttpServiceFactory for DocumentedScope {
+    fn register(self, config: &mut AppService) {
+        self.scope.register(config)
+    }
+}
+
+fn actix_method(method: &HttpMethod) -> Method {
+    match method {
+        HttpMethod::Get => Method::GET,
+        HttpMethod::Post => Method::POST,
+        HttpMethod::Put => Method::PUT,
+        HttpMethod::Delete => Method::DELETE,
+        HttpMethod::Options => Method::OPTIONS,
+        HttpMethod::Head => Method::HEAD,
+        HttpMethod::Patch => Method::PATCH,
+        HttpMethod::Trace => Method::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{App, HttpResponse, Responder};
+    use crate::internals::openapi::documented_scope::DocumentedScope;
+
+    #[utoipa::path(
+        delete,
+        path = "/things/{id}",
+        params(("id" = String, Path, description = "Thing")),
+        responses((status = 204, description = "Dropped"))
+    )]
+    async fn drop_thing() -> impl Responder {
+        HttpResponse::NoContent().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_routes_are_served_as_documented() {
+        let scope = || DocumentedScope::new("/things", "things").route(__path_drop_thing, drop_thing);
+
+        let paths = scope().into_paths();
+        let operation = paths.paths["/things/{id}"].delete.as_ref().unwrap();
+        assert_eq!(operation.tags, Some(vec!["things".to_string()]));
+
+        let app = init_service(App::new().service(scope())).await;
+        let res = call_service(&app, TestRequest::delete().uri("/things/42").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let res = call_service(&app, TestRequest::get().uri("/things/42").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of the /other scope")]
+    fn test_routes_outside_the_scope_are_refused() {
+        DocumentedScope::new("/other", "things").route(__path_drop_thing, drop_thing);
+    }
+}
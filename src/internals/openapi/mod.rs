@@ -0,0 +1,3 @@
+pub mod api_doc;
+pub mod documented_scope;
+pub mod openapi_router;
\ No newline at end of file
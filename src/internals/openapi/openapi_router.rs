@@ -0,0 +1,30 @@
+use actix_web::{web, HttpResponse, Responder};
+use utoipa::openapi::OpenApi;
+
+/// Redoc is pinned to one release so the page only changes along with this repository.
+const REDOC_HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>fynoss API</title>
+    <meta charset="utf-8"/>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+  </head>
+  <body>
+    <redoc spec-url="/openapi.json"></redoc>
+    <script src="https://cdn.jsdelivr.net/npm/redoc@2.1.5/bundles/redoc.standalone.js" crossorigin="anonymous"></script>
+  </body>
+</html>"#;
+
+async fn openapi_json_handler(doc: web::Data<OpenApi>) -> impl Responder {
+    HttpResponse::Ok().json(doc.get_ref())
+}
+
+async fn docs_handler() -> impl Responder {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(REDOC_HTML)
+}
+
+pub fn openapi_config(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/openapi.json", web::get().to(openapi_json_handler))
+        .route("/docs", web::get().to(docs_handler));
+}
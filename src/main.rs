@@ -1,69 +1,49 @@
 mod pkg;
 mod internals;
+#[allow(dead_code)]
 mod cli;
+mod config;
+mod server;
+#[cfg(test)]
+mod e2e;
 
 use dotenv::dotenv;
-use std::env;
-use std::sync::Arc;
-use actix_web::{App, HttpServer, web};
-use actix_web::middleware::Logger;
-use reqwest::Client as ReqwestClient;
-use sqlx::MySqlPool;
-use crate::internals::ai::controllers::ai_controller::OpenAIController;
-use crate::internals::ai::routers::ai_router::OpenAIRouter;
-use crate::internals::ai::services::ai_service::OpenAIService;
-use crate::internals::ai::usecases::ai_usecase::OpenAIUseCase;
-use crate::internals::github::controller::repository_controller::GithubRepositoryController;
-use crate::internals::github::repositories::repository_repository::{GithubRepositoryRepository};
-use crate::internals::github::router::repository_router::{GithubRepositoryRouter};
-use crate::internals::github::services::repository_service::GithubRepositoryService;
-use crate::internals::github::usecases::repository_usecase::GithubRepositoryUseCase;
-use crate::pkg::ai::client::client::OpenAIClient;
-use crate::pkg::github::client::client::GithubClient;
-use crate::pkg::github::repositories::GithubRepositoryClient;
+use std::net::TcpListener;
+use std::process;
+use std::time::Duration;
+use clap::Parser;
+use sqlx::mysql::MySqlPoolOptions;
+use crate::config::config::{Args, Config};
+use crate::server::server::build_server;
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
-    unsafe { env::set_var("RUST_LOG", "debug"); }
-    env_logger::init();
+    let config = match Config::load(Args::parse()) {
+        Ok(config) => config,
+        Err(errors) => {
+            eprintln!("Invalid configuration:");
+            for e in errors.errors {
+                eprintln!("  {}: {}", e.field, e.message);
+            }
+            process::exit(1);
+        }
+    };
 
-    let github_token = env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN is not set");
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL is not set");
-    let openai_apikey = env::var("OPENAI_KEY").expect("OPENAI_KEY is not set");
+    env_logger::Builder::new().parse_filters(&config.log.level).init();
 
-    let reqwest_client = ReqwestClient::new();
-    let pool = MySqlPool::connect(&database_url).await.unwrap();
-    let github_client = GithubClient::new(github_token, reqwest_client.clone());
-    let github_repository_client = GithubRepositoryClient::new(github_client);
-    let github_repository_repository = GithubRepositoryRepository::new(pool);
-    let github_repository_service =
-        GithubRepositoryService::new(github_repository_client, github_repository_repository);
-    let github_repository_usecase = GithubRepositoryUseCase::new(github_repository_service.clone());
-    let github_repository_controller = GithubRepositoryController::new(github_repository_usecase);
-    let github_repository_router = GithubRepositoryRouter::new(github_repository_controller);
-    let github_repository_router = Arc::new(github_repository_router);
+    let pool = MySqlPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .acquire_timeout(Duration::from_secs(config.database.acquire_timeout_secs))
+        .idle_timeout(Duration::from_secs(config.database.idle_timeout_secs))
+        .connect(&config.database.url)
+        .await
+        .unwrap();
 
-    let ai_client = OpenAIClient::new(openai_apikey, reqwest_client.clone());
-    let ai_service = OpenAIService::new(ai_client);
-    let ai_usecase = OpenAIUseCase::new(ai_service, github_repository_service);
-    let ai_controller = OpenAIController::new(ai_usecase);
-    let ai_router = OpenAIRouter::new(ai_controller);
-    let ai_router = Arc::new(ai_router);
-
-    HttpServer::new(move || {
-        let github_router_clone = github_repository_router.clone();
-        let ai_router_clone = ai_router.clone();
-        App::new()
-            .app_data(web::Data::from(github_router_clone))
-            .app_data(web::Data::from(ai_router_clone))
-            .service(github_repository_router.repository_scope())
-            .service(ai_router.ai_scope())
-            .wrap(Logger::default())
-    })
-        .bind(("0.0.0.0", 8080))
+    let listener = TcpListener::bind(&config.server.bind).unwrap();
+    build_server(&config, pool, listener)
         .unwrap()
-        .run()
-        .await.unwrap();
+        .await
+        .unwrap();
 }
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::pkg::ai::budget::tokenizer::Tokenizer;
+use crate::pkg::ai::model::openai::{Message, Role};
+use crate::pkg::markdown::sections::{classify, SectionKind};
+
+/// Tokens kept for the note that lists what a section left out.
+const NOTE_TOKENS: usize = 64;
+/// Below this, a document section is left out rather than cut.
+const MIN_CUT_TOKENS: usize = 32;
+
+/// How a section's text can be shortened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionShape {
+    /// Plain text with `#` headings, shortened by leaving out or cutting whole sections,
+    /// those that help a newcomer least first
+    Document,
+    /// Entries that start at the beginning of a line, with indented continuation lines,
+    /// shortened by leaving out entries from the end
+    List,
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptSection {
+    /// Also the delimiter, as in `<name>...</name>`
+    pub name: String,
+    pub text: String,
+    pub shape: SectionShape,
+    /// Share of the budget the section is guaranteed, relative to the other sections
+    pub weight: u32,
+}
+
+/// Instructions followed by delimited context sections, in priority order.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    /// Sent as the system message; never shortened
+    pub system: Option<String>,
+    pub instructions: String,
+    pub sections: Vec<PromptSection>,
+}
+
+/// A prompt as sent to the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RenderedPrompt {
+    pub system: Option<String>,
+    /// Instructions and context sections
+    pub user: String,
+}
+
+/// What went into a prompt and what was left out to fit the budget.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PromptReport {
+    pub budget_tokens: usize,
+    /// Tokens of the whole prompt, system message and instructions included
+    pub prompt_tokens: usize,
+    pub sections: Vec<SectionReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SectionReport {
+    pub name: String,
+    pub original_tokens: usize,
+    pub tokens: usize,
+    /// Headings of left out document sections, marked "(cut)" when kept in part, or the first
+    /// lines of left out list entries
+    pub omitted: Vec<String>,
+}
+
+/// Fits prompts into a model's token budget.
+#[derive(Clone, Copy)]
+pub struct PromptBudget {
+    tokenizer: Tokenizer,
+    max_tokens: usize,
+}
+
+impl PromptBudget {
+    pub fn new(tokenizer: Tokenizer, max_tokens: usize) -> Self {
+        Self {
+            tokenizer,
+            max_tokens,
+        }
+    }
+
+    /// The same tokenizer with `percent` of the tokens, for a prompt that leaves room for more.
+    pub fn share(&self, percent: usize) -> Self {
+        Self::new(self.tokenizer, self.max_tokens * percent / 100)
+    }
+
+    /// The latest messages of `history` that fit in what `fixed` leaves of the budget, oldest
+    /// first, and how many earlier ones were left out. What is kept never starts with an
+    /// assistant message, whose question would be missing.
+    pub fn fit_history(&self, fixed: &[&str], history: Vec<Message>) -> (Vec<Message>, usize) {
+        let mut available = self.max_tokens.saturating_sub(fixed.iter().map(|text| self.tokenizer.count(text)).sum());
+        let mut start = history.len();
+        for (i, message) in history.iter().enumerate().rev() {
+            let tokens = self.tokenizer.count(&message.content);
+            if tokens > available {
+                break;
+            }
+            available -= tokens;
+            start = i;
+        }
+        while history.get(start).is_some_and(|m| m.role == Role::Assistant) {
+            start += 1;
+        }
+        let kept = history[start..].to_vec();
+        (kept, start)
+    }
+
+    /// Renders `prompt`, shortening sections that do not fit.
+    ///
+    /// Each section is first guaranteed its weighted share of what the instructions leave;
+    /// shares a section does not need then go to the sections that need more, in priority order.
+    pub fn fit(&self, prompt: Prompt) -> (RenderedPrompt, PromptReport) {
+        let system_tokens = prompt.system.as_deref().map_or(0, |s| self.tokenizer.count(s));
+        let fixed = system_tokens + self.tokenizer.count(&prompt.instructions)
+            + prompt.sections.iter().map(|s| self.tokenizer.count(&render(&s.name, ""))).sum::<usize>();
+        let available = self.max_tokens.saturating_sub(fixed);
+
+        let needs: Vec<usize> = prompt.sections.iter().map(|s| self.tokenizer.count(&s.text)).collect();
+        let total_weight: u64 = prompt.sections.iter().zip(&needs)
+            .filter(|(_, need)| **need > 0)
+            .map(|(s, _)| s.weight as u64)
+            .sum();
+        let mut allowances: Vec<usize> = prompt.sections.iter().zip(&needs)
+            .map(|(s, need)| match total_weight {
+                0 => 0,
+                _ => (*need).min((available as u64 * s.weight as u64 / total_weight) as usize),
+            })
+            .collect();
+        let mut leftover = available.saturating_sub(allowances.iter().sum());
+        for (allowance, need) in allowances.iter_mut().zip(&needs) {
+            let extra = (need - *allowance).min(leftover);
+            *allowance += extra;
+            leftover -= extra;
+        }
+
+        let mut rendered = vec![prompt.instructions];
+        let mut reports = vec![];
+        for ((section, need), allowance) in prompt.sections.into_iter().zip(needs).zip(allowances) {
+            let (text, omitted) = match (need <= allowance, section.shape) {
+                (true, _) => (section.text, vec![]),
+                (false, SectionShape::Document) => self.shorten_document(&section.text, allowance),
+                (false, SectionShape::List) => self.shorten_list(&section.text, allowance),
+            };
+            reports.push(SectionReport {
+                name: section.name.clone(),
+                original_tokens: need,
+                tokens: self.tokenizer.count(&text),
+                omitted,
+            });
+            rendered.push(render(&section.name, &text));
+        }
+
+        let user = rendered.join("\n\n");
+        let report = PromptReport {
+            budget_tokens: self.max_tokens,
+            prompt_tokens: system_tokens + self.tokenizer.count(&user),
+            sections: reports,
+        };
+        (RenderedPrompt { system: prompt.system, user }, report)
+    }
+
+    /// Keeps the introduction and the setup, build, test and contributing sections first, cutting
+    /// one of them if it is what no longer fits, then the rest in document order.
+    fn shorten_document(&self, text: &str, max_tokens: usize) -> (String, Vec<String>) {
+        let chunks = split_headings(text);
+        let available = max_tokens.saturating_sub(NOTE_TOKENS.min(max_tokens / 4));
+        let rank = |i: usize, heading: &Option<String>| match heading.as_deref().and_then(classify) {
+            _ if i == 0 => 0,
+            Some(SectionKind::Installation | SectionKind::Build | SectionKind::Test | SectionKind::Contributing) => 1,
+            _ => 2,
+        };
+        let mut order: Vec<usize> = (0..chunks.len()).collect();
+        order.sort_by_key(|&i| rank(i, &chunks[i].0));
+
+        let mut kept: Vec<Option<String>> = vec![None; chunks.len()];
+        let mut cut = vec![false; chunks.len()];
+        let mut used = 0;
+        for i in order {
+            let (heading, chunk) = &chunks[i];
+            let tokens = self.tokenizer.count(chunk);
+            if used + tokens <= available {
+                kept[i] = Some(chunk.clone());
+                used += tokens;
+            } else if rank(i, heading) < 2 && available - used >= MIN_CUT_TOKENS {
+                kept[i] = Some(self.tokenizer.truncate(chunk, available - used));
+                cut[i] = true;
+                used = available;
+            }
+        }
+
+        let omitted: Vec<String> = chunks.iter().enumerate()
+            .filter(|(i, _)| kept[*i].is_none() || cut[*i])
+            .map(|(i, (heading, _))| {
+                let heading = heading.clone().unwrap_or_else(|| "(introduction)".to_string());
+                if cut[i] { format!("{} (cut)", heading) } else { heading }
+            })
+            .collect();
+        let mut parts: Vec<String> = kept.into_iter().flatten().collect();
+        parts.push(self.note(&omitted, max_tokens.saturating_sub(used)));
+        (parts.join("\n\n"), omitted)
+    }
+
+    fn shorten_list(&self, text: &str, max_tokens: usize) -> (String, Vec<String>) {
+        let available = max_tokens.saturating_sub(NOTE_TOKENS.min(max_tokens / 4));
+        let mut kept = vec![];
+        let mut omitted = vec![];
+        let mut used = 0;
+        for entry in split_entries(text) {
+            let tokens = self.tokenizer.count(&entry);
+            if omitted.is_empty() && used + tokens <= available {
+                kept.push(entry);
+                used += tokens;
+            } else {
+                omitted.push(entry.lines().next().unwrap_or_default().to_string());
+            }
+        }
+        kept.push(self.note(&omitted, max_tokens.saturating_sub(used)));
+        (kept.join("\n"), omitted)
+    }
+
+    /// Tells the model what is missing, so it does not take the shortened text as complete.
+    fn note(&self, omitted: &[String], max_tokens: usize) -> String {
+        let note = format!("[Left out to fit the prompt: {}]", omitted.join("; "));
+        if self.tokenizer.count(&note) <= max_tokens {
+            return note;
+        }
+        let short = format!("[{} parts left out to fit the prompt]", omitted.len());
+        self.tokenizer.truncate(&short, max_tokens)
+    }
+}
+
+fn render(name: &str, text: &str) -> String {
+    match text.is_empty() {
+        true => format!("<{}>\n</{}>", name, name),
+        false => format!("<{}>\n{}\n</{}>", name, text, name),
+    }
+}
+
+/// Splits text at `#` heading lines outside code fences, returning each part with its heading.
+fn split_headings(text: &str) -> Vec<(Option<String>, String)> {
+    let mut chunks: Vec<(Option<String>, Vec<&str>)> = vec![];
+    let mut fenced = false;
+    for line in text.lines() {
+        if line.starts_with("```") {
+            fenced = !fenced;
+        }
+        let heading = line.trim_start_matches('#');
+        if !fenced && line.starts_with('#') && heading.starts_with(' ') {
+            chunks.push((Some(heading.trim().to_string()), vec![line]));
+            continue;
+        }
+        match chunks.last_mut() {
+            Some((_, lines)) => lines.push(line),
+            None => chunks.push((None, vec![line])),
+        }
+    }
+    chunks.into_iter()
+        .map(|(heading, lines)| (heading, lines.join("\n").trim().to_string()))
+        .filter(|(_, chunk)| !chunk.is_empty())
+        .collect()
+}
+
+fn split_entries(text: &str) -> Vec<String> {
+    let mut entries: Vec<String> = vec![];
+    for line in text.lines() {
+        match entries.last_mut() {
+            Some(entry) if line.starts_with(char::is_whitespace) => {
+                entry.push('\n');
+                entry.push_str(line);
+            }
+            _ => entries.push(line.to_string()),
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pkg::ai::budget::budget::{Prompt, PromptBudget, PromptSection, SectionShape};
+    use crate::pkg::ai::budget::tokenizer::Tokenizer;
+    use crate::pkg::ai::model::model::AIProvider;
+    use crate::pkg::ai::model::openai::{Message, Role};
+
+    fn section(name: &str, text: String, shape: SectionShape, weight: u32) -> PromptSection {
+        PromptSection {
+            name: name.to_string(),
+            text,
+            shape,
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_fit_shortens_sections_over_their_share() {
+        let tokenizer = Tokenizer::for_model(AIProvider::OpenAI, "gpt-4o-mini");
+        let filler = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(40);
+        let readme = format!(
+            "# Big Project\n\nA big project.\n\n## History\n\n{filler}\n\n## Installation\n\n```sh\n# not a heading\nmake install\n```\n\n## FAQ\n\n{filler}"
+        );
+        let issues = (1..=30).map(|n| format!("- https://github.com/o/r/issues/{n} Issue {n}\n  {filler:.80}")).collect::<Vec<_>>().join("\n");
+        let prompt = Prompt {
+            system: Some("You help newcomers contribute to open source projects.".to_string()),
+            instructions: "How do I contribute?".to_string(),
+            sections: vec![
+                section("readme", readme, SectionShape::Document, 2),
+                section("open_issues", issues, SectionShape::List, 1),
+                section("file_tree", "src/ (3 files)".to_string(), SectionShape::List, 1),
+            ],
+        };
+
+        let (rendered, report) = PromptBudget::new(tokenizer, 500).fit(prompt);
+        let text = rendered.user;
+
+        assert_eq!(report.budget_tokens, 500);
+        assert!(report.prompt_tokens <= 500, "{}", report.prompt_tokens);
+        assert_eq!(report.prompt_tokens, tokenizer.count(rendered.system.as_deref().unwrap()) + tokenizer.count(&text));
+        assert_eq!(report.sections[0].omitted, vec!["History", "FAQ"]);
+        assert!(text.contains("# not a heading\nmake install"));
+        assert!(text.contains("[Left out to fit the prompt: History; FAQ]"));
+        let issues = &report.sections[1];
+        assert!(issues.tokens < issues.original_tokens);
+        assert_eq!(issues.omitted.last().unwrap(), "- https://github.com/o/r/issues/30 Issue 30");
+        assert!(text.contains("/issues/1 Issue 1\n"));
+        // The tree is small enough to go in whole.
+        assert!(report.sections[2].omitted.is_empty());
+        assert!(text.ends_with("<file_tree>\nsrc/ (3 files)\n</file_tree>"));
+    }
+
+    #[test]
+    fn test_fit_history_keeps_the_latest_turns() {
+        let tokenizer = Tokenizer::for_model(AIProvider::OpenAI, "gpt-4o-mini");
+        let message = |role: Role, n: usize| Message { role, content: format!("message {} ", n).repeat(10) };
+        let history = vec![message(Role::User, 1), message(Role::Assistant, 2), message(Role::User, 3), message(Role::Assistant, 4)];
+        let tokens = tokenizer.count(&history[0].content);
+
+        let budget = PromptBudget::new(tokenizer, 3 * tokens + 10);
+        let (kept, omitted) = budget.fit_history(&["0123456789"], history.clone());
+        // Three messages fit, but the first of them is an answer to a question left out.
+        assert_eq!(omitted, 2);
+        assert_eq!(kept.iter().map(|m| m.role.clone()).collect::<Vec<_>>(), vec![Role::User, Role::Assistant]);
+        assert!(kept[0].content.starts_with("message 3"));
+
+        let (kept, omitted) = PromptBudget::new(tokenizer, 100_000).fit_history(&[], history.clone());
+        assert_eq!((kept.len(), omitted), (4, 0));
+        let (kept, omitted) = PromptBudget::new(tokenizer, 100_000).share(0).fit_history(&[], history);
+        assert_eq!((kept.len(), omitted), (0, 4));
+    }
+
+    #[test]
+    fn test_truncate_cuts_at_token_boundaries() {
+        let tokenizer = Tokenizer::for_model(AIProvider::Anthropic, "claude-3-5-haiku-latest");
+        let text = "日本語のテキストを途中で切る。".repeat(10);
+
+        let cut = tokenizer.truncate(&text, 10);
+        assert!(text.starts_with(&cut));
+        assert!(tokenizer.count(&cut) <= 10);
+        assert_eq!(tokenizer.truncate("short", 10), "short");
+    }
+}
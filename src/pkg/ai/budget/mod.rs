@@ -0,0 +1,2 @@
+pub mod budget;
+pub mod tokenizer;
\ No newline at end of file
@@ -0,0 +1,47 @@
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer as Encoding};
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton, p50k_base_singleton, p50k_edit_singleton, r50k_base_singleton, CoreBPE};
+use crate::pkg::ai::model::model::AIProvider;
+
+/// Counts and cuts text in a model's tokens.
+///
+/// OpenAI models get their own encoding. Other providers publish no tokenizer that tiktoken
+/// knows, so `cl100k_base` stands in; it tends to count more tokens than newer encodings,
+/// which errs on the side of fitting.
+#[derive(Clone, Copy)]
+pub struct Tokenizer {
+    bpe: &'static CoreBPE,
+}
+
+impl Tokenizer {
+    pub fn for_model(provider: AIProvider, model: &str) -> Self {
+        let encoding = match provider {
+            AIProvider::OpenAI | AIProvider::OpenAICompatible => get_tokenizer(model),
+            AIProvider::Anthropic | AIProvider::Ollama => None,
+        };
+        let bpe = match encoding {
+            Some(Encoding::O200kBase) => o200k_base_singleton(),
+            Some(Encoding::P50kBase) => p50k_base_singleton(),
+            Some(Encoding::P50kEdit) => p50k_edit_singleton(),
+            Some(Encoding::R50kBase) | Some(Encoding::Gpt2) => r50k_base_singleton(),
+            Some(Encoding::Cl100kBase) | None => cl100k_base_singleton(),
+        };
+        Self { bpe }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+
+    /// The longest prefix of `text` within `max_tokens`, cut at a token boundary that does not
+    /// split a character.
+    pub fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        let tokens = self.bpe.encode_with_special_tokens(text);
+        if tokens.len() <= max_tokens {
+            return text.to_string();
+        }
+        // A character spans at most four tokens, so one of these ends on a character boundary.
+        (max_tokens.saturating_sub(3)..=max_tokens).rev()
+            .find_map(|n| self.bpe.decode(tokens[..n].to_vec()).ok())
+            .unwrap_or_default()
+    }
+}
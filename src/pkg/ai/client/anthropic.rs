@@ -0,0 +1,165 @@
+use std::error::Error;
+use futures::channel::mpsc::UnboundedSender;
+use reqwest::Client as ReqwestClient;
+use crate::pkg::ai::budget::budget::PromptBudget;
+use crate::pkg::ai::client::client::{ensure_success, post_json, post_streaming, send_delta, GenAIClient, GenAIClientSettings};
+use crate::pkg::ai::model::anthropic::{AnthropicDelta, AnthropicMessagesRequest, AnthropicMessagesResponse, AnthropicStreamEvent, AnthropicTool, AnthropicToolChoice};
+use crate::pkg::ai::model::model::{AIProvider, Completion, InquiryRequest, Usage};
+use crate::pkg::ai::model::openai::Message;
+use crate::pkg::ai::model::openai::Role::User;
+use crate::pkg::utils::http_fixture::http_fixture::HttpFixtures;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// The Messages API needs a limit, so this applies when none is configured.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Talks to the Anthropic Messages API.
+#[derive(Clone)]
+pub struct AnthropicClient {
+    api_key: String,
+    settings: GenAIClientSettings,
+    reqwest_client: ReqwestClient,
+    fixtures: Option<HttpFixtures>,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, settings: GenAIClientSettings, reqwest_client: ReqwestClient) -> Self {
+        Self {
+            api_key,
+            settings,
+            reqwest_client,
+            fixtures: None,
+        }
+    }
+
+    /// Records responses to, or replays them from, `fixtures` instead of only using the network.
+    pub fn with_fixtures(self, fixtures: HttpFixtures) -> Self {
+        Self {
+            fixtures: Some(fixtures),
+            ..self
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/messages", self.settings.base_url.trim_end_matches('/'))
+    }
+
+    fn headers(&self) -> [(&'static str, String); 3] {
+        [
+            ("x-api-key", self.api_key.clone()),
+            ("anthropic-version", ANTHROPIC_VERSION.to_string()),
+            ("Content-Type", "application/json".to_string()),
+        ]
+    }
+
+    fn request_body(&self, req: InquiryRequest, stream: bool) -> AnthropicMessagesRequest {
+        // The Messages API has no JSON mode, so a schema becomes the input of a tool the model
+        // has to call, and that input is the answer.
+        let (tools, tool_choice) = match req.response_schema {
+            Some(s) => (
+                vec![AnthropicTool {
+                    name: s.name.clone(),
+                    description: "Records the answer".to_string(),
+                    input_schema: s.schema,
+                }],
+                Some(AnthropicToolChoice { kind: "tool", name: s.name }),
+            ),
+            None => (vec![], None),
+        };
+        AnthropicMessagesRequest {
+            model: self.settings.model.clone(),
+            max_tokens: self.settings.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            system: req.system,
+            messages: req.history.into_iter()
+                .chain([Message {
+                    role: User,
+                    content: req.content,
+                }])
+                .collect(),
+            tools,
+            tool_choice,
+            stream,
+        }
+    }
+}
+
+impl GenAIClient for AnthropicClient {
+    fn provider(&self) -> AIProvider {
+        AIProvider::Anthropic
+    }
+
+    async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>> {
+        let request_body = self.request_body(req, false);
+        let res = post_json(&self.reqwest_client, self.fixtures.as_ref(), &self.url(), &self.headers(), serde_json::to_string(&request_body)?, self.settings.timeout).await?;
+        ensure_success(AIProvider::Anthropic, &res)?;
+
+        let message: AnthropicMessagesResponse = serde_json::from_str(&res.text)?;
+        let content = message.content.into_iter()
+            .filter_map(|block| match block.kind.as_str() {
+                "text" => block.text,
+                "tool_use" => block.input.map(|input| input.to_string()),
+                _ => None,
+            })
+            .collect();
+        Ok(Completion {
+            content,
+            finish_reason: message.stop_reason,
+            model: message.model,
+            usage: Usage {
+                prompt_tokens: message.usage.input_tokens,
+                completion_tokens: message.usage.output_tokens,
+            },
+        })
+    }
+
+    async fn inquire_stream(&self, req: InquiryRequest, deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>> {
+        let request_body = self.request_body(req, true);
+        let mut completion = Completion {
+            content: String::new(),
+            finish_reason: None,
+            model: self.settings.model.clone(),
+            usage: Usage::default(),
+        };
+        let res = post_streaming(&self.reqwest_client, self.fixtures.as_ref(), &self.url(), &self.headers(), serde_json::to_string(&request_body)?, self.settings.timeout, |line| {
+            // Each `event:` line is followed by a `data:` line that names the event again.
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(());
+            };
+            match serde_json::from_str::<AnthropicStreamEvent>(data)? {
+                AnthropicStreamEvent::MessageStart { message } => {
+                    completion.model = message.model;
+                    completion.usage.prompt_tokens = message.usage.input_tokens;
+                }
+                AnthropicStreamEvent::ContentBlockDelta { delta } => {
+                    let text = match delta {
+                        AnthropicDelta::TextDelta { text } => text,
+                        AnthropicDelta::InputJsonDelta { partial_json } => partial_json,
+                        AnthropicDelta::Other => return Ok(()),
+                    };
+                    if !text.is_empty() {
+                        completion.content.push_str(&text);
+                        send_delta(deltas, text)?;
+                    }
+                }
+                AnthropicStreamEvent::MessageDelta { delta, usage } => {
+                    completion.finish_reason = delta.stop_reason;
+                    completion.usage.completion_tokens = usage.output_tokens;
+                }
+                // Errors after the response started, such as overload, come as an event.
+                AnthropicStreamEvent::Error { error } => return Err(format!("anthropic stopped answering: {}", error.message).into()),
+                AnthropicStreamEvent::Other => {}
+            }
+            Ok(())
+        }).await?;
+        ensure_success(AIProvider::Anthropic, &res)?;
+        Ok(completion)
+    }
+
+    fn prompt_budget(&self, _provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>> {
+        Ok(self.settings.prompt_budget(AIProvider::Anthropic))
+    }
+
+    fn model(&self, _provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>> {
+        Ok((AIProvider::Anthropic, self.settings.model.clone()))
+    }
+}
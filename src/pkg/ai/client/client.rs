@@ -1,53 +1,338 @@
+use futures::channel::mpsc::UnboundedSender;
 use reqwest::Client as ReqwestClient;
 use std::error::Error;
-use crate::pkg::ai::model::openai::{GPTResponse, Message, OpenAIInquiryRequest};
-use crate::pkg::ai::model::openai::Role::User;
+use std::time::Duration;
+use crate::pkg::ai::budget::budget::PromptBudget;
+use crate::pkg::ai::budget::tokenizer::Tokenizer;
+use crate::pkg::ai::model::model::{AIProvider, Completion, InquiryRequest, ProviderError, Usage};
+use crate::pkg::ai::model::openai::{GPTResponse, Message, OpenAIChatChunk, OpenAIChatResponse, OpenAIInquiryRequest, OpenAIJsonSchema, OpenAIResponseFormat, OpenAIStreamOptions};
+use crate::pkg::ai::model::openai::Role::{System, User};
+use crate::pkg::utils::http_fixture::http_fixture::{HttpFixtures, RecordedResponse};
 
 pub trait GenAIClient {
-    async fn inquire(&self, content: String) -> Result<GPTResponse, Box<dyn Error>>;
+    fn provider(&self) -> AIProvider;
+    /// Fails with a `ProviderError` when the provider answers with an error status.
+    async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>>;
+    /// Like `inquire`, but sends the answer's text to `deltas` piece by piece while the provider
+    /// generates it. Stops reading the answer, with an error, once nobody receives from `deltas`.
+    async fn inquire_stream(&self, req: InquiryRequest, deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>>;
+    /// Token budget for prompts to `provider`, or to the default provider when `None`. Clients
+    /// of a single provider ignore `provider`, as `inquire` does.
+    fn prompt_budget(&self, provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>>;
+    /// Provider and configured model that `provider`, or the default provider when `None`,
+    /// resolves to.
+    fn model(&self, provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>>;
 }
 
+#[derive(Clone, Debug)]
+pub struct GenAIClientSettings {
+    pub base_url: String,
+    pub model: String,
+    pub max_tokens: Option<u32>,
+    pub max_prompt_tokens: usize,
+    pub timeout: Duration,
+}
+
+impl GenAIClientSettings {
+    pub fn prompt_budget(&self, provider: AIProvider) -> PromptBudget {
+        PromptBudget::new(Tokenizer::for_model(provider, &self.model), self.max_prompt_tokens)
+    }
+}
+
+/// Talks to OpenAI or, with another `provider`, to any server that implements its chat
+/// completions API.
 #[derive(Clone)]
 pub struct OpenAIClient {
+    provider: AIProvider,
     api_key: String,
+    settings: GenAIClientSettings,
     reqwest_client: ReqwestClient,
+    fixtures: Option<HttpFixtures>,
 }
 
 impl OpenAIClient {
-    pub fn new(api_key: String, reqwest_client: ReqwestClient) -> Self {
+    pub fn new(api_key: String, settings: GenAIClientSettings, reqwest_client: ReqwestClient) -> Self {
         Self {
+            provider: AIProvider::OpenAI,
             api_key,
+            settings,
             reqwest_client,
+            fixtures: None,
+        }
+    }
+
+    /// Reports requests as going to `provider`, e.g. an OpenAI-compatible local server.
+    pub fn with_provider(self, provider: AIProvider) -> Self {
+        Self {
+            provider,
+            ..self
+        }
+    }
+
+    /// Records responses to, or replays them from, `fixtures` instead of only using the network.
+    pub fn with_fixtures(self, fixtures: HttpFixtures) -> Self {
+        Self {
+            fixtures: Some(fixtures),
+            ..self
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/chat/completions", self.settings.base_url.trim_end_matches('/'))
+    }
+
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![("Content-Type", "application/json".to_string())];
+        // Local servers often run without authentication.
+        if !self.api_key.is_empty() {
+            headers.push(("Authorization", format!("Bearer {}", self.api_key)));
+        }
+        headers
+    }
+
+    fn request_body(&self, req: InquiryRequest, stream: bool) -> OpenAIInquiryRequest {
+        let mut messages: Vec<Message> = req.system.map(|content| Message { role: System, content }).into_iter().collect();
+        messages.extend(req.history);
+        messages.push(Message {
+            role: User,
+            content: req.content,
+        });
+        OpenAIInquiryRequest {
+            model: self.settings.model.clone(),
+            messages,
+            max_tokens: self.settings.max_tokens,
+            response_format: req.response_schema.map(|s| OpenAIResponseFormat {
+                kind: "json_schema",
+                json_schema: OpenAIJsonSchema {
+                    name: s.name,
+                    schema: s.schema,
+                    strict: true,
+                },
+            }),
+            stream,
+            stream_options: stream.then_some(OpenAIStreamOptions { include_usage: true }),
         }
     }
 }
 
 impl GenAIClient for OpenAIClient {
-    async fn inquire(&self, content: String) -> Result<GPTResponse, Box<dyn Error>> {
-        let url = "https://api.openai.com/v1/chat/completions";
-
-        let request_body = OpenAIInquiryRequest {
-            model: "gpt-4o-mini-2024-07-18".to_string(),
-            messages: vec![Message {
-                role: User,
-                content,
-            }],
+    fn provider(&self) -> AIProvider {
+        self.provider
+    }
+
+    async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>> {
+        let request_body = self.request_body(req, false);
+        let res = post_json(&self.reqwest_client, self.fixtures.as_ref(), &self.url(), &self.headers(), serde_json::to_string(&request_body)?, self.settings.timeout).await?;
+        ensure_success(self.provider, &res)?;
+
+        let chat: OpenAIChatResponse = serde_json::from_str(&res.text)?;
+        let choice = chat.choices.into_iter().next()
+            .ok_or_else(|| format!("{} returned no choices", self.provider))?;
+        Ok(Completion {
+            content: choice.message.content.unwrap_or_default(),
+            finish_reason: choice.finish_reason,
+            model: chat.model,
+            usage: chat.usage.map(|u| Usage {
+                prompt_tokens: u.prompt_tokens,
+                completion_tokens: u.completion_tokens,
+            }).unwrap_or_default(),
+        })
+    }
+
+    async fn inquire_stream(&self, req: InquiryRequest, deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>> {
+        let request_body = self.request_body(req, true);
+        let mut completion = Completion {
+            content: String::new(),
+            finish_reason: None,
+            model: self.settings.model.clone(),
+            usage: Usage::default(),
         };
+        let res = post_streaming(&self.reqwest_client, self.fixtures.as_ref(), &self.url(), &self.headers(), serde_json::to_string(&request_body)?, self.settings.timeout, |line| {
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(());
+            };
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            let chunk: OpenAIChatChunk = serde_json::from_str(data)?;
+            if let Some(model) = chunk.model {
+                completion.model = model;
+            }
+            if let Some(u) = chunk.usage {
+                completion.usage = Usage {
+                    prompt_tokens: u.prompt_tokens,
+                    completion_tokens: u.completion_tokens,
+                };
+            }
+            for choice in chunk.choices {
+                if choice.finish_reason.is_some() {
+                    completion.finish_reason = choice.finish_reason;
+                }
+                if let Some(text) = choice.delta.content.filter(|t| !t.is_empty()) {
+                    completion.content.push_str(&text);
+                    send_delta(deltas, text)?;
+                }
+            }
+            Ok(())
+        }).await?;
+        ensure_success(self.provider, &res)?;
+        Ok(completion)
+    }
 
-        let res = self.reqwest_client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .json(&request_body)
-            .send()
-            .await?;
+    fn prompt_budget(&self, _provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>> {
+        Ok(self.settings.prompt_budget(self.provider))
+    }
+
+    fn model(&self, _provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>> {
+        Ok((self.provider, self.settings.model.clone()))
+    }
+}
+
+/// Turns error statuses into a `ProviderError` carrying the message from the error body, which
+/// is `{"error": {"message": ...}}` for OpenAI and Anthropic and `{"error": ...}` for Ollama.
+pub(crate) fn ensure_success(provider: AIProvider, res: &GPTResponse) -> Result<(), Box<dyn Error>> {
+    if (200..300).contains(&res.status) {
+        return Ok(());
+    }
+    let body = serde_json::from_str::<serde_json::Value>(&res.text).ok();
+    let message = body.as_ref()
+        .and_then(|v| v.get("error"))
+        .and_then(|e| e.get("message").unwrap_or(e).as_str())
+        .map(|m| m.to_string())
+        .unwrap_or_else(|| res.text.clone());
+    Err(Box::new(ProviderError {
+        provider,
+        status: res.status,
+        message,
+    }))
+}
 
-        let status = res.status();
-        let text = res.text().await?;
+/// Posts `body` to `url`, or replays the response when `fixtures` are in replay mode.
+/// Exchanges that did reach the network are recorded when `fixtures` are recording.
+pub(crate) async fn post_json(
+    reqwest_client: &ReqwestClient,
+    fixtures: Option<&HttpFixtures>,
+    url: &str,
+    headers: &[(&str, String)],
+    body: String,
+    timeout: Duration,
+) -> Result<GPTResponse, Box<dyn Error>> {
+    if let Some(fixtures) = fixtures.filter(|f| f.is_replay()) {
+        let recorded = fixtures.replay("POST", url, Some(&body))?;
+        return Ok(GPTResponse {
+            status: recorded.status,
+            text: recorded.body,
+        });
+    }
+
+    let mut req = reqwest_client
+        .post(url)
+        .timeout(timeout);
+    for (name, value) in headers {
+        req = req.header(*name, value);
+    }
+    let res = req.body(body.clone()).send().await?;
+
+    let status = res.status();
+    let response_headers = res.headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+        .collect();
+    let text = res.text().await?;
 
-        Ok(GPTResponse {
+    if let Some(fixtures) = fixtures {
+        fixtures.record("POST", url, headers, Some(&body), RecordedResponse {
             status: status.as_u16(),
-            text
-        })
+            headers: response_headers,
+            body: text.clone(),
+            base64: false,
+        })?;
     }
+
+    Ok(GPTResponse {
+        status: status.as_u16(),
+        text
+    })
 }
 
+/// Hands a piece of a streamed answer to whoever listens on `deltas`.
+pub(crate) fn send_delta(deltas: &UnboundedSender<String>, text: String) -> Result<(), Box<dyn Error>> {
+    deltas.unbounded_send(text).map_err(|_| "stopped streaming the answer: nobody is listening any more".into())
+}
+
+/// Like `post_json`, but hands each line of a successful response to `on_line` as soon as it
+/// arrives, and stops reading when `on_line` fails. Error responses are read whole, for
+/// `ensure_success`. Replayed responses are handed over line by line too.
+pub(crate) async fn post_streaming(
+    reqwest_client: &ReqwestClient,
+    fixtures: Option<&HttpFixtures>,
+    url: &str,
+    headers: &[(&str, String)],
+    body: String,
+    timeout: Duration,
+    mut on_line: impl FnMut(&str) -> Result<(), Box<dyn Error>>,
+) -> Result<GPTResponse, Box<dyn Error>> {
+    if let Some(fixtures) = fixtures.filter(|f| f.is_replay()) {
+        let recorded = fixtures.replay("POST", url, Some(&body))?;
+        if (200..300).contains(&recorded.status) {
+            for line in recorded.body.lines() {
+                on_line(line)?;
+            }
+        }
+        return Ok(GPTResponse {
+            status: recorded.status,
+            text: recorded.body,
+        });
+    }
+
+    let mut req = reqwest_client
+        .post(url)
+        .timeout(timeout);
+    for (name, value) in headers {
+        req = req.header(*name, value);
+    }
+    let mut res = req.body(body.clone()).send().await?;
+
+    let status = res.status();
+    let response_headers = res.headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+        .collect();
+    let text = if status.is_success() {
+        let mut text = String::new();
+        let mut pending: Vec<u8> = vec![];
+        while let Some(chunk) = res.chunk().await? {
+            pending.extend_from_slice(&chunk);
+            // Chunks split the body anywhere, even inside a character, so only complete lines
+            // are decoded.
+            while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                text.push_str(&line);
+                on_line(line.trim_end_matches(['\r', '\n']))?;
+            }
+        }
+        let rest = String::from_utf8_lossy(&pending);
+        text.push_str(&rest);
+        if !rest.trim().is_empty() {
+            on_line(rest.trim_end())?;
+        }
+        text
+    } else {
+        res.text().await?
+    };
+
+    if let Some(fixtures) = fixtures {
+        fixtures.record("POST", url, headers, Some(&body), RecordedResponse {
+            status: status.as_u16(),
+            headers: response_headers,
+            body: text.clone(),
+            base64: false,
+        })?;
+    }
+
+    Ok(GPTResponse {
+        status: status.as_u16(),
+        text
+    })
+}
@@ -0,0 +1,122 @@
+use std::error::Error;
+use futures::channel::mpsc::UnboundedSender;
+use reqwest::Client as ReqwestClient;
+use crate::pkg::ai::budget::budget::PromptBudget;
+use crate::pkg::ai::client::client::{ensure_success, post_json, post_streaming, send_delta, GenAIClient, GenAIClientSettings};
+use crate::pkg::ai::model::model::{AIProvider, Completion, InquiryRequest, Usage};
+use crate::pkg::ai::model::ollama::{OllamaChatRequest, OllamaChatResponse, OllamaOptions};
+use crate::pkg::ai::model::openai::Message;
+use crate::pkg::ai::model::openai::Role::{System, User};
+use crate::pkg::utils::http_fixture::http_fixture::HttpFixtures;
+
+/// Talks to Ollama's native chat API, so prompts never leave the machine running the model.
+#[derive(Clone)]
+pub struct OllamaClient {
+    settings: GenAIClientSettings,
+    reqwest_client: ReqwestClient,
+    fixtures: Option<HttpFixtures>,
+}
+
+impl OllamaClient {
+    pub fn new(settings: GenAIClientSettings, reqwest_client: ReqwestClient) -> Self {
+        Self {
+            settings,
+            reqwest_client,
+            fixtures: None,
+        }
+    }
+
+    /// Records responses to, or replays them from, `fixtures` instead of only using the network.
+    pub fn with_fixtures(self, fixtures: HttpFixtures) -> Self {
+        Self {
+            fixtures: Some(fixtures),
+            ..self
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/api/chat", self.settings.base_url.trim_end_matches('/'))
+    }
+
+    fn request_body(&self, req: InquiryRequest, stream: bool) -> OllamaChatRequest {
+        let mut messages: Vec<Message> = req.system.map(|content| Message { role: System, content }).into_iter().collect();
+        messages.extend(req.history);
+        messages.push(Message {
+            role: User,
+            content: req.content,
+        });
+        OllamaChatRequest {
+            model: self.settings.model.clone(),
+            messages,
+            stream,
+            options: self.settings.max_tokens.map(|num_predict| OllamaOptions { num_predict }),
+            format: req.response_schema.map(|s| s.schema),
+        }
+    }
+}
+
+impl GenAIClient for OllamaClient {
+    fn provider(&self) -> AIProvider {
+        AIProvider::Ollama
+    }
+
+    async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>> {
+        let request_body = self.request_body(req, false);
+        let res = post_json(&self.reqwest_client, self.fixtures.as_ref(), &self.url(), &headers(), serde_json::to_string(&request_body)?, self.settings.timeout).await?;
+        ensure_success(AIProvider::Ollama, &res)?;
+
+        let chat: OllamaChatResponse = serde_json::from_str(&res.text)?;
+        Ok(Completion {
+            content: chat.message.content.unwrap_or_default(),
+            finish_reason: chat.done_reason,
+            model: chat.model,
+            usage: Usage {
+                prompt_tokens: chat.prompt_eval_count,
+                completion_tokens: chat.eval_count,
+            },
+        })
+    }
+
+    async fn inquire_stream(&self, req: InquiryRequest, deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>> {
+        let request_body = self.request_body(req, true);
+        let mut completion = Completion {
+            content: String::new(),
+            finish_reason: None,
+            model: self.settings.model.clone(),
+            usage: Usage::default(),
+        };
+        let res = post_streaming(&self.reqwest_client, self.fixtures.as_ref(), &self.url(), &headers(), serde_json::to_string(&request_body)?, self.settings.timeout, |line| {
+            if line.trim().is_empty() {
+                return Ok(());
+            }
+            let chunk: OllamaChatResponse = serde_json::from_str(line)?;
+            completion.model = chunk.model;
+            if let Some(text) = chunk.message.content.filter(|t| !t.is_empty()) {
+                completion.content.push_str(&text);
+                send_delta(deltas, text)?;
+            }
+            if chunk.done {
+                completion.finish_reason = chunk.done_reason;
+                completion.usage = Usage {
+                    prompt_tokens: chunk.prompt_eval_count,
+                    completion_tokens: chunk.eval_count,
+                };
+            }
+            Ok(())
+        }).await?;
+        ensure_success(AIProvider::Ollama, &res)?;
+        Ok(completion)
+    }
+
+    fn prompt_budget(&self, _provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>> {
+        Ok(self.settings.prompt_budget(AIProvider::Ollama))
+    }
+
+    fn model(&self, _provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>> {
+        Ok((AIProvider::Ollama, self.settings.model.clone()))
+    }
+}
+
+fn headers() -> [(&'static str, String); 1] {
+    [("Content-Type", "application/json".to_string())]
+}
@@ -1,2 +1,7 @@
+pub mod budget;
 pub mod client;
-pub mod model;
\ No newline at end of file
+pub mod model;
+pub mod prompt;
+pub mod provider;
+#[cfg(test)]
+pub mod stub;
\ No newline at end of file
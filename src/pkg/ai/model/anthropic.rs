@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use crate::pkg::ai::model::openai::Message;
+
+/// Body of `POST /v1/messages`. Unlike OpenAI, `max_tokens` is required.
+#[derive(Serialize)]
+pub struct AnthropicMessagesRequest {
+    pub model: String,
+    pub max_tokens: u32,
+    /// The Messages API takes no system role in `messages`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<AnthropicTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<AnthropicToolChoice>,
+    /// Sends the answer as server-sent events
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+}
+
+/// A tool the model may call, with a JSON Schema for its input.
+#[derive(Serialize)]
+pub struct AnthropicTool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+/// `{"type": "tool", "name": ...}` makes the model call that tool instead of answering in text.
+#[derive(Serialize)]
+pub struct AnthropicToolChoice {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicMessagesResponse {
+    pub model: String,
+    pub content: Vec<AnthropicContentBlock>,
+    pub stop_reason: Option<String>,
+    pub usage: AnthropicUsage,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicContentBlock {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Only set on `text` blocks
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Only set on `tool_use` blocks
+    #[serde(default)]
+    pub input: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+}
+
+/// The `data:` of a streamed answer's events. Text and tool input arrive in
+/// `content_block_delta` events, the model in `message_start` and the stop reason in `message_delta`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicStreamEvent {
+    MessageStart {
+        message: AnthropicStreamMessage,
+    },
+    ContentBlockDelta {
+        delta: AnthropicDelta,
+    },
+    MessageDelta {
+        delta: AnthropicMessageDelta,
+        usage: AnthropicOutputUsage,
+    },
+    Error {
+        error: AnthropicError,
+    },
+    /// `ping`, `content_block_start`, `content_block_stop` and `message_stop`
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicStreamMessage {
+    pub model: String,
+    pub usage: AnthropicUsage,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicDelta {
+    TextDelta {
+        text: String,
+    },
+    /// A piece of a tool call's input, which only parses as JSON once complete
+    InputJsonDelta {
+        partial_json: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicMessageDelta {
+    pub stop_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicOutputUsage {
+    pub output_tokens: u32,
+}
+
+#[derive(Deserialize)]
+pub struct AnthropicError {
+    pub message: String,
+}
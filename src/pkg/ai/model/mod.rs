@@ -1 +1,4 @@
-pub mod openai;
\ No newline at end of file
+pub mod model;
+pub mod openai;
+pub mod anthropic;
+pub mod ollama;
\ No newline at end of file
@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::pkg::ai::model::openai::Message;
+
+/// API flavour of a generative AI backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AIProvider {
+    #[serde(rename = "openai")]
+    OpenAI,
+    Anthropic,
+    /// Ollama's native API
+    Ollama,
+    /// Any server speaking OpenAI's chat completions API, such as llama.cpp or vLLM
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
+}
+
+impl AIProvider {
+    /// API root used when none is configured; OpenAI-compatible servers have no canonical one.
+    pub fn default_base_url(&self) -> Option<&'static str> {
+        match self {
+            AIProvider::OpenAI => Some("https://api.openai.com/v1"),
+            AIProvider::Anthropic => Some("https://api.anthropic.com/v1"),
+            AIProvider::Ollama => Some("http://localhost:11434"),
+            AIProvider::OpenAICompatible => None,
+        }
+    }
+
+    /// Prompt token budget used when none is configured. Hosted models take far more, but
+    /// long prompts cost more and rarely help; Ollama runs models with a small context window
+    /// unless told otherwise.
+    pub fn default_max_prompt_tokens(&self) -> u32 {
+        match self {
+            AIProvider::Ollama => 3_000,
+            _ => 16_000,
+        }
+    }
+
+    /// Hosted APIs need a key; local servers usually run without one.
+    pub fn requires_api_key(&self) -> bool {
+        matches!(self, AIProvider::OpenAI | AIProvider::Anthropic)
+    }
+}
+
+impl Display for AIProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AIProvider::OpenAI => "openai",
+            AIProvider::Anthropic => "anthropic",
+            AIProvider::Ollama => "ollama",
+            AIProvider::OpenAICompatible => "openai_compatible",
+        })
+    }
+}
+
+impl FromStr for AIProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "openai" => Ok(AIProvider::OpenAI),
+            "anthropic" => Ok(AIProvider::Anthropic),
+            "ollama" => Ok(AIProvider::Ollama),
+            "openai_compatible" => Ok(AIProvider::OpenAICompatible),
+            _ => Err(format!("unknown AI provider {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InquiryRequest {
+    /// Provider to ask; the default one when unset
+    pub provider: Option<AIProvider>,
+    /// Instructions sent apart from `content`, where the API has a place for them
+    pub system: Option<String>,
+    /// Earlier user and assistant turns of a conversation, oldest first, sent before `content`
+    pub history: Vec<Message>,
+    pub content: String,
+    /// Constrains the answer to JSON following this schema
+    pub response_schema: Option<ResponseSchema>,
+}
+
+/// A JSON Schema for the answer. OpenAI and compatible servers enforce it through
+/// `response_format`, Ollama through `format` and Anthropic through a forced tool call.
+#[derive(Debug, Clone)]
+pub struct ResponseSchema {
+    /// Identifier for the schema, which OpenAI and Anthropic require
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
+/// A provider's answer, whichever API shape it came in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub content: String,
+    /// Why generation stopped, in the provider's words, e.g. `stop`, `length` or `end_turn`
+    pub finish_reason: Option<String>,
+    /// Model that answered, which may be more specific than the one requested
+    pub model: String,
+    pub usage: Usage,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+/// A 4xx or 5xx answer from the provider, with the message from its error body.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProviderError {
+    pub provider: AIProvider,
+    pub status: u16,
+    pub message: String,
+}
+
+impl Display for ProviderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} responded {}: {}", self.provider, self.status, self.message)
+    }
+}
+
+impl Error for ProviderError {}
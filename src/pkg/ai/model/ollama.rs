@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use crate::pkg::ai::model::openai::{Message, OpenAIResponseMessage};
+
+/// Body of Ollama's native `POST /api/chat`.
+#[derive(Serialize)]
+pub struct OllamaChatRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    /// Ollama streams by default, as one JSON object per line
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<OllamaOptions>,
+    /// `"json"` or a JSON Schema the answer must follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+pub struct OllamaOptions {
+    /// Ollama's name for the completion token limit
+    pub num_predict: u32,
+}
+
+#[derive(Deserialize)]
+pub struct OllamaChatResponse {
+    pub model: String,
+    pub message: OpenAIResponseMessage,
+    /// False on all but the last object of a stream
+    #[serde(default)]
+    pub done: bool,
+    pub done_reason: Option<String>,
+    /// Left out when the prompt was cached
+    #[serde(default)]
+    pub prompt_eval_count: u32,
+    #[serde(default)]
+    pub eval_count: u32,
+}
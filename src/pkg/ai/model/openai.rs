@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
+/// Status and body of a provider's HTTP response, before parsing.
 #[derive(Deserialize)]
 pub struct GPTResponse {
     pub status: u16,
@@ -9,7 +11,38 @@ pub struct GPTResponse {
 #[derive(Serialize)]
 pub struct OpenAIInquiryRequest {
     pub model: String,
-    pub messages: Vec<Message>
+    pub messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<OpenAIResponseFormat>,
+    /// Sends the answer as server-sent `chat.completion.chunk` events
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAIStreamOptions>,
+}
+
+#[derive(Serialize)]
+pub struct OpenAIStreamOptions {
+    /// Adds a last chunk with the usage, which streams otherwise leave out
+    pub include_usage: bool,
+}
+
+/// `{"type": "json_schema", "json_schema": {...}}`
+#[derive(Serialize)]
+pub struct OpenAIResponseFormat {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub json_schema: OpenAIJsonSchema,
+}
+
+#[derive(Serialize)]
+pub struct OpenAIJsonSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+    /// Makes the model follow the schema exactly instead of on a best-effort basis
+    pub strict: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -18,10 +51,56 @@ pub struct Message {
     pub content: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Role {
     System,
     User,
     Assistant,
 }
+
+#[derive(Deserialize)]
+pub struct OpenAIChatResponse {
+    pub model: String,
+    pub choices: Vec<OpenAIChoice>,
+    /// Some compatible servers leave usage out
+    #[serde(default)]
+    pub usage: Option<OpenAIUsage>,
+}
+
+#[derive(Deserialize)]
+pub struct OpenAIChoice {
+    pub message: OpenAIResponseMessage,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct OpenAIResponseMessage {
+    /// Null when the model only called tools
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// One `data:` event of a streamed answer.
+#[derive(Deserialize)]
+pub struct OpenAIChatChunk {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub choices: Vec<OpenAIChunkChoice>,
+    /// Only on the last chunk, when asked for with `include_usage`
+    #[serde(default)]
+    pub usage: Option<OpenAIUsage>,
+}
+
+#[derive(Deserialize)]
+pub struct OpenAIChunkChoice {
+    pub delta: OpenAIResponseMessage,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct OpenAIUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
@@ -0,0 +1 @@
+pub mod template;
\ No newline at end of file
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::pkg::ai::budget::budget::{Prompt, PromptSection, SectionShape};
+use crate::pkg::utils::validation::validation::ValidationErrors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableKind {
+    /// Substituted for `{{name}}` in the system and user parts
+    Text,
+    /// Like `text`, but has to parse as a number
+    Number,
+    /// Context section appended to the user part, shortened by leaving out `#` sections
+    Document,
+    /// Context section appended to the user part, shortened by leaving out entries from the end
+    List,
+}
+
+impl VariableKind {
+    fn shape(&self) -> Option<SectionShape> {
+        match self {
+            VariableKind::Document => Some(SectionShape::Document),
+            VariableKind::List => Some(SectionShape::List),
+            VariableKind::Text | VariableKind::Number => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: VariableKind,
+    #[serde(default)]
+    pub description: String,
+    /// Rendering fails without a value; optional sections are otherwise left out
+    #[serde(default)]
+    pub required: bool,
+    /// Allowed values; any when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+    /// Used when no value is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Share of the prompt budget a section is guaranteed, relative to the other sections
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Which template produced a prompt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TemplateRef {
+    pub name: String,
+    pub version: u32,
+}
+
+/// A named, versioned prompt, read from TOML:
+///
+/// ```toml
+/// name = "how_to_contribute"
+/// version = 1
+/// system = "You help newcomers to {{repo}}."
+/// user = "How do I contribute to {{owner}}/{{repo}}?"
+///
+/// [[variables]]
+/// name = "readme"
+/// type = "document"
+/// required = true
+/// ```
+///
+/// Section variables follow the user part as `<name>...</name>`, in declaration order, which is
+/// also the order they get what other sections leave of the budget.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: u32,
+    #[serde(default)]
+    pub description: String,
+    /// Sent as the system message; none when empty
+    #[serde(default)]
+    pub system: String,
+    pub user: String,
+    #[serde(default)]
+    pub variables: Vec<TemplateVariable>,
+}
+
+impl PromptTemplate {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let template: PromptTemplate = toml::from_str(text).map_err(|e| e.to_string())?;
+        template.validate()?;
+        Ok(template)
+    }
+
+    pub fn id(&self) -> TemplateRef {
+        TemplateRef {
+            name: self.name.clone(),
+            version: self.version,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is empty".to_string());
+        }
+        if self.version == 0 {
+            return Err(format!("{}: version has to be 1 or more", self.name));
+        }
+        let mut names = HashSet::new();
+        for variable in &self.variables {
+            if !names.insert(variable.name.as_str()) {
+                return Err(format!("{} v{}: variable {} is declared twice", self.name, self.version, variable.name));
+            }
+            if let Some(default) = &variable.default {
+                check_value(variable, default).map_err(|e| format!("{} v{}: default of {} {}", self.name, self.version, variable.name, e))?;
+            }
+            if variable.kind.shape().is_some() && variable.weight == 0 {
+                return Err(format!("{} v{}: weight of {} has to be 1 or more", self.name, self.version, variable.name));
+            }
+        }
+        for part in [&self.system, &self.user] {
+            for placeholder in placeholders(part)? {
+                match self.variable(placeholder) {
+                    None => return Err(format!("{} v{}: {{{{{}}}}} is not a declared variable", self.name, self.version, placeholder)),
+                    Some(v) if v.kind.shape().is_some() => {
+                        return Err(format!("{} v{}: {} is a section and cannot be substituted", self.name, self.version, placeholder))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn variable(&self, name: &str) -> Option<&TemplateVariable> {
+        self.variables.iter().find(|v| v.name == name)
+    }
+
+    /// Fills in the variables from `values`; values of undeclared variables are ignored, so
+    /// callers can offer every value they have to any version of a template.
+    pub fn render(&self, values: &HashMap<&str, String>) -> Result<Prompt, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let mut inline = HashMap::new();
+        let mut sections = vec![];
+        for variable in &self.variables {
+            let value = values.get(variable.name.as_str()).or(variable.default.as_ref());
+            let Some(value) = value else {
+                if variable.required {
+                    errors.add(&variable.name, "is required");
+                }
+                continue;
+            };
+            if let Err(e) = check_value(variable, value) {
+                errors.add(&variable.name, e);
+                continue;
+            }
+            match variable.kind.shape() {
+                Some(shape) => sections.push(PromptSection {
+                    name: variable.name.clone(),
+                    text: value.clone(),
+                    shape,
+                    weight: variable.weight,
+                }),
+                None => {
+                    inline.insert(variable.name.as_str(), value.as_str());
+                }
+            }
+        }
+        let system = substitute(&self.system, &inline, &mut errors);
+        let instructions = substitute(&self.user, &inline, &mut errors);
+        errors.into_result(Prompt {
+            system: Some(system).filter(|s| !s.trim().is_empty()),
+            instructions,
+            sections,
+        })
+    }
+}
+
+fn check_value(variable: &TemplateVariable, value: &str) -> Result<(), String> {
+    if !variable.values.is_empty() && !variable.values.iter().any(|v| v == value) {
+        return Err(format!("has to be one of {}", variable.values.join(", ")));
+    }
+    if variable.kind == VariableKind::Number && value.trim().parse::<f64>().is_err() {
+        return Err(format!("has to be a number, not {:?}", value));
+    }
+    Ok(())
+}
+
+/// Names in `{{name}}` placeholders, in order.
+fn placeholders(text: &str) -> Result<Vec<&str>, String> {
+    let mut names = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or_else(|| format!("unclosed placeholder in {:?}", &rest[start..]))?;
+        names.push(rest[start + 2..start + end].trim());
+        rest = &rest[start + end + 2..];
+    }
+    Ok(names)
+}
+
+/// Placeholders of variables without a value render empty, after recording the error.
+fn substitute(text: &str, values: &HashMap<&str, &str>, errors: &mut ValidationErrors) -> String {
+    let mut rendered = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        // Placeholders were checked when the template was loaded.
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else { break };
+        let name = rest[start + 2..end].trim();
+        rendered.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) => rendered.push_str(value),
+            None if errors.errors.iter().any(|e| e.field == name) => {}
+            None => errors.add(name, "is required by the template"),
+        }
+        rest = &rest[end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Templates available to requests, by name and version.
+#[derive(Debug, Clone, Default)]
+pub struct PromptTemplates {
+    /// Sorted by name, then version
+    templates: Vec<PromptTemplate>,
+}
+
+impl PromptTemplates {
+    pub fn new(mut templates: Vec<PromptTemplate>) -> Result<Self, String> {
+        templates.sort_by(|a, b| (&a.name, a.version).cmp(&(&b.name, b.version)));
+        if let Some(pair) = templates.windows(2).find(|pair| pair[0].id() == pair[1].id()) {
+            return Err(format!("{} v{} is defined twice", pair[0].name, pair[0].version));
+        }
+        Ok(Self { templates })
+    }
+
+    /// Reads the `builtin` template texts, then every `.toml` file under `dir`, which may add
+    /// templates or versions but not replace a built-in one.
+    pub fn load(builtin: &[&str], dir: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let mut templates = vec![];
+        for text in builtin {
+            templates.push(PromptTemplate::parse(text).map_err(|e| format!("built-in prompt template: {}", e))?);
+        }
+        if let Some(dir) = dir {
+            let mut paths = vec![];
+            toml_files(dir, &mut paths).map_err(|e| format!("{}: {}", dir.display(), e))?;
+            for path in paths {
+                let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                templates.push(PromptTemplate::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?);
+            }
+        }
+        Ok(Self::new(templates)?)
+    }
+
+    pub fn list(&self) -> &[PromptTemplate] {
+        &self.templates
+    }
+
+    /// The given version of `name`, or its latest version when `None`.
+    pub fn get(&self, name: &str, version: Option<u32>) -> Option<&PromptTemplate> {
+        let mut versions = self.templates.iter().filter(|t| t.name == name);
+        match version {
+            Some(version) => versions.find(|t| t.version == version),
+            None => versions.next_back(),
+        }
+    }
+}
+
+fn toml_files(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?.map(|e| e.map(|e| e.path())).collect::<Result<_, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            toml_files(&path, paths)?;
+        } else if path.extension().is_some_and(|ext| ext == "toml") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::pkg::ai::budget::budget::SectionShape;
+    use crate::pkg::ai::prompt::template::{PromptTemplate, PromptTemplates};
+
+    const TEMPLATE: &str = r#"
+        name = "greeting"
+        version = 2
+        system = "Answer for a {{ level }} reader."
+        user = "Introduce {{repo}} in {{sentences}} sentences."
+
+        [[variables]]
+        name = "repo"
+        type = "text"
+        required = true
+
+        [[variables]]
+        name = "level"
+        type = "text"
+        values = ["beginner", "advanced"]
+        default = "beginner"
+
+        [[variables]]
+        name = "sentences"
+        type = "number"
+        default = "3"
+
+        [[variables]]
+        name = "readme"
+        type = "document"
+        weight = 3
+
+        [[variables]]
+        name = "files"
+        type = "list"
+    "#;
+
+    #[test]
+    fn test_render_substitutes_text_and_appends_sections() {
+        let template = PromptTemplate::parse(TEMPLATE).unwrap();
+        let values = HashMap::from([
+            ("repo", "octo-repo".to_string()),
+            ("readme", "# Octo".to_string()),
+            ("unused", "ignored".to_string()),
+        ]);
+
+        let prompt = template.render(&values).unwrap();
+        assert_eq!(prompt.system.as_deref(), Some("Answer for a beginner reader."));
+        assert_eq!(prompt.instructions, "Introduce octo-repo in 3 sentences.");
+        // `files` has no value and is optional, so it is left out.
+        assert_eq!(prompt.sections.len(), 1);
+        assert_eq!(prompt.sections[0].name, "readme");
+        assert_eq!(prompt.sections[0].shape, SectionShape::Document);
+        assert_eq!(prompt.sections[0].weight, 3);
+
+        let values = HashMap::from([("level", "expert".to_string()), ("sentences", "a few".to_string())]);
+        let errors = template.render(&values).unwrap_err();
+        let fields: Vec<&str> = errors.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["repo", "level", "sentences"]);
+    }
+
+    #[test]
+    fn test_load_rejects_bad_templates_and_picks_the_latest_version() {
+        let undeclared = TEMPLATE.replace("{{repo}}", "{{project}}");
+        assert!(PromptTemplate::parse(&undeclared).unwrap_err().contains("{{project}} is not a declared variable"));
+        let section = TEMPLATE.replace("{{repo}}", "{{readme}}");
+        assert!(PromptTemplate::parse(&section).unwrap_err().contains("readme is a section"));
+        let bad_default = TEMPLATE.replace(r#"default = "beginner""#, r#"default = "expert""#);
+        assert!(PromptTemplate::parse(&bad_default).is_err());
+
+        let v1 = TEMPLATE.replace("version = 2", "version = 1");
+        let templates = PromptTemplates::load(&[TEMPLATE, &v1], None).unwrap();
+        assert_eq!(templates.get("greeting", None).unwrap().version, 2);
+        assert_eq!(templates.get("greeting", Some(1)).unwrap().version, 1);
+        assert!(templates.get("greeting", Some(3)).is_none());
+        assert!(PromptTemplates::load(&[TEMPLATE, TEMPLATE], None).unwrap_err().to_string().contains("defined twice"));
+    }
+}
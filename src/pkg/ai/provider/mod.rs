@@ -0,0 +1 @@
+pub mod provider;
\ No newline at end of file
@@ -0,0 +1,111 @@
+use std::error::Error;
+use futures::channel::mpsc::UnboundedSender;
+use crate::pkg::ai::budget::budget::PromptBudget;
+use crate::pkg::ai::client::anthropic::AnthropicClient;
+use crate::pkg::ai::client::client::{GenAIClient, OpenAIClient};
+use crate::pkg::ai::client::ollama::OllamaClient;
+use crate::pkg::ai::model::model::{AIProvider, Completion, InquiryRequest};
+use crate::pkg::utils::validation::validation::ValidationErrors;
+
+/// Any provider's `GenAIClient`, so differently shaped APIs can be configured side by side.
+#[derive(Clone)]
+pub enum AIProviderClient {
+    /// OpenAI itself or an OpenAI-compatible server
+    OpenAI(OpenAIClient),
+    Anthropic(AnthropicClient),
+    Ollama(OllamaClient),
+}
+
+impl GenAIClient for AIProviderClient {
+    fn provider(&self) -> AIProvider {
+        match self {
+            AIProviderClient::OpenAI(client) => client.provider(),
+            AIProviderClient::Anthropic(client) => client.provider(),
+            AIProviderClient::Ollama(client) => client.provider(),
+        }
+    }
+
+    async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>> {
+        match self {
+            AIProviderClient::OpenAI(client) => client.inquire(req).await,
+            AIProviderClient::Anthropic(client) => client.inquire(req).await,
+            AIProviderClient::Ollama(client) => client.inquire(req).await,
+        }
+    }
+
+    async fn inquire_stream(&self, req: InquiryRequest, deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>> {
+        match self {
+            AIProviderClient::OpenAI(client) => client.inquire_stream(req, deltas).await,
+            AIProviderClient::Anthropic(client) => client.inquire_stream(req, deltas).await,
+            AIProviderClient::Ollama(client) => client.inquire_stream(req, deltas).await,
+        }
+    }
+
+    fn prompt_budget(&self, provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>> {
+        match self {
+            AIProviderClient::OpenAI(client) => client.prompt_budget(provider),
+            AIProviderClient::Anthropic(client) => client.prompt_budget(provider),
+            AIProviderClient::Ollama(client) => client.prompt_budget(provider),
+        }
+    }
+
+    fn model(&self, provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>> {
+        match self {
+            AIProviderClient::OpenAI(client) => client.model(provider),
+            AIProviderClient::Anthropic(client) => client.model(provider),
+            AIProviderClient::Ollama(client) => client.model(provider),
+        }
+    }
+}
+
+/// Routes each inquiry to the client of the requested provider, or to the first client when
+/// the request names none.
+#[derive(Clone)]
+pub struct MultiProviderClient<C: GenAIClient> {
+    clients: Vec<C>,
+}
+
+impl<C: GenAIClient> MultiProviderClient<C> {
+    pub fn new(clients: Vec<C>) -> Self {
+        Self {
+            clients,
+        }
+    }
+
+    fn client(&self, provider: Option<AIProvider>) -> Result<&C, Box<dyn Error>> {
+        let client = match provider {
+            Some(provider) => self.clients.iter().find(|c| c.provider() == provider),
+            None => self.clients.first(),
+        };
+        client.ok_or_else(|| {
+            let mut errors = ValidationErrors::new();
+            errors.add("provider", match provider {
+                Some(provider) => format!("{} is not configured", provider),
+                None => "no AI provider is configured".to_string(),
+            });
+            errors.into()
+        })
+    }
+}
+
+impl<C: GenAIClient> GenAIClient for MultiProviderClient<C> {
+    fn provider(&self) -> AIProvider {
+        self.clients.first().map(|c| c.provider()).unwrap_or(AIProvider::OpenAI)
+    }
+
+    async fn inquire(&self, req: InquiryRequest) -> Result<Completion, Box<dyn Error>> {
+        self.client(req.provider)?.inquire(req).await
+    }
+
+    async fn inquire_stream(&self, req: InquiryRequest, deltas: &UnboundedSender<String>) -> Result<Completion, Box<dyn Error>> {
+        self.client(req.provider)?.inquire_stream(req, deltas).await
+    }
+
+    fn prompt_budget(&self, provider: Option<AIProvider>) -> Result<PromptBudget, Box<dyn Error>> {
+        self.client(provider)?.prompt_budget(provider)
+    }
+
+    fn model(&self, provider: Option<AIProvider>) -> Result<(AIProvider, String), Box<dyn Error>> {
+        self.client(provider)?.model(provider)
+    }
+}
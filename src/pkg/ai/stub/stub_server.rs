@@ -0,0 +1,303 @@
+//! A stand-in for generative AI APIs that answers prompts with canned replies.
+//!
+//! One server speaks all supported API shapes, so each provider's client can point at it:
+//!
+//! - `POST /v1/chat/completions`, OpenAI and OpenAI-compatible servers (base URL `{root}/v1`)
+//! - `POST /v1/messages`, Anthropic (base URL `{root}/v1`)
+//! - `POST /api/chat`, Ollama (base URL `{root}`)
+//!
+//! Answers given with `set_answers` are used in order, the last one for every later request.
+//! Anthropic requests that force a tool call get the answer, parsed as JSON, as the tool input.
+//! After `fail_with`, every endpoint answers with that status and an error body in its API's shape.
+//! Requests with `"stream": true` get the answer in pieces, as server-sent events for OpenAI and
+//! Anthropic and as JSON lines for Ollama, `set_stream_delay` apart; `streamed_events` counts
+//! what was sent, so tests can tell when a client stopped reading.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_web::dev::Server;
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+/// A contribution guide, since that is what the server asks for.
+pub const DEFAULT_ANSWER: &str = r#"{
+  "skills": [{"name": "Rust", "why": "The project is written in Rust.", "links": ["https://doc.rust-lang.org/book/"]}],
+  "recommended_issues": [{"url": "https://github.com/octo-org/octo-repo/issues/12", "why": "It is labelled good first issue.", "difficulty": "easy"}],
+  "files": [{"path": "src/", "why": "All of the code lives here."}],
+  "setup_steps": ["cargo test --all-features"],
+  "caveats": ["Run clippy before opening a pull request."]
+}"#;
+/// Characters of the answer per streamed piece.
+const STREAM_PIECE_CHARS: usize = 40;
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Value,
+}
+
+struct StubState {
+    requests: Mutex<Vec<RecordedRequest>>,
+    failure: Mutex<Option<(u16, String)>>,
+    answers: Mutex<VecDeque<String>>,
+    stream_delay: Mutex<Duration>,
+    streamed_events: AtomicUsize,
+}
+
+#[derive(Clone)]
+pub struct AIStubHandle {
+    state: web::Data<StubState>,
+    /// Server root, without a version prefix
+    pub base_url: String,
+}
+
+impl AIStubHandle {
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    pub fn set_answers(&self, answers: &[&str]) {
+        *self.state.answers.lock().unwrap() = answers.iter().map(|a| a.to_string()).collect();
+    }
+
+    pub fn fail_with(&self, status: u16, message: &str) {
+        *self.state.failure.lock().unwrap() = Some((status, message.to_string()));
+    }
+
+    /// Pause before each event of a streamed answer.
+    pub fn set_stream_delay(&self, delay: Duration) {
+        *self.state.stream_delay.lock().unwrap() = delay;
+    }
+
+    /// Events of streamed answers sent so far, over all requests.
+    pub fn streamed_events(&self) -> usize {
+        self.state.streamed_events.load(Ordering::SeqCst)
+    }
+}
+
+pub fn start_ai_stub_server(listener: TcpListener) -> io::Result<(Server, AIStubHandle)> {
+    let base_url = format!("http://{}", listener.local_addr()?);
+    let state = web::Data::new(StubState {
+        requests: Mutex::new(vec![]),
+        failure: Mutex::new(None),
+        answers: Mutex::new(VecDeque::from([DEFAULT_ANSWER.to_string()])),
+        stream_delay: Mutex::new(Duration::ZERO),
+        streamed_events: AtomicUsize::new(0),
+    });
+
+    let app_state = state.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_state.clone())
+            .route("/v1/chat/completions", web::post().to(openai_handler))
+            .route("/v1/messages", web::post().to(anthropic_handler))
+            .route("/api/chat", web::post().to(ollama_handler))
+    })
+        .workers(1)
+        .listen(listener)?
+        .run();
+
+    Ok((server, AIStubHandle { state, base_url }))
+}
+
+/// Records the request and returns the model it asked for with the answer to give, or the
+/// configured failure.
+fn record(state: &StubState, req: &HttpRequest, body: Value) -> Result<(String, String), (StatusCode, String)> {
+    let model = body["model"].as_str().unwrap_or_default().to_string();
+    state.requests.lock().unwrap().push(RecordedRequest {
+        path: req.path().to_string(),
+        headers: req.headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect(),
+        body,
+    });
+    if let Some((status, message)) = state.failure.lock().unwrap().clone() {
+        return Err((StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR), message));
+    }
+    let mut answers = state.answers.lock().unwrap();
+    let answer = if answers.len() > 1 { answers.pop_front() } else { answers.front().cloned() };
+    Ok((model, answer.unwrap_or_default()))
+}
+
+/// `answer` cut into pieces of `STREAM_PIECE_CHARS`, which together are the answer again.
+fn pieces(answer: &str) -> Vec<String> {
+    let chars: Vec<char> = answer.chars().collect();
+    chars.chunks(STREAM_PIECE_CHARS).map(|piece| piece.iter().collect()).collect()
+}
+
+/// Sends `events` one by one, each after the configured delay, counting them as they go.
+fn stream(state: web::Data<StubState>, content_type: &str, events: Vec<String>) -> HttpResponse {
+    let delay = *state.stream_delay.lock().unwrap();
+    let events = futures::stream::iter(events).then(move |event| {
+        let state = state.clone();
+        async move {
+            tokio::time::sleep(delay).await;
+            state.streamed_events.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, actix_web::Error>(Bytes::from(event))
+        }
+    });
+    HttpResponse::Ok().content_type(content_type).streaming(events)
+}
+
+fn sse(event: Option<&str>, data: &Value) -> String {
+    match event {
+        Some(event) => format!("event: {}\ndata: {}\n\n", event, data),
+        None => format!("data: {}\n\n", data),
+    }
+}
+
+async fn openai_handler(state: web::Data<StubState>, req: HttpRequest, body: web::Json<Value>) -> HttpResponse {
+    let body = body.into_inner();
+    let streaming = body["stream"] == true;
+    let include_usage = body["stream_options"]["include_usage"] == true;
+    let (model, answer) = match record(&state, &req, body) {
+        Ok(answer) => answer,
+        Err((status, message)) => return HttpResponse::build(status).json(json!({
+            "error": {"message": message, "type": "invalid_request_error", "code": null},
+        })),
+    };
+    if streaming {
+        let chunk = |delta: Value, finish_reason: Value| sse(None, &json!({
+            "id": "chatcmpl-stub",
+            "object": "chat.completion.chunk",
+            "created": 1_700_000_000,
+            "model": model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        }));
+        let mut events = vec![chunk(json!({"role": "assistant", "content": ""}), Value::Null)];
+        events.extend(pieces(&answer).into_iter().map(|piece| chunk(json!({"content": piece}), Value::Null)));
+        events.push(chunk(json!({}), json!("stop")));
+        if include_usage {
+            events.push(sse(None, &json!({
+                "id": "chatcmpl-stub",
+                "object": "chat.completion.chunk",
+                "created": 1_700_000_000,
+                "model": model,
+                "choices": [],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 8, "total_tokens": 20},
+            })));
+        }
+        events.push("data: [DONE]\n\n".to_string());
+        return stream(state, "text/event-stream", events);
+    }
+    HttpResponse::Ok().json(json!({
+        "id": "chatcmpl-stub",
+        "object": "chat.completion",
+        "created": 1_700_000_000,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": answer},
+            "finish_reason": "stop",
+        }],
+        "usage": {"prompt_tokens": 12, "completion_tokens": 8, "total_tokens": 20},
+    }))
+}
+
+async fn anthropic_handler(state: web::Data<StubState>, req: HttpRequest, body: web::Json<Value>) -> HttpResponse {
+    let body = body.into_inner();
+    let streaming = body["stream"] == true;
+    let tool = body["tool_choice"]["name"].as_str().map(|name| name.to_string());
+    let (model, answer) = match record(&state, &req, body) {
+        Ok(answer) => answer,
+        Err((status, message)) => return HttpResponse::build(status).json(json!({
+            "type": "error",
+            "error": {"type": "api_error", "message": message},
+        })),
+    };
+    if streaming {
+        // Tool input arrives as pieces of its JSON text.
+        let (block, delta, stop_reason) = match (tool.as_deref(), serde_json::from_str::<Value>(&answer)) {
+            (Some(name), Ok(_)) => (json!({"type": "tool_use", "id": "toolu_stub", "name": name, "input": {}}), ("input_json_delta", "partial_json"), "tool_use"),
+            _ => (json!({"type": "text", "text": ""}), ("text_delta", "text"), "end_turn"),
+        };
+        let mut events = vec![
+            sse(Some("message_start"), &json!({"type": "message_start", "message": {
+                "id": "msg_stub",
+                "type": "message",
+                "role": "assistant",
+                "model": model,
+                "content": [],
+                "stop_reason": null,
+                "usage": {"input_tokens": 12, "output_tokens": 1},
+            }})),
+            sse(Some("content_block_start"), &json!({"type": "content_block_start", "index": 0, "content_block": block})),
+            sse(Some("ping"), &json!({"type": "ping"})),
+        ];
+        events.extend(pieces(&answer).into_iter().map(|piece| sse(Some("content_block_delta"), &json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": delta.0, delta.1: piece},
+        }))));
+        events.extend([
+            sse(Some("content_block_stop"), &json!({"type": "content_block_stop", "index": 0})),
+            sse(Some("message_delta"), &json!({
+                "type": "message_delta",
+                "delta": {"stop_reason": stop_reason, "stop_sequence": null},
+                "usage": {"output_tokens": 8},
+            })),
+            sse(Some("message_stop"), &json!({"type": "message_stop"})),
+        ]);
+        return stream(state, "text/event-stream", events);
+    }
+    let (content, stop_reason) = match (tool, serde_json::from_str::<Value>(&answer)) {
+        (Some(name), Ok(input)) => (json!({"type": "tool_use", "id": "toolu_stub", "name": name, "input": input}), "tool_use"),
+        _ => (json!({"type": "text", "text": answer}), "end_turn"),
+    };
+    HttpResponse::Ok().json(json!({
+        "id": "msg_stub",
+        "type": "message",
+        "role": "assistant",
+        "model": model,
+        "content": [content],
+        "stop_reason": stop_reason,
+        "usage": {"input_tokens": 12, "output_tokens": 8},
+    }))
+}
+
+async fn ollama_handler(state: web::Data<StubState>, req: HttpRequest, body: web::Json<Value>) -> HttpResponse {
+    let body = body.into_inner();
+    let streaming = body["stream"] == true;
+    let (model, answer) = match record(&state, &req, body) {
+        Ok(answer) => answer,
+        Err((status, message)) => return HttpResponse::build(status).json(json!({"error": message})),
+    };
+    if streaming {
+        let mut events: Vec<String> = pieces(&answer).into_iter()
+            .map(|piece| format!("{}\n", json!({
+                "model": model,
+                "created_at": "2024-09-07T00:00:00Z",
+                "message": {"role": "assistant", "content": piece},
+                "done": false,
+            })))
+            .collect();
+        events.push(format!("{}\n", json!({
+            "model": model,
+            "created_at": "2024-09-07T00:00:00Z",
+            "message": {"role": "assistant", "content": ""},
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 8,
+        })));
+        return stream(state, "application/x-ndjson", events);
+    }
+    HttpResponse::Ok().json(json!({
+        "model": model,
+        "created_at": "2024-09-07T00:00:00Z",
+        "message": {"role": "assistant", "content": answer},
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 12,
+        "eval_count": 8,
+    }))
+}
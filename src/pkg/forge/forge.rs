@@ -0,0 +1,99 @@
+use std::error::Error;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::pkg::gitea::client::client::GiteaClient;
+use crate::pkg::gitea::repositories::GiteaRepositoryClient;
+use crate::pkg::github::client::client::GithubClient;
+use crate::pkg::github::model::model::{FileTree, Issue, ReadmeResponse, Repositories, SearchIssuesRequest, SearchRepositoriesRequest};
+use crate::pkg::github::repositories::{GithubRepositoryClient, RepositoryClient};
+use crate::pkg::gitlab::client::client::GitlabClient;
+use crate::pkg::gitlab::repositories::GitlabRepositoryClient;
+
+/// The kind of code hosting software a repository lives on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    #[default]
+    Github,
+    Gitlab,
+    /// Gitea and its fork Forgejo, which runs Codeberg
+    Gitea,
+}
+
+impl Forge {
+    /// As stored with crawled repositories.
+    #[allow(dead_code)]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Forge::Github => "github",
+            Forge::Gitlab => "gitlab",
+            Forge::Gitea => "gitea",
+        }
+    }
+}
+
+/// Any forge's `RepositoryClient`, so hosts of different forges can be searched together.
+#[derive(Clone)]
+pub enum ForgeRepositoryClient {
+    Github(GithubRepositoryClient<GithubClient>),
+    Gitlab(GitlabRepositoryClient<GitlabClient>),
+    Gitea(GiteaRepositoryClient<GiteaClient>),
+}
+
+impl RepositoryClient for ForgeRepositoryClient {
+    fn host(&self, host: Option<&str>) -> Result<String, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.host(host),
+            ForgeRepositoryClient::Gitlab(client) => client.host(host),
+            ForgeRepositoryClient::Gitea(client) => client.host(host),
+        }
+    }
+
+    async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.fetch_repositories(req).await,
+            ForgeRepositoryClient::Gitlab(client) => client.fetch_repositories(req).await,
+            ForgeRepositoryClient::Gitea(client) => client.fetch_repositories(req).await,
+        }
+    }
+
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.fetch_top_readme(host, owner_name, repository_name).await,
+            ForgeRepositoryClient::Gitlab(client) => client.fetch_top_readme(host, owner_name, repository_name).await,
+            ForgeRepositoryClient::Gitea(client) => client.fetch_top_readme(host, owner_name, repository_name).await,
+        }
+    }
+
+    async fn fetch_top_readmes(&self, host: Option<&str>, repositories: &[(String, String)]) -> Result<Option<Vec<Option<ReadmeResponse>>>, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.fetch_top_readmes(host, repositories).await,
+            ForgeRepositoryClient::Gitlab(client) => client.fetch_top_readmes(host, repositories).await,
+            ForgeRepositoryClient::Gitea(client) => client.fetch_top_readmes(host, repositories).await,
+        }
+    }
+
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.fetch_issues(host, owner_name, repository_name, req).await,
+            ForgeRepositoryClient::Gitlab(client) => client.fetch_issues(host, owner_name, repository_name, req).await,
+            ForgeRepositoryClient::Gitea(client) => client.fetch_issues(host, owner_name, repository_name, req).await,
+        }
+    }
+
+    async fn fetch_contributing(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.fetch_contributing(host, owner_name, repository_name).await,
+            ForgeRepositoryClient::Gitlab(client) => client.fetch_contributing(host, owner_name, repository_name).await,
+            ForgeRepositoryClient::Gitea(client) => client.fetch_contributing(host, owner_name, repository_name).await,
+        }
+    }
+
+    async fn fetch_file_tree(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>> {
+        match self {
+            ForgeRepositoryClient::Github(client) => client.fetch_file_tree(host, owner_name, repository_name).await,
+            ForgeRepositoryClient::Gitlab(client) => client.fetch_file_tree(host, owner_name, repository_name).await,
+            ForgeRepositoryClient::Gitea(client) => client.fetch_file_tree(host, owner_name, repository_name).await,
+        }
+    }
+}
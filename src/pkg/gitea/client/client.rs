@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use reqwest::{Client as ReqwestClient, StatusCode};
+use crate::pkg::github::client::client::host_of;
+use crate::pkg::utils::http_fixture::http_fixture::{HttpFixtures, RecordedResponse};
+
+pub trait GiteaApiClient {
+    async fn get(&self, url: &str) -> Result<GiteaClientResponse, Box<dyn Error>>;
+    /// API root every request URL is built from, e.g. `https://codeberg.org/api/v1`, without
+    /// a trailing slash.
+    fn base_url(&self) -> &str;
+    /// Host repositories fetched through this client are tagged with, e.g. `codeberg.org`.
+    fn host(&self) -> &str;
+}
+
+#[derive(Debug)]
+pub struct GiteaClientResponse {
+    pub text: String,
+    pub status: StatusCode,
+    /// `X-Total-Count`, which list endpoints set to the number of matches across all pages
+    pub total_count: Option<u32>,
+}
+
+#[derive(Clone)]
+pub struct GiteaClient {
+    /// Access token; public repositories can be read without one
+    token: Option<String>,
+    base_url: String,
+    host: String,
+    reqwest_client: ReqwestClient,
+    fixtures: Option<HttpFixtures>,
+}
+
+impl GiteaClient {
+    pub fn new(token: Option<String>, base_url: &str, reqwest_client: ReqwestClient) -> Result<Self, Box<dyn Error>> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let host = host_of(&base_url)?;
+        Ok(Self {
+            token: token.filter(|t| !t.is_empty()),
+            base_url,
+            host,
+            reqwest_client,
+            fixtures: None,
+        })
+    }
+
+    /// Records responses to, or replays them from, `fixtures` instead of only using the network.
+    pub fn with_fixtures(self, fixtures: HttpFixtures) -> Self {
+        Self {
+            fixtures: Some(fixtures),
+            ..self
+        }
+    }
+}
+
+fn total_count(headers: &BTreeMap<String, String>) -> Option<u32> {
+    headers.get("x-total-count").and_then(|v| v.parse().ok())
+}
+
+impl GiteaApiClient for GiteaClient {
+    async fn get(&self, url: &str) -> Result<GiteaClientResponse, Box<dyn Error>> {
+        if let Some(fixtures) = self.fixtures.as_ref().filter(|f| f.is_replay()) {
+            let recorded = fixtures.replay("GET", url, None)?;
+            return Ok(GiteaClientResponse {
+                total_count: total_count(&recorded.headers),
+                text: recorded.body,
+                status: StatusCode::from_u16(recorded.status)?,
+            });
+        }
+
+        let mut headers = vec![
+            ("User-Agent", "rust-api-client".to_string()),
+            ("Accept", "application/json".to_string()),
+        ];
+        if let Some(token) = &self.token {
+            headers.push(("Authorization", format!("token {}", token)));
+        }
+        let mut req = self.reqwest_client.get(url);
+        for (name, value) in &headers {
+            req = req.header(*name, value);
+        }
+        let res = req.send().await?;
+        let status = res.status();
+        let response_headers: BTreeMap<String, String> = res.headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect();
+        let text = res.text().await?;
+
+        if let Some(fixtures) = &self.fixtures {
+            fixtures.record("GET", url, &headers, None, RecordedResponse {
+                status: status.as_u16(),
+                headers: response_headers.clone(),
+                body: text.clone(),
+                base64: false,
+            })?;
+        }
+
+        Ok(GiteaClientResponse {
+            text,
+            status,
+            total_count: total_count(&response_headers),
+        })
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+}
@@ -0,0 +1,5 @@
+pub mod client;
+pub mod model;
+pub mod repositories;
+#[cfg(test)]
+pub mod stub;
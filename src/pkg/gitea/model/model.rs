@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct User {
+    pub login: String,
+    pub avatar_url: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Repository {
+    pub id: u32,
+    pub name: String,
+    pub full_name: String,
+    pub description: String,
+    pub html_url: String,
+    pub stars_count: u32,
+    pub owner: User,
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub default_branch: String,
+    /// RFC 3339 time of the last push or other change, such as to the settings
+    pub updated_at: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PayloadCommit {
+    /// RFC 3339 time the commit was made
+    pub timestamp: String,
+}
+
+/// Response of `GET /repos/{owner}/{repo}/branches/{branch}`.
+#[derive(Deserialize, Debug)]
+pub struct Branch {
+    pub commit: PayloadCommit,
+}
+
+/// Response of `GET /repos/search`.
+#[derive(Deserialize, Debug)]
+pub struct SearchResults {
+    pub ok: bool,
+    pub data: Vec<Repository>,
+}
+
+/// Response of `GET /repos/{owner}/{repo}/contents/{path}` for a file.
+#[derive(Deserialize, Debug)]
+pub struct ContentsResponse {
+    pub content: Option<String>,
+    pub encoding: Option<String>,
+    pub html_url: Option<String>,
+    pub download_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Issue {
+    pub html_url: String,
+    pub title: String,
+    pub body: Option<String>,
+    #[serde(default)]
+    pub assignees: Option<Vec<User>>,
+    pub comments: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::error::Error;
+use reqwest::Url;
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::gitea::client::client::{GiteaApiClient, GiteaClientResponse};
+use crate::pkg::gitea::model::model::{Branch, ContentsResponse, Issue as GiteaIssue, Repository as GiteaRepository, SearchResults};
+use crate::pkg::github::model::model::{FileTree, GitTree, Issue, IssueState, Owner, ReadmeResponse, Repositories, Repository, SearchIssuesRequest, SearchIssuesSortKey, SearchRepositoriesRequest, SortOrder};
+use crate::pkg::github::model::readme::ReadmeFormat;
+use crate::pkg::markdown::markdown::LinkBase;
+use crate::pkg::github::repositories::RepositoryClient;
+use crate::pkg::utils::base64::base64::decode_to_string;
+
+/// Gitea caps `limit` at 50 by default.
+const PAGE_LIMIT: u32 = 50;
+/// Search pages read at most, for instances with many starred repositories above the floor.
+const MAX_SEARCH_PAGES: u32 = 20;
+/// Gitea's default cap on tree entries per page.
+const TREE_PER_PAGE: u32 = 1000;
+/// Gitea has no README endpoint, so these are tried in order through the contents API.
+const README_FILES: &[&str] = &["README.md", "README.rst", "README.adoc", "README.org", "README.txt", "README", "readme.md"];
+/// Where Gitea and Forgejo look for contribution guidelines.
+const CONTRIBUTING_FILES: &[&str] = &["CONTRIBUTING.md", ".gitea/CONTRIBUTING.md", ".forgejo/CONTRIBUTING.md", "docs/CONTRIBUTING.md"];
+
+/// Maps the Gitea/Forgejo v1 API onto the models shared with the GitHub backend.
+///
+/// Repository search can only sort by stars, so results are paged through until they fall
+/// below the star floor, and language, push date and star ceiling are filtered here. Issue
+/// counts for the good-first-issue and help-wanted thresholds are the `X-Total-Count` of one
+/// listing of open issues carrying any of the configured labels.
+#[derive(Clone)]
+pub struct GiteaRepositoryClient<C: GiteaApiClient> {
+    client: C,
+    good_first_issue_labels: Vec<String>,
+    help_wanted_labels: Vec<String>,
+}
+
+impl<C: GiteaApiClient> GiteaRepositoryClient<C> {
+    pub fn new(client: C, good_first_issue_labels: Vec<String>, help_wanted_labels: Vec<String>) -> Self {
+        Self {
+            client,
+            good_first_issue_labels,
+            help_wanted_labels,
+        }
+    }
+
+    /// Gitea's `labels` filter matches issues carrying any of them, so an issue with two of the
+    /// labels counts once.
+    async fn count_open_issues(&self, repo: &GiteaRepository, labels: &[String]) -> Result<u32, Box<dyn Error>> {
+        let url = Url::parse_with_params(
+            &format!("{}/repos/{}/issues", self.client.base_url(), repo.full_name),
+            &[("state", "open"), ("type", "issues"), ("labels", &labels.join(",")), ("limit", "1")],
+        )?;
+        let res = self.client.get(url.as_str()).await?;
+        ensure_success(&res)?;
+        Ok(res.total_count.unwrap_or_default())
+    }
+
+    /// Date of the last commit on the default branch, as `YYYY-MM-DD`; `None` when the
+    /// repository has no commits.
+    async fn pushed_on(&self, repo: &GiteaRepository) -> Result<Option<String>, Box<dyn Error>> {
+        let url = format!("{}/repos/{}/branches/{}", self.client.base_url(), repo.full_name, repo.default_branch);
+        let res = self.client.get(&url).await?;
+        if res.status == 404 {
+            return Ok(None);
+        }
+        ensure_success(&res)?;
+        let branch: Branch = serde_json::from_str(&res.text)?;
+        Ok(branch.commit.timestamp.get(..10).map(|date| date.to_string()))
+    }
+
+    /// Search results down to `min_stars`, most starred first.
+    async fn search(&self, min_stars: u32) -> Result<Vec<GiteaRepository>, Box<dyn Error>> {
+        let mut repos = vec![];
+        for page in 1..=MAX_SEARCH_PAGES {
+            let url = Url::parse_with_params(
+                &format!("{}/repos/search", self.client.base_url()),
+                &[
+                    ("sort", "stars".to_string()),
+                    ("order", "desc".to_string()),
+                    ("archived", "false".to_string()),
+                    ("limit", PAGE_LIMIT.to_string()),
+                    ("page", page.to_string()),
+                ],
+            )?;
+            let res = self.client.get(url.as_str()).await?;
+            ensure_success(&res)?;
+            let results: SearchResults = serde_json::from_str(&res.text)?;
+            if !results.ok {
+                return Err("Gitea repository search reported failure".into());
+            }
+
+            // The instance may cap pages below PAGE_LIMIT, so the total tells whether more follow.
+            let last = results.data.is_empty()
+                || results.data.iter().any(|repo| repo.stars_count < min_stars)
+                || res.total_count.is_some_and(|total| repos.len() + results.data.len() >= total as usize);
+            repos.extend(results.data.into_iter().filter(|repo| repo.stars_count >= min_stars));
+            if last {
+                break;
+            }
+        }
+        Ok(repos)
+    }
+
+    /// Reads the first of `files` that exists through the contents API.
+    async fn fetch_first_file(&self, owner_name: &str, repository_name: &str, files: &[&str]) -> Result<ReadmeResponse, Box<dyn Error>> {
+        for file in files {
+            let url = format!("{}/repos/{}/{}/contents/{}", self.client.base_url(), owner_name, repository_name, file);
+            let res = self.client.get(&url).await?;
+            if res.status == 404 {
+                continue;
+            }
+            ensure_success(&res)?;
+
+            let contents: ContentsResponse = serde_json::from_str(&res.text)?;
+            return match (contents.encoding.as_deref(), contents.content) {
+                (Some("base64"), Some(content)) => {
+                    let content = decode_to_string(&content)?;
+                    Ok(ReadmeResponse {
+                        found: true,
+                        format: Some(ReadmeFormat::detect(file, &content)),
+                        content: Some(content),
+                        links: LinkBase::for_file(contents.html_url.as_deref(), contents.download_url.as_deref(), file),
+                    })
+                }
+                _ => Err(format!("Unknown encoding for the content of {}", file).into()),
+            };
+        }
+
+        Ok(ReadmeResponse {
+            found: false,
+            content: None,
+            format: None,
+            links: LinkBase::default(),
+        })
+    }
+
+    fn repository(&self, repo: GiteaRepository) -> Repository {
+        Repository {
+            id: repo.id,
+            name: repo.name,
+            full_name: repo.full_name,
+            stargazers_count: repo.stars_count,
+            html_url: repo.html_url,
+            description: repo.description,
+            owner: Owner {
+                login: repo.owner.login,
+                avatar_url: repo.owner.avatar_url,
+            },
+            host: self.client.host().to_string(),
+            forge: Forge::Gitea,
+        }
+    }
+}
+
+impl<C: GiteaApiClient> RepositoryClient for GiteaRepositoryClient<C> {
+    fn host(&self, _host: Option<&str>) -> Result<String, Box<dyn Error>> {
+        Ok(self.client.host().to_string())
+    }
+
+    async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>> {
+        if req.forge.is_some_and(|forge| forge != Forge::Gitea) {
+            return Ok(Repositories {
+                total_count: 0,
+                items: vec![],
+            });
+        }
+
+        let mut items = vec![];
+        for repo in self.search(req.min_stars).await? {
+            // Dates are RFC 3339, so their date prefix compares like the ISO date in last_pushed.
+            // A repository is updated whenever it is pushed to, so updated_at rules out stale
+            // ones before the default branch is looked up.
+            let updated = repo.updated_at.get(..10).unwrap_or_default();
+            if repo.archived
+                || !repo.language.eq_ignore_ascii_case(&req.language)
+                || updated < req.last_pushed.as_str()
+                || req.max_stars.is_some_and(|max| repo.stars_count > max) {
+                continue;
+            }
+            if self.pushed_on(&repo).await?.is_none_or(|pushed| pushed < req.last_pushed) {
+                continue;
+            }
+            if req.good_first_issues_count > 0
+                && self.count_open_issues(&repo, &self.good_first_issue_labels).await? < req.good_first_issues_count {
+                continue;
+            }
+            if req.help_wanted_count > 0
+                && self.count_open_issues(&repo, &self.help_wanted_labels).await? < req.help_wanted_count {
+                continue;
+            }
+            items.push(self.repository(repo));
+        }
+        items.sort_by_key(|r| Reverse(r.stargazers_count));
+
+        Ok(Repositories {
+            total_count: items.len() as u32,
+            items,
+        })
+    }
+
+    async fn fetch_top_readme(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        self.fetch_first_file(owner_name, repository_name, README_FILES).await
+    }
+
+    async fn fetch_issues(&self, _host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let state = match req.state {
+            IssueState::Open => "open",
+            IssueState::Closed => "closed",
+            IssueState::All => "all",
+        };
+        let mut params = vec![
+            ("state", state.to_string()),
+            ("type", "issues".to_string()),
+            ("limit", PAGE_LIMIT.to_string()),
+        ];
+        if !req.labels.is_empty() {
+            params.push(("labels", req.labels.join(",")));
+        }
+        let url = Url::parse_with_params(
+            &format!("{}/repos/{}/{}/issues", self.client.base_url(), owner_name, repository_name),
+            &params,
+        )?;
+        let res = self.client.get(url.as_str()).await?;
+        ensure_success(&res)?;
+        let mut issues: Vec<GiteaIssue> = serde_json::from_str(&res.text)?;
+
+        // The issue list API can neither exclude assigned issues nor sort, so both happen here.
+        issues.retain(|issue| issue.assignees.as_ref().is_none_or(|a| a.is_empty()));
+        match req.sort_key {
+            SearchIssuesSortKey::Created => issues.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+            SearchIssuesSortKey::Updated => issues.sort_by(|a, b| a.updated_at.cmp(&b.updated_at)),
+            SearchIssuesSortKey::Comments => issues.sort_by_key(|issue| issue.comments),
+        }
+        if let SortOrder::Desc = req.sort_order {
+            issues.reverse();
+        }
+
+        Ok(issues.into_iter().map(|issue| Issue {
+            html_url: issue.html_url,
+            title: issue.title,
+            body: issue.body,
+        }).collect())
+    }
+
+    async fn fetch_contributing(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        self.fetch_first_file(owner_name, repository_name, CONTRIBUTING_FILES).await
+    }
+
+    async fn fetch_file_tree(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>> {
+        let res = self.client.get(&format!("{}/repos/{}/{}", self.client.base_url(), owner_name, repository_name)).await?;
+        if res.status == 404 {
+            return Ok(FileTree::default());
+        }
+        ensure_success(&res)?;
+        let repo: GiteaRepository = serde_json::from_str(&res.text)?;
+
+        // Only the first page is read; later pages would not change the summary much.
+        let url = Url::parse_with_params(
+            &format!("{}/repos/{}/{}/git/trees/{}", self.client.base_url(), owner_name, repository_name, repo.default_branch),
+            &[("recursive", "true".to_string()), ("per_page", TREE_PER_PAGE.to_string())],
+        )?;
+        let res = self.client.get(url.as_str()).await?;
+        if res.status == 404 {
+            return Ok(FileTree::default());
+        }
+        ensure_success(&res)?;
+        let tree: GitTree = serde_json::from_str(&res.text)?;
+
+        Ok(FileTree {
+            paths: tree.tree.into_iter().filter(|e| e.kind == "blob").map(|e| e.path).collect(),
+            truncated: tree.truncated,
+        })
+    }
+}
+
+/// Gitea reports errors as `{"message": ..., "url": ...}`.
+fn ensure_success(res: &GiteaClientResponse) -> Result<(), Box<dyn Error>> {
+    if res.status.is_success() {
+        return Ok(());
+    }
+    let message = serde_json::from_str::<serde_json::Value>(&res.text).ok()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()))
+        .unwrap_or_else(|| res.text.clone());
+    Err(format!("Gitea API responded {}: {}", res.status, message).into())
+}
@@ -0,0 +1 @@
+pub mod stub_server;
@@ -0,0 +1,168 @@
+//! A stand-in for the Gitea/Forgejo v1 API that serves responses from a fixture directory.
+//!
+//! Fixture layout, relative to the fixture root:
+//!
+//! - `repos/search.json`, the `{"ok": true, "data": [...]}` body of `GET /repos/search`, paged
+//!   by `page` and `limit` and counted into `X-Total-Count`
+//! - `repos/{owner}/{repo}/contents/{file}.json` for `GET /repos/{owner}/{repo}/contents/{file}`
+//! - `repos/{owner}/{repo}/branches/{branch}.json` for `GET /repos/{owner}/{repo}/branches/{branch}`
+//! - `repos/{owner}/{repo}/issues.json`, an array of issues that the stub filters by `state`
+//!   and by carrying any of `labels`, and counts into `X-Total-Count` like Gitea does
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_web::dev::Server;
+use serde_json::Value;
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+}
+
+struct StubState {
+    fixtures: PathBuf,
+    requests: Mutex<Vec<RecordedRequest>>,
+    max_response_items: RwLock<usize>,
+}
+
+#[derive(Clone)]
+pub struct GiteaStubHandle {
+    state: web::Data<StubState>,
+    /// API root to configure clients with, ending in `/api/v1`
+    pub base_url: String,
+}
+
+impl GiteaStubHandle {
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Caps page sizes whatever `limit` asks for, like the instance setting of the same name.
+    pub fn set_max_response_items(&self, max: usize) {
+        *self.state.max_response_items.write().unwrap() = max;
+    }
+}
+
+pub fn start_gitea_stub_server(fixtures: PathBuf, listener: TcpListener) -> io::Result<(Server, GiteaStubHandle)> {
+    let base_url = format!("http://{}/api/v1", listener.local_addr()?);
+    let state = web::Data::new(StubState {
+        fixtures,
+        requests: Mutex::new(vec![]),
+        max_response_items: RwLock::new(50),
+    });
+
+    let app_state = state.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_state.clone())
+            .service(
+                web::scope("/api/v1")
+                    .route("/repos/search", web::get().to(search_handler))
+                    .route("/repos/{owner}/{repo}/contents/{file}", web::get().to(contents_handler))
+                    .route("/repos/{owner}/{repo}/branches/{branch}", web::get().to(branch_handler))
+                    .route("/repos/{owner}/{repo}/issues", web::get().to(issues_handler))
+            )
+    })
+        .workers(1)
+        .listen(listener)?
+        .run();
+
+    Ok((server, GiteaStubHandle { state, base_url }))
+}
+
+fn record(state: &StubState, req: &HttpRequest) -> HashMap<String, String> {
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map(|q| q.into_inner())
+        .unwrap_or_default();
+    state.requests.lock().unwrap().push(RecordedRequest {
+        path: req.path().to_string(),
+        query: query.clone(),
+        headers: req.headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect(),
+    });
+    query
+}
+
+fn fixture(state: &StubState, path: &str) -> Option<Value> {
+    fs::read_to_string(state.fixtures.join(path)).ok().and_then(|text| serde_json::from_str(&text).ok())
+}
+
+/// `limit` items of page `page`, both as Gitea reads them.
+fn page(state: &StubState, query: &HashMap<String, String>, items: Vec<Value>) -> Vec<Value> {
+    let limit = query.get("limit").and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(30);
+    let limit = limit.min(*state.max_response_items.read().unwrap());
+    let page = query.get("page").and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(1);
+    items.into_iter().skip((page - 1) * limit).take(limit).collect()
+}
+
+async fn search_handler(state: web::Data<StubState>, req: HttpRequest) -> HttpResponse {
+    let query = record(&state, &req);
+    let Some(mut body) = fixture(&state, "repos/search.json") else {
+        return not_found(&req);
+    };
+
+    let total = body["data"].as_array().map(|d| d.len()).unwrap_or_default();
+    if let Some(Value::Array(data)) = body.get_mut("data").map(Value::take) {
+        body["data"] = Value::Array(page(&state, &query, data));
+    }
+    HttpResponse::Ok().insert_header(("x-total-count", total.to_string())).json(body)
+}
+
+async fn contents_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<(String, String, String)>) -> HttpResponse {
+    record(&state, &req);
+    let (owner, repo, file) = path.into_inner();
+    match fixture(&state, &format!("repos/{}/{}/contents/{}.json", owner, repo, file)) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => not_found(&req),
+    }
+}
+
+async fn branch_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<(String, String, String)>) -> HttpResponse {
+    record(&state, &req);
+    let (owner, repo, branch) = path.into_inner();
+    match fixture(&state, &format!("repos/{}/{}/branches/{}.json", owner, repo, branch)) {
+        Some(body) => HttpResponse::Ok().json(body),
+        None => not_found(&req),
+    }
+}
+
+async fn issues_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<(String, String)>) -> HttpResponse {
+    let query = record(&state, &req);
+    let (owner, repo) = path.into_inner();
+    let Some(Value::Array(issues)) = fixture(&state, &format!("repos/{}/{}/issues.json", owner, repo)) else {
+        return not_found(&req);
+    };
+
+    let state_filter = query.get("state").map(|s| s.as_str()).unwrap_or("open");
+    let labels: Vec<&str> = query.get("labels")
+        .map(|l| l.split(',').filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default();
+    let matching: Vec<Value> = issues.into_iter()
+        .filter(|issue| state_filter == "all" || issue["state"] == state_filter)
+        .filter(|issue| {
+            let names: Vec<&str> = issue["labels"].as_array()
+                .map(|l| l.iter().filter_map(|l| l["name"].as_str()).collect())
+                .unwrap_or_default();
+            labels.is_empty() || labels.iter().any(|label| names.contains(label))
+        })
+        .collect();
+
+    let total = matching.len();
+    HttpResponse::Ok().insert_header(("x-total-count", total.to_string())).json(page(&state, &query, matching))
+}
+
+fn not_found(req: &HttpRequest) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "message": "The target couldn't be found.",
+        "url": req.path(),
+    }))
+}
@@ -0,0 +1,97 @@
+use std::error::Error;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::Client as ReqwestClient;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+/// Installation tokens live for an hour; anything expiring sooner than this is refreshed.
+const REFRESH_MARGIN_SECS: i64 = 300;
+/// GitHub rejects app JWTs valid for more than ten minutes.
+const JWT_LIFETIME_SECS: i64 = 540;
+
+#[derive(Debug, Serialize)]
+struct AppClaims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct InstallationToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Authenticates as a GitHub App installation: signs a JWT with the app's private key,
+/// exchanges it for an installation access token and reuses that token until shortly
+/// before it expires.
+pub struct GithubAppAuth {
+    app_id: String,
+    installation_id: u64,
+    key: EncodingKey,
+    base_url: String,
+    reqwest_client: ReqwestClient,
+    cached: Mutex<Option<InstallationToken>>,
+}
+
+impl GithubAppAuth {
+    pub fn new(app_id: String, installation_id: u64, private_key_pem: &[u8], base_url: &str, reqwest_client: ReqwestClient) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            app_id,
+            installation_id,
+            key: EncodingKey::from_rsa_pem(private_key_pem)?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            reqwest_client,
+            cached: Mutex::new(None),
+        })
+    }
+
+    /// Returns a cached installation token, exchanging a fresh JWT for a new one when the
+    /// cached token is missing or about to expire.
+    pub async fn token(&self) -> Result<String, Box<dyn Error>> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref() {
+            if token.expires_at - Utc::now() > Duration::seconds(REFRESH_MARGIN_SECS) {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let token = self.exchange().await?;
+        *cached = Some(token.clone());
+        Ok(token.token)
+    }
+
+    /// Forgets the cached token so the next request exchanges a new one, e.g. after a 401.
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+
+    fn jwt(&self) -> Result<String, Box<dyn Error>> {
+        // Backdated to tolerate clock drift between us and GitHub.
+        let now = Utc::now().timestamp();
+        let claims = AppClaims {
+            iat: now - 60,
+            exp: now + JWT_LIFETIME_SECS,
+            iss: self.app_id.clone(),
+        };
+        Ok(jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)?)
+    }
+
+    async fn exchange(&self) -> Result<InstallationToken, Box<dyn Error>> {
+        let url = format!("{}/app/installations/{}/access_tokens", self.base_url, self.installation_id);
+        let res = self.reqwest_client.post(&url)
+            .header("Authorization", format!("Bearer {}", self.jwt()?))
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "rust-api-client")
+            .send()
+            .await?;
+        let status = res.status();
+        let text = res.text().await?;
+        if !status.is_success() {
+            return Err(format!("GitHub App installation token exchange responded {}: {}", status, text).into());
+        }
+
+        Ok(serde_json::from_str(&text)?)
+    }
+}
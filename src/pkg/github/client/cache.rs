@@ -0,0 +1,268 @@
+use std::error::Error;
+use std::fmt::Write;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use chrono::{NaiveDateTime, Utc};
+use lru::LruCache;
+use sea_query::{Expr, Iden, MysqlQueryBuilder, OnConflict, Query};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{MySql, Pool};
+use tokio::fs;
+use utoipa::ToSchema;
+
+/// A response body together with the validators GitHub sent for it.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub status: u16,
+    pub body: String,
+    /// When GitHub last sent or confirmed this body; unknown for entries written before it was kept
+    #[serde(default)]
+    #[sqlx(rename = "updated_at")]
+    pub stored_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CacheStats {
+    /// Requests answered with 304 and served from the cache
+    pub hits: u64,
+    /// Requests served from the cache without asking GitHub, within the README or issues TTL
+    pub fresh: u64,
+    /// Requests with nothing cached to revalidate
+    pub misses: u64,
+    /// Requests that had a cached entry but got a fresh body because the resource changed
+    pub stale: u64,
+    /// Entries found in the persistent tier after missing in memory
+    pub tier_hits: u64,
+    /// Entries currently held in memory
+    pub memory_entries: usize,
+}
+
+/// Persistent second tier behind the in-memory LRU, so validators survive restarts.
+pub enum CacheTier {
+    Disk(PathBuf),
+    MySql(Pool<MySql>),
+}
+
+pub enum GHHttpCache {
+    Table,
+    UrlHash,
+    Url,
+    Etag,
+    LastModified,
+    Status,
+    Body,
+    UpdatedAt,
+}
+
+impl Iden for GHHttpCache {
+    fn unquoted(&self, s: &mut dyn Write) {
+        write!(
+            s,
+            "{}", match self {
+                Self::Table => "gh_http_cache",
+                Self::UrlHash => "url_hash",
+                Self::Url => "url",
+                Self::Etag => "etag",
+                Self::LastModified => "last_modified",
+                Self::Status => "status",
+                Self::Body => "body",
+                Self::UpdatedAt => "updated_at",
+            }
+        ).unwrap();
+    }
+}
+
+impl CacheTier {
+    async fn get(&self, url: &str) -> Result<Option<CachedResponse>, Box<dyn Error>> {
+        match self {
+            CacheTier::Disk(dir) => {
+                match fs::read_to_string(dir.join(format!("{}.json", url_hash(url)))).await {
+                    Ok(text) => Ok(Some(serde_json::from_str(&text)?)),
+                    Err(_) => Ok(None),
+                }
+            }
+            CacheTier::MySql(pool) => {
+                let (q, args) = Query::select()
+                    .columns([GHHttpCache::Etag, GHHttpCache::LastModified, GHHttpCache::Status, GHHttpCache::Body, GHHttpCache::UpdatedAt])
+                    .from(GHHttpCache::Table)
+                    .and_where(Expr::col(GHHttpCache::UrlHash).eq(url_hash(url)))
+                    .build_sqlx(MysqlQueryBuilder);
+                let row: Option<CachedResponse> = sqlx::query_as_with(&q, args).fetch_optional(pool).await?;
+                Ok(row)
+            }
+        }
+    }
+
+    async fn put(&self, url: &str, entry: &CachedResponse) -> Result<(), Box<dyn Error>> {
+        match self {
+            CacheTier::Disk(dir) => {
+                fs::create_dir_all(dir).await?;
+                fs::write(dir.join(format!("{}.json", url_hash(url))), serde_json::to_string(entry)?).await?;
+            }
+            CacheTier::MySql(pool) => {
+                let (q, args) = Query::insert()
+                    .into_table(GHHttpCache::Table)
+                    .columns([
+                        GHHttpCache::UrlHash,
+                        GHHttpCache::Url,
+                        GHHttpCache::Etag,
+                        GHHttpCache::LastModified,
+                        GHHttpCache::Status,
+                        GHHttpCache::Body,
+                        GHHttpCache::UpdatedAt,
+                    ])
+                    .values([
+                        url_hash(url).into(),
+                        url.into(),
+                        entry.etag.clone().into(),
+                        entry.last_modified.clone().into(),
+                        entry.status.into(),
+                        entry.body.clone().into(),
+                        entry.stored_at.unwrap_or_else(|| Utc::now().naive_utc()).into(),
+                    ])?
+                    .on_conflict(
+                        OnConflict::column(GHHttpCache::UrlHash)
+                            .update_columns([
+                                GHHttpCache::Etag,
+                                GHHttpCache::LastModified,
+                                GHHttpCache::Status,
+                                GHHttpCache::Body,
+                                GHHttpCache::UpdatedAt,
+                            ])
+                            .to_owned(),
+                    )
+                    .build_sqlx(MysqlQueryBuilder);
+                sqlx::query_with(&q, args).execute(pool).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn url_hash(url: &str) -> String {
+    Sha256::digest(url.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// How long READMEs and issue searches are served from the cache before asking GitHub again
+/// whether they changed. Zero always asks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheTtls {
+    pub readme: Duration,
+    pub issues: Duration,
+}
+
+/// Conditional-request cache keyed by URL. GitHub does not count 304 responses against the
+/// rate limit, so revalidating a cached README costs no quota.
+pub struct ResponseCache {
+    memory: Mutex<LruCache<String, CachedResponse>>,
+    tier: Option<CacheTier>,
+    ttls: CacheTtls,
+    hits: AtomicU64,
+    fresh: AtomicU64,
+    misses: AtomicU64,
+    stale: AtomicU64,
+    tier_hits: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize, tier: Option<CacheTier>) -> Self {
+        Self {
+            memory: Mutex::new(LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN))),
+            tier,
+            ttls: CacheTtls::default(),
+            hits: AtomicU64::new(0),
+            fresh: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stale: AtomicU64::new(0),
+            tier_hits: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_ttls(self, ttls: CacheTtls) -> Self {
+        Self { ttls, ..self }
+    }
+
+    /// How long `url` may be served without revalidating: the README TTL for READMEs, the
+    /// issues TTL for issue searches and zero for everything else.
+    pub fn ttl(&self, url: &str) -> Duration {
+        let path = url.split('?').next().unwrap_or(url);
+        if path.ends_with("/readme") {
+            self.ttls.readme
+        } else if path.ends_with("/issues") {
+            self.ttls.issues
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    pub fn is_fresh(&self, url: &str, entry: &CachedResponse) -> bool {
+        match entry.stored_at {
+            Some(stored_at) => (Utc::now().naive_utc() - stored_at).to_std().is_ok_and(|age| age < self.ttl(url)),
+            None => false,
+        }
+    }
+
+    /// Looks in memory first, then in the persistent tier. Tier failures are logged and
+    /// treated as a miss so a broken cache never fails a request.
+    pub async fn lookup(&self, url: &str) -> Option<CachedResponse> {
+        if let Some(entry) = self.memory.lock().unwrap().get(url) {
+            return Some(entry.clone());
+        }
+
+        let entry = match &self.tier {
+            Some(tier) => tier.get(url).await.unwrap_or_else(|e| {
+                log::warn!("GitHub response cache tier lookup failed for {}: {}", url, e);
+                None
+            }),
+            None => None,
+        };
+        if let Some(entry) = &entry {
+            self.tier_hits.fetch_add(1, Ordering::Relaxed);
+            self.memory.lock().unwrap().put(url.to_string(), entry.clone());
+        }
+        entry
+    }
+
+    pub async fn store(&self, url: &str, entry: CachedResponse) {
+        if let Some(tier) = &self.tier {
+            if let Err(e) = tier.put(url, &entry).await {
+                log::warn!("GitHub response cache tier write failed for {}: {}", url, e);
+            }
+        }
+        self.memory.lock().unwrap().put(url.to_string(), entry);
+    }
+
+    pub fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_fresh(&self) {
+        self.fresh.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_miss(&self, had_entry: bool) {
+        if had_entry {
+            self.stale.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            fresh: self.fresh.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            stale: self.stale.load(Ordering::Relaxed),
+            tier_hits: self.tier_hits.load(Ordering::Relaxed),
+            memory_entries: self.memory.lock().unwrap().len(),
+        }
+    }
+}
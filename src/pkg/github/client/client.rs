@@ -1,46 +1,310 @@
-use reqwest::Client as ReqwestClient;
+use reqwest::{Client as ReqwestClient, Method, Url};
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::sync::Arc;
+use chrono::Utc;
 use reqwest::StatusCode;
+use crate::pkg::github::client::app_auth::GithubAppAuth;
+use crate::pkg::github::client::cache::{CachedResponse, ResponseCache};
+use crate::pkg::github::client::token_pool::{is_rate_limited, RateLimitResource, TokenPool};
+use crate::pkg::utils::http_fixture::http_fixture::{HttpFixtures, RecordedResponse};
 
 pub trait GithubApiClient {
     async fn get(&self, url: &str) -> Result<GithubClientResponse, Box<dyn Error>>;
+    /// Sends `body` as JSON. POST responses are never cached.
+    async fn post(&self, url: &str, body: &str) -> Result<GithubClientResponse, Box<dyn Error>>;
+    /// Fetches `url` with the raw media type and returns the body undecoded. Never cached.
+    async fn get_raw(&self, url: &str) -> Result<GithubRawResponse, Box<dyn Error>>;
+    /// API root every request URL is built from, e.g. `https://api.github.com` or
+    /// `https://ghe.example.com/api/v3`, without a trailing slash.
+    fn base_url(&self) -> &str;
+    /// Host repositories fetched through this client are tagged with, e.g. `github.com`.
+    fn host(&self) -> &str;
 }
 
 #[derive(Clone)]
 pub struct GithubClient {
-    token: String,
-    reqwest_client: ReqwestClient
+    tokens: Arc<TokenPool>,
+    app: Option<Arc<GithubAppAuth>>,
+    base_url: String,
+    host: String,
+    reqwest_client: ReqwestClient,
+    fixtures: Option<HttpFixtures>,
+    cache: Option<Arc<ResponseCache>>,
 }
 
 impl GithubClient {
-    pub fn new(token: String, reqwest_client: ReqwestClient) -> Self {
-        Self {
-            token,
+    pub fn new(token: String, base_url: &str, reqwest_client: ReqwestClient) -> Result<Self, Box<dyn Error>> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let host = host_of(&base_url)?;
+        Ok(Self {
+            tokens: Arc::new(TokenPool::new(vec![token])),
+            app: None,
+            base_url,
+            host,
             reqwest_client,
+            fixtures: None,
+            cache: None,
+        })
+    }
+
+    /// Revalidates cached responses with `If-None-Match`/`If-Modified-Since` and serves the
+    /// cached body when GitHub answers 304.
+    pub fn with_cache(self, cache: Arc<ResponseCache>) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Sends each request with whichever token in `tokens` has the most quota left.
+    pub fn with_token_pool(self, tokens: Arc<TokenPool>) -> Self {
+        Self {
+            tokens,
+            ..self
+        }
+    }
+
+    /// Authenticates as a GitHub App installation instead of with personal access tokens.
+    pub fn with_app_auth(self, app: Arc<GithubAppAuth>) -> Self {
+        Self {
+            app: Some(app),
+            ..self
+        }
+    }
+
+    /// Records responses to, or replays them from, `fixtures` instead of only using the network.
+    pub fn with_fixtures(self, fixtures: HttpFixtures) -> Self {
+        Self {
+            fixtures: Some(fixtures),
+            ..self
         }
     }
 }
 
+/// `api.github.com` is reported as `github.com`; Enterprise and stub hosts are kept as is.
+pub(crate) fn host_of(base_url: &str) -> Result<String, Box<dyn Error>> {
+    let url = Url::parse(base_url)?;
+    let host = url.host_str().ok_or_else(|| format!("No host in GitHub base URL {}", base_url))?;
+    let host = host.strip_prefix("api.").filter(|h| *h == "github.com").unwrap_or(host);
+    Ok(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
 #[derive(Debug)]
 pub struct GithubClientResponse {
     pub text: String,
     pub status: StatusCode,
 }
 
+#[derive(Debug)]
+pub struct GithubRawResponse {
+    pub bytes: Vec<u8>,
+    pub status: StatusCode,
+}
+
+impl GithubClient {
+    fn replay(&self, method: &str, url: &str, body: Option<&str>) -> Option<Result<GithubClientResponse, Box<dyn Error>>> {
+        let fixtures = self.fixtures.as_ref().filter(|f| f.is_replay())?;
+        Some(fixtures.replay(method, url, body).and_then(|recorded| {
+            Ok(GithubClientResponse {
+                text: recorded.body,
+                status: StatusCode::from_u16(recorded.status)?,
+            })
+        }))
+    }
+
+    /// Sends one request, authenticated from the token pool or the GitHub App, and records
+    /// the exchange when fixtures are being recorded.
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        body: Option<&str>,
+        extra_headers: &[(&'static str, String)],
+    ) -> Result<(StatusCode, BTreeMap<String, String>, Vec<u8>), Box<dyn Error>> {
+        // A pooled token rejected as revoked or out of quota is retried once per remaining
+        // token; a rejected installation token is exchanged for a new one and retried once.
+        let resource = if url.starts_with(&format!("{}/search/", self.base_url)) {
+            RateLimitResource::Search
+        } else if url == graphql_url(&self.base_url) {
+            RateLimitResource::GraphQL
+        } else {
+            RateLimitResource::Core
+        };
+        let mut attempts = 0;
+        let (headers, status, response_headers, bytes) = loop {
+            attempts += 1;
+            let (index, token) = match &self.app {
+                Some(app) => (None, app.token().await?),
+                None => {
+                    let (index, token, _) = self.tokens.select(resource)?;
+                    (Some(index), token)
+                }
+            };
+            let mut headers = vec![
+                ("Authorization", format!("token {}", token)),
+                ("User-Agent", "rust-api-client".to_string()),
+            ];
+            headers.extend(extra_headers.iter().cloned());
+
+            let mut req = self.reqwest_client.request(method.clone(), url);
+            for (name, value) in &headers {
+                req = req.header(*name, value);
+            }
+            if let Some(body) = body {
+                req = req.header("Content-Type", "application/json").body(body.to_string());
+            }
+            let res = req.send().await?;
+            let status = res.status();
+            let response_headers: BTreeMap<String, String> = res.headers()
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+                .collect();
+            let bytes = res.bytes().await?.to_vec();
+
+            if let Some(index) = index {
+                self.tokens.update(index, resource, status, &response_headers);
+                let rejected = status == StatusCode::UNAUTHORIZED || is_rate_limited(status, &response_headers);
+                let has_alternative = self.tokens.select_preview(resource)
+                    .is_some_and(|(next, headroom)| next != index && headroom > 0);
+                if rejected && attempts < self.tokens.len() && has_alternative {
+                    continue;
+                }
+            } else if let Some(app) = self.app.as_ref().filter(|_| status == StatusCode::UNAUTHORIZED) {
+                app.invalidate().await;
+                if attempts == 1 {
+                    continue;
+                }
+            }
+            break (headers, status, response_headers, bytes);
+        };
+
+        if let Some(fixtures) = &self.fixtures {
+            fixtures.record(method.as_str(), url, &headers, body, RecordedResponse::from_bytes(
+                status.as_u16(),
+                response_headers.clone(),
+                &bytes,
+            ))?;
+        }
+
+        Ok((status, response_headers, bytes))
+    }
+}
+
+/// `https://api.github.com/graphql`, or `https://ghe.example.com/api/graphql` for an
+/// Enterprise Server whose REST root is `https://ghe.example.com/api/v3`.
+pub fn graphql_url(base_url: &str) -> String {
+    match base_url.strip_suffix("/api/v3") {
+        Some(root) => format!("{}/api/graphql", root),
+        None => format!("{}/graphql", base_url),
+    }
+}
+
 impl GithubApiClient for GithubClient {
     async fn get(&self, url: &str) -> Result<GithubClientResponse, Box<dyn Error>> {
-        let res = self.reqwest_client
-            .get(url)
-            .header("Authorization", format!("token {}", self.token))
-            .header("User-Agent", "rust-api-client")
-            .send()
-            .await?;
-        let status = res.status();
-        let text = res.text().await?;
+        if let Some(replayed) = self.replay("GET", url, None) {
+            return replayed;
+        }
+
+        let cached = match &self.cache {
+            Some(cache) => cache.lookup(url).await,
+            None => None,
+        };
+
+        if let (Some(cache), Some(cached)) = (&self.cache, &cached) {
+            if cache.is_fresh(url, cached) {
+                cache.record_fresh();
+                return Ok(GithubClientResponse {
+                    text: cached.body.clone(),
+                    status: StatusCode::from_u16(cached.status)?,
+                });
+            }
+        }
+
+        let mut conditional = vec![];
+        if let Some(cached) = &cached {
+            if let Some(etag) = &cached.etag {
+                conditional.push(("If-None-Match", etag.clone()));
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                conditional.push(("If-Modified-Since", last_modified.clone()));
+            }
+        }
+
+        let (status, response_headers, bytes) = self.send(Method::GET, url, None, &conditional).await?;
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+
+        if let Some(cache) = &self.cache {
+            if status == StatusCode::NOT_MODIFIED {
+                if let Some(cached) = cached {
+                    cache.record_hit();
+                    let status = StatusCode::from_u16(cached.status)?;
+                    let text = cached.body.clone();
+                    if !cache.ttl(url).is_zero() {
+                        // Confirmed unchanged, which starts its TTL over.
+                        cache.store(url, CachedResponse { stored_at: Some(Utc::now().naive_utc()), ..cached }).await;
+                    }
+                    return Ok(GithubClientResponse { text, status });
+                }
+            }
+
+            cache.record_miss(cached.is_some());
+            let etag = response_headers.get("etag").cloned();
+            let last_modified = response_headers.get("last-modified").cloned();
+            if status.is_success() && (etag.is_some() || last_modified.is_some()) {
+                cache.store(url, CachedResponse {
+                    etag,
+                    last_modified,
+                    status: status.as_u16(),
+                    body: text.clone(),
+                    stored_at: Some(Utc::now().naive_utc()),
+                }).await;
+            }
+        }
 
         Ok(GithubClientResponse {
             text,
             status
         })
     }
-}
\ No newline at end of file
+
+    async fn post(&self, url: &str, body: &str) -> Result<GithubClientResponse, Box<dyn Error>> {
+        if let Some(replayed) = self.replay("POST", url, Some(body)) {
+            return replayed;
+        }
+
+        let (status, _, bytes) = self.send(Method::POST, url, Some(body), &[]).await?;
+        Ok(GithubClientResponse {
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+            status
+        })
+    }
+
+    async fn get_raw(&self, url: &str) -> Result<GithubRawResponse, Box<dyn Error>> {
+        if let Some(fixtures) = self.fixtures.as_ref().filter(|f| f.is_replay()) {
+            let recorded = fixtures.replay("GET", url, None)?;
+            return Ok(GithubRawResponse {
+                bytes: recorded.bytes()?,
+                status: StatusCode::from_u16(recorded.status)?,
+            });
+        }
+
+        let accept = [("Accept", "application/vnd.github.raw+json".to_string())];
+        let (status, _, bytes) = self.send(Method::GET, url, None, &accept).await?;
+        Ok(GithubRawResponse {
+            bytes,
+            status
+        })
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+}
@@ -1 +1,4 @@
-pub mod client;
\ No newline at end of file
+pub mod client;
+pub mod cache;
+pub mod token_pool;
+pub mod app_auth;
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use reqwest::StatusCode;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+const DEFAULT_CORE_LIMIT: u32 = 5000;
+const DEFAULT_SEARCH_LIMIT: u32 = 30;
+const DEFAULT_GRAPHQL_LIMIT: u32 = 5000;
+
+/// GitHub meters the search and GraphQL APIs separately from everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitResource {
+    Core,
+    Search,
+    GraphQL,
+}
+
+#[derive(Debug, Clone)]
+struct Quota {
+    limit: u32,
+    remaining: u32,
+    reset: u64,
+}
+
+impl Quota {
+    fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            remaining: limit,
+            reset: 0,
+        }
+    }
+
+    /// Remaining requests, assuming a full window once the reset time has passed.
+    fn headroom(&self, now: u64) -> u32 {
+        if self.reset <= now {
+            self.limit
+        } else {
+            self.remaining
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenState {
+    token: String,
+    core: Quota,
+    search: Quota,
+    graphql: Quota,
+    quarantined: bool,
+    requests: u64,
+}
+
+impl TokenState {
+    fn quota(&self, resource: RateLimitResource) -> &Quota {
+        match resource {
+            RateLimitResource::Core => &self.core,
+            RateLimitResource::Search => &self.search,
+            RateLimitResource::GraphQL => &self.graphql,
+        }
+    }
+
+    fn quota_mut(&mut self, resource: RateLimitResource) -> &mut Quota {
+        match resource {
+            RateLimitResource::Core => &mut self.core,
+            RateLimitResource::Search => &mut self.search,
+            RateLimitResource::GraphQL => &mut self.graphql,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TokenHealth {
+    /// The token with everything but its last four characters masked
+    pub label: String,
+    pub quarantined: bool,
+    pub requests: u64,
+    pub core_remaining: u32,
+    pub core_reset: u64,
+    pub search_remaining: u32,
+    pub search_reset: u64,
+    pub graphql_remaining: u32,
+    pub graphql_reset: u64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TokenPoolHealth {
+    pub host: String,
+    pub healthy: usize,
+    pub tokens: Vec<TokenHealth>,
+}
+
+/// Spreads requests over several tokens by picking the one with the most remaining quota,
+/// as last reported by GitHub's `x-ratelimit-*` headers. Tokens rejected with 401 are
+/// quarantined and never used again.
+#[derive(Debug)]
+pub struct TokenPool {
+    tokens: Mutex<Vec<TokenState>>,
+}
+
+impl TokenPool {
+    pub fn new(tokens: Vec<String>) -> Self {
+        Self {
+            tokens: Mutex::new(tokens.into_iter().map(|token| TokenState {
+                token,
+                core: Quota::new(DEFAULT_CORE_LIMIT),
+                search: Quota::new(DEFAULT_SEARCH_LIMIT),
+                graphql: Quota::new(DEFAULT_GRAPHQL_LIMIT),
+                quarantined: false,
+                requests: 0,
+            }).collect()),
+        }
+    }
+
+    /// Returns the index and value of the token with the most headroom for `resource`,
+    /// preferring the less used and then the earlier one on ties, along with that headroom.
+    pub fn select(&self, resource: RateLimitResource) -> Result<(usize, String, u32), Box<dyn Error>> {
+        let mut tokens = self.tokens.lock().unwrap();
+        match best(&tokens, resource) {
+            Some((index, headroom)) => {
+                let state = &mut tokens[index];
+                state.requests += 1;
+                Ok((index, state.token.clone(), headroom))
+            }
+            None => Err("No usable GitHub token: every token is quarantined".into()),
+        }
+    }
+
+    /// The index and headroom `select` would pick next, without counting it as a request.
+    pub fn select_preview(&self, resource: RateLimitResource) -> Option<(usize, u32)> {
+        best(&self.tokens.lock().unwrap(), resource)
+    }
+
+    /// Records what GitHub said about the token at `index`.
+    pub fn update(&self, index: usize, resource: RateLimitResource, status: StatusCode, headers: &BTreeMap<String, String>) {
+        let mut tokens = self.tokens.lock().unwrap();
+        let Some(state) = tokens.get_mut(index) else {
+            return;
+        };
+
+        if status == StatusCode::UNAUTHORIZED {
+            state.quarantined = true;
+            return;
+        }
+
+        let header = |name: &str| headers.get(name).and_then(|v| v.parse::<u64>().ok());
+        let resource = match headers.get("x-ratelimit-resource").map(|r| r.as_str()) {
+            Some("search") => RateLimitResource::Search,
+            Some("core") => RateLimitResource::Core,
+            Some("graphql") => RateLimitResource::GraphQL,
+            _ => resource,
+        };
+        let quota = state.quota_mut(resource);
+        if let Some(limit) = header("x-ratelimit-limit") {
+            quota.limit = limit as u32;
+        }
+        if let Some(remaining) = header("x-ratelimit-remaining") {
+            quota.remaining = remaining as u32;
+        }
+        if let Some(reset) = header("x-ratelimit-reset") {
+            quota.reset = reset;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.lock().unwrap().len()
+    }
+
+    pub fn health(&self, host: &str) -> TokenPoolHealth {
+        let now = now();
+        let tokens: Vec<TokenHealth> = self.tokens.lock().unwrap().iter().map(|t| TokenHealth {
+            label: mask(&t.token),
+            quarantined: t.quarantined,
+            requests: t.requests,
+            core_remaining: t.core.headroom(now),
+            core_reset: t.core.reset,
+            search_remaining: t.search.headroom(now),
+            search_reset: t.search.reset,
+            graphql_remaining: t.graphql.headroom(now),
+            graphql_reset: t.graphql.reset,
+        }).collect();
+
+        TokenPoolHealth {
+            host: host.to_string(),
+            healthy: tokens.iter().filter(|t| !t.quarantined).count(),
+            tokens,
+        }
+    }
+}
+
+fn best(tokens: &[TokenState], resource: RateLimitResource) -> Option<(usize, u32)> {
+    let now = now();
+    tokens.iter()
+        .enumerate()
+        .filter(|(_, t)| !t.quarantined)
+        .max_by(|(i, a), (j, b)| {
+            a.quota(resource).headroom(now).cmp(&b.quota(resource).headroom(now))
+                .then(b.requests.cmp(&a.requests))
+                .then(j.cmp(i))
+        })
+        .map(|(i, t)| (i, t.quota(resource).headroom(now)))
+}
+
+/// Whether GitHub refused the request because the token ran out of quota.
+pub fn is_rate_limited(status: StatusCode, headers: &BTreeMap<String, String>) -> bool {
+    (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS)
+        && headers.get("x-ratelimit-remaining").map(|r| r.as_str()) == Some("0")
+}
+
+fn mask(token: &str) -> String {
+    let visible: String = token.chars().rev().take(4).collect::<Vec<char>>().into_iter().rev().collect();
+    format!("…{}", visible)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use reqwest::StatusCode;
+    use crate::pkg::github::client::token_pool::{now, RateLimitResource, TokenPool};
+
+    fn headers(remaining: u32, reset: u64) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("x-ratelimit-limit".to_string(), "5000".to_string()),
+            ("x-ratelimit-remaining".to_string(), remaining.to_string()),
+            ("x-ratelimit-reset".to_string(), reset.to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_select_prefers_most_headroom() {
+        let pool = TokenPool::new(vec!["token-a".to_string(), "token-b".to_string()]);
+        let later = now() + 600;
+
+        pool.update(0, RateLimitResource::Core, StatusCode::OK, &headers(10, later));
+        pool.update(1, RateLimitResource::Core, StatusCode::OK, &headers(4000, later));
+        let (index, token, headroom) = pool.select(RateLimitResource::Core).unwrap();
+        assert_eq!((index, token.as_str(), headroom), (1, "token-b", 4000));
+
+        // Search quota is tracked apart from core quota.
+        let (_, _, headroom) = pool.select(RateLimitResource::Search).unwrap();
+        assert_eq!(headroom, 30);
+    }
+
+    #[test]
+    fn test_expired_window_counts_as_full() {
+        let pool = TokenPool::new(vec!["token-a".to_string(), "token-b".to_string()]);
+
+        pool.update(0, RateLimitResource::Core, StatusCode::OK, &headers(0, now() - 1));
+        pool.update(1, RateLimitResource::Core, StatusCode::OK, &headers(100, now() + 600));
+
+        let (index, _, headroom) = pool.select(RateLimitResource::Core).unwrap();
+        assert_eq!((index, headroom), (0, 5000));
+    }
+
+    #[test]
+    fn test_unauthorized_tokens_are_quarantined() {
+        let pool = TokenPool::new(vec!["ghp_revoked1234".to_string()]);
+
+        pool.update(0, RateLimitResource::Core, StatusCode::UNAUTHORIZED, &BTreeMap::new());
+
+        assert!(pool.select(RateLimitResource::Core).is_err());
+        let health = pool.health("github.com");
+        assert_eq!(health.healthy, 0);
+        assert_eq!(health.tokens[0].label, "…1234");
+        assert!(health.tokens[0].quarantined);
+    }
+}
@@ -0,0 +1,90 @@
+use std::error::Error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::pkg::github::client::client::{graphql_url, GithubApiClient};
+use crate::pkg::github::repositories::ensure_success;
+
+/// A GraphQL document together with its variables and the shape of its `data`.
+pub trait GraphQLQuery {
+    type Data: DeserializeOwned;
+
+    fn document(&self) -> String;
+    fn variables(&self) -> Map<String, Value>;
+}
+
+/// The `rateLimit` object GitHub returns when a query asks for it. `cost` is what this
+/// query consumed, in points, out of the hourly `limit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub cost: u32,
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_at: String,
+}
+
+/// Selection set to add to a query to get its `RateLimit` back.
+pub const RATE_LIMIT_FIELDS: &str = "rateLimit { cost limit remaining resetAt }";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub path: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQLError>,
+}
+
+/// `data` along with any errors GitHub reported for parts of it, e.g. `NOT_FOUND` for one
+/// aliased repository in an otherwise successful batch.
+#[derive(Debug)]
+pub struct GraphQLResult<T> {
+    pub data: T,
+    pub errors: Vec<GraphQLError>,
+}
+
+#[derive(Clone)]
+pub struct GithubGraphQLClient<C: GithubApiClient> {
+    client: C,
+}
+
+impl<C: GithubApiClient> GithubGraphQLClient<C> {
+    pub fn new(client: C) -> Self {
+        Self {
+            client,
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        self.client.host()
+    }
+
+    pub async fn query<Q: GraphQLQuery>(&self, query: &Q) -> Result<GraphQLResult<Q::Data>, Box<dyn Error>> {
+        let body = serde_json::json!({
+            "query": query.document(),
+            "variables": query.variables(),
+        });
+        let res = self.client.post(&graphql_url(self.client.base_url()), &body.to_string()).await?;
+        ensure_success(&res)?;
+
+        let response: GraphQLResponse<Q::Data> = serde_json::from_str(&res.text)?;
+        match response.data {
+            Some(data) => Ok(GraphQLResult {
+                data,
+                errors: response.errors,
+            }),
+            None => {
+                let messages: Vec<String> = response.errors.into_iter().map(|e| e.message).collect();
+                Err(format!("GitHub GraphQL API returned no data: {}", messages.join("; ")).into())
+            }
+        }
+    }
+}
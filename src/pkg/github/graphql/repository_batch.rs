@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::error::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::pkg::github::client::client::GithubApiClient;
+use crate::pkg::github::graphql::graphql::{GithubGraphQLClient, GraphQLQuery, RateLimit, RATE_LIMIT_FIELDS};
+
+/// GitHub caps a query at 500,000 nodes; 50 repositories with 100 labels each stays well under.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+const GOOD_FIRST_ISSUE_LABEL: &str = "good first issue";
+const HELP_WANTED_LABEL: &str = "help wanted";
+
+const REPOSITORY_FRAGMENT: &str = r#"fragment RepositorySnapshot on Repository {
+  nameWithOwner
+  description
+  url
+  stargazerCount
+  forkCount
+  pushedAt
+  primaryLanguage { name }
+  defaultBranchRef { name }
+  labels(first: 100) { nodes { name } }
+  goodFirstIssues: issues(states: OPEN, labels: ["GOOD_FIRST_ISSUE"]) { totalCount }
+  helpWantedIssues: issues(states: OPEN, labels: ["HELP_WANTED"]) { totalCount }
+  readmeMd: object(expression: "HEAD:README.md") { ... on Blob { text isTruncated } }
+  readmeRst: object(expression: "HEAD:README.rst") { ... on Blob { text isTruncated } }
+  readme: object(expression: "HEAD:README") { ... on Blob { text isTruncated } }
+}"#;
+
+/// Everything the catalog needs about one repository, fetched in a single GraphQL round trip.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositorySnapshot {
+    /// `owner/name` as requested, which differs from `owner` and `name` after a rename
+    pub requested: String,
+    pub owner: String,
+    pub name: String,
+    pub host: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    pub stargazers_count: u64,
+    pub forks_count: u64,
+    pub language: Option<String>,
+    pub pushed_at: Option<String>,
+    pub default_branch: Option<String>,
+    pub labels: Vec<String>,
+    pub good_first_issues_count: u64,
+    pub help_wanted_issues_count: u64,
+    /// Text of `README.md`, `README.rst` or `README` at the default branch, in that order;
+    /// `None` when there is none or it is too large to come back whole
+    pub readme: Option<String>,
+    /// Which of those `readme` is
+    pub readme_path: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RepositoryBatch {
+    pub repositories: Vec<RepositorySnapshot>,
+    /// `owner/name` of requested repositories that do not exist or are not visible
+    pub missing: Vec<String>,
+    /// Rate limit as reported after the last query
+    pub rate_limit: Option<RateLimit>,
+    /// Points spent over every query of the batch
+    pub cost: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct NameNode {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Count {
+    total_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Blob {
+    text: Option<String>,
+    #[serde(default)]
+    is_truncated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepositoryNode {
+    name_with_owner: String,
+    description: Option<String>,
+    url: String,
+    stargazer_count: u64,
+    fork_count: u64,
+    pushed_at: Option<String>,
+    primary_language: Option<NameNode>,
+    default_branch_ref: Option<NameNode>,
+    labels: Option<Nodes<NameNode>>,
+    good_first_issues: Count,
+    help_wanted_issues: Count,
+    readme_md: Option<Blob>,
+    readme_rst: Option<Blob>,
+    readme: Option<Blob>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepositoryBatchData {
+    rate_limit: Option<RateLimit>,
+    /// `r0`, `r1`, ... as aliased in the document; null when the repository is missing
+    #[serde(flatten)]
+    repositories: HashMap<String, Option<RepositoryNode>>,
+}
+
+/// One query for up to `MAX_BATCH_SIZE` repositories, each aliased `r{index}`.
+pub struct RepositoryBatchQuery<'a> {
+    repositories: &'a [(String, String)],
+}
+
+impl GraphQLQuery for RepositoryBatchQuery<'_> {
+    type Data = RepositoryBatchData;
+
+    fn document(&self) -> String {
+        let params: Vec<String> = (0..self.repositories.len())
+            .map(|i| format!("$o{i}: String!, $n{i}: String!"))
+            .collect();
+        let fields: Vec<String> = (0..self.repositories.len())
+            .map(|i| format!("  r{i}: repository(owner: $o{i}, name: $n{i}) {{ ...RepositorySnapshot }}"))
+            .collect();
+        let fragment = REPOSITORY_FRAGMENT
+            .replace("GOOD_FIRST_ISSUE", GOOD_FIRST_ISSUE_LABEL)
+            .replace("HELP_WANTED", HELP_WANTED_LABEL);
+
+        format!(
+            "query RepositoryBatch({}) {{\n  {}\n{}\n}}\n{}",
+            params.join(", "),
+            RATE_LIMIT_FIELDS,
+            fields.join("\n"),
+            fragment,
+        )
+    }
+
+    fn variables(&self) -> Map<String, Value> {
+        let mut variables = Map::new();
+        for (i, (owner, name)) in self.repositories.iter().enumerate() {
+            variables.insert(format!("o{i}"), Value::String(owner.clone()));
+            variables.insert(format!("n{i}"), Value::String(name.clone()));
+        }
+        variables
+    }
+}
+
+/// Pulls metadata, labels, open good-first-issue/help-wanted counts and README text for
+/// many repositories, `MAX_BATCH_SIZE` per GraphQL query.
+#[derive(Clone)]
+pub struct RepositoryBatchFetcher<C: GithubApiClient> {
+    graphql: GithubGraphQLClient<C>,
+}
+
+impl<C: GithubApiClient> RepositoryBatchFetcher<C> {
+    pub fn new(client: C) -> Self {
+        Self {
+            graphql: GithubGraphQLClient::new(client),
+        }
+    }
+
+    /// `repositories` are `(owner, name)` pairs; results keep their order.
+    pub async fn fetch(&self, repositories: &[(String, String)]) -> Result<RepositoryBatch, Box<dyn Error>> {
+        let mut batch = RepositoryBatch {
+            repositories: vec![],
+            missing: vec![],
+            rate_limit: None,
+            cost: 0,
+        };
+
+        for chunk in repositories.chunks(MAX_BATCH_SIZE) {
+            let mut result = self.graphql.query(&RepositoryBatchQuery { repositories: chunk }).await?;
+            // Missing repositories come back as NOT_FOUND; anything else nulls out what it is about.
+            for error in result.errors.iter().filter(|e| e.kind.as_deref() != Some("NOT_FOUND")) {
+                log::warn!("GitHub GraphQL error at {:?}: {}", error.path, error.message);
+            }
+            for (i, (owner, name)) in chunk.iter().enumerate() {
+                match result.data.repositories.remove(&format!("r{i}")).flatten() {
+                    Some(node) => batch.repositories.push(self.snapshot(owner, name, node)),
+                    None => batch.missing.push(format!("{}/{}", owner, name)),
+                }
+            }
+            if let Some(rate_limit) = result.data.rate_limit {
+                batch.cost += rate_limit.cost;
+                batch.rate_limit = Some(rate_limit);
+            }
+        }
+
+        Ok(batch)
+    }
+
+    fn snapshot(&self, requested_owner: &str, requested_name: &str, node: RepositoryNode) -> RepositorySnapshot {
+        // nameWithOwner carries the canonical casing, which may differ from what was asked for.
+        let (owner, name) = node.name_with_owner.split_once('/')
+            .map(|(o, n)| (o.to_string(), n.to_string()))
+            .unwrap_or_else(|| (requested_owner.to_string(), requested_name.to_string()));
+        let readme = [("README.md", node.readme_md), ("README.rst", node.readme_rst), ("README", node.readme)].into_iter()
+            .find_map(|(path, blob)| blob.map(|blob| (path, blob)))
+            .and_then(|(path, blob)| blob.text.filter(|_| !blob.is_truncated).map(|text| (path, text)));
+
+        RepositorySnapshot {
+            requested: format!("{}/{}", requested_owner, requested_name),
+            owner,
+            name,
+            host: self.graphql.host().to_string(),
+            description: node.description,
+            html_url: node.url,
+            stargazers_count: node.stargazer_count,
+            forks_count: node.fork_count,
+            language: node.primary_language.map(|l| l.name),
+            pushed_at: node.pushed_at,
+            default_branch: node.default_branch_ref.map(|b| b.name),
+            labels: node.labels.map(|l| l.nodes.into_iter().map(|n| n.name).collect()).unwrap_or_default(),
+            good_first_issues_count: node.good_first_issues.total_count,
+            help_wanted_issues_count: node.help_wanted_issues.total_count,
+            readme_path: readme.as_ref().map(|(path, _)| path.to_string()),
+            readme: readme.map(|(_, text)| text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use reqwest::StatusCode;
+    use serde_json::{json, Map, Value};
+    use crate::pkg::github::client::client::{GithubApiClient, GithubClientResponse, GithubRawResponse};
+    use crate::pkg::github::graphql::repository_batch::RepositoryBatchFetcher;
+
+    /// Answers every aliased repository except `missing`, and records the posted bodies.
+    #[derive(Clone, Default)]
+    struct MockGraphQLClient {
+        bodies: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl GithubApiClient for MockGraphQLClient {
+        async fn get(&self, _url: &str) -> Result<GithubClientResponse, Box<dyn Error>> {
+            Err("unexpected GET".into())
+        }
+
+        async fn get_raw(&self, _url: &str) -> Result<GithubRawResponse, Box<dyn Error>> {
+            Err("unexpected raw GET".into())
+        }
+
+        async fn post(&self, url: &str, body: &str) -> Result<GithubClientResponse, Box<dyn Error>> {
+            assert_eq!(url, "https://api.github.com/graphql");
+            let body: Value = serde_json::from_str(body)?;
+            self.bodies.lock().unwrap().push(body.clone());
+
+            let variables = body["variables"].as_object().unwrap();
+            let mut data = Map::new();
+            data.insert("rateLimit".to_string(), json!({"cost": 1, "limit": 5000, "remaining": 4999, "resetAt": "2024-10-19T00:00:00Z"}));
+            for i in 0..variables.len() / 2 {
+                let (owner, name) = (variables[&format!("o{i}")].as_str().unwrap(), variables[&format!("n{i}")].as_str().unwrap());
+                let node = if name == "missing" {
+                    Value::Null
+                } else {
+                    json!({
+                        "nameWithOwner": format!("{}/{}", owner, name),
+                        "description": "A repository",
+                        "url": format!("https://github.com/{}/{}", owner, name),
+                        "stargazerCount": 1500,
+                        "forkCount": 10,
+                        "pushedAt": "2024-10-01T00:00:00Z",
+                        "primaryLanguage": {"name": "Rust"},
+                        "defaultBranchRef": {"name": "main"},
+                        "labels": {"nodes": [{"name": "good first issue"}, {"name": "bug"}]},
+                        "goodFirstIssues": {"totalCount": 3},
+                        "helpWantedIssues": {"totalCount": 1},
+                        "readmeMd": null,
+                        "readmeRst": {"text": "Title\n====="},
+                        "readme": null
+                    })
+                };
+                data.insert(format!("r{i}"), node);
+            }
+
+            Ok(GithubClientResponse {
+                text: json!({
+                    "data": data,
+                    "errors": [{"type": "NOT_FOUND", "path": ["r1"], "message": "Could not resolve to a Repository"}]
+                }).to_string(),
+                status: StatusCode::OK,
+            })
+        }
+
+        fn base_url(&self) -> &str {
+            "https://api.github.com"
+        }
+
+        fn host(&self) -> &str {
+            "github.com"
+        }
+    }
+
+    fn repos(names: &[&str]) -> Vec<(String, String)> {
+        names.iter().map(|n| ("octo-org".to_string(), n.to_string())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_maps_aliases_back_to_repositories() {
+        let client = MockGraphQLClient::default();
+        let fetcher = RepositoryBatchFetcher::new(client.clone());
+
+        let batch = fetcher.fetch(&repos(&["octo-repo", "missing"])).await.unwrap();
+
+        assert_eq!(batch.repositories.len(), 1);
+        let repo = &batch.repositories[0];
+        assert_eq!((repo.owner.as_str(), repo.name.as_str(), repo.host.as_str()), ("octo-org", "octo-repo", "github.com"));
+        assert_eq!((repo.good_first_issues_count, repo.help_wanted_issues_count), (3, 1));
+        assert_eq!(repo.labels, ["good first issue", "bug"]);
+        assert_eq!(repo.readme.as_deref(), Some("Title\n====="));
+        assert_eq!(repo.readme_path.as_deref(), Some("README.rst"));
+        assert_eq!(batch.missing, ["octo-org/missing"]);
+        assert_eq!((batch.cost, batch.rate_limit.unwrap().remaining), (1, 4999));
+
+        let body = &client.bodies.lock().unwrap()[0];
+        let document = body["query"].as_str().unwrap();
+        assert!(document.contains("r1: repository(owner: $o1, name: $n1)"), "{}", document);
+        assert!(document.contains(r#"labels: ["good first issue"]"#), "{}", document);
+        assert_eq!(body["variables"]["n1"], "missing");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_splits_into_batches_of_fifty() {
+        let client = MockGraphQLClient::default();
+        let fetcher = RepositoryBatchFetcher::new(client.clone());
+        let names: Vec<String> = (0..51).map(|i| format!("repo-{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+
+        let batch = fetcher.fetch(&repos(&names)).await.unwrap();
+
+        assert_eq!(batch.repositories.len(), 51);
+        assert_eq!(batch.repositories[50].name, "repo-50");
+        assert_eq!(batch.cost, 2);
+        let bodies = client.bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(bodies[0]["variables"].as_object().unwrap().len(), 100);
+        assert_eq!(bodies[1]["variables"]["n0"], "repo-50");
+    }
+}
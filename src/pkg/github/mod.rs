@@ -1,3 +1,6 @@
 pub mod repositories;
 pub mod client;
+pub mod graphql;
 pub mod model;
+#[cfg(test)]
+pub mod stub;
@@ -0,0 +1,85 @@
+/// Language names as GitHub linguist reports them, paired with the aliases we accept.
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("Assembly", &["asm", "nasm"]),
+    ("C", &[]),
+    ("C#", &["csharp", "cs"]),
+    ("C++", &["cpp", "cplusplus"]),
+    ("Clojure", &["clj"]),
+    ("CoffeeScript", &["coffee"]),
+    ("Crystal", &[]),
+    ("CSS", &[]),
+    ("Dart", &[]),
+    ("Dockerfile", &["docker"]),
+    ("Elixir", &["ex"]),
+    ("Elm", &[]),
+    ("Emacs Lisp", &["elisp", "emacs"]),
+    ("Erlang", &["erl"]),
+    ("F#", &["fsharp"]),
+    ("Fortran", &[]),
+    ("Go", &["golang"]),
+    ("Groovy", &[]),
+    ("Haskell", &["hs"]),
+    ("HTML", &[]),
+    ("Java", &[]),
+    ("JavaScript", &["js", "node", "nodejs"]),
+    ("Julia", &["jl"]),
+    ("Jupyter Notebook", &["jupyter", "ipynb"]),
+    ("Kotlin", &["kt"]),
+    ("Lua", &[]),
+    ("Makefile", &["make"]),
+    ("MATLAB", &["octave"]),
+    ("Nim", &[]),
+    ("Nix", &[]),
+    ("Objective-C", &["objc", "objectivec"]),
+    ("OCaml", &["ml"]),
+    ("Perl", &["pl"]),
+    ("PHP", &[]),
+    ("PowerShell", &["posh", "pwsh"]),
+    ("Python", &["py", "python3"]),
+    ("R", &[]),
+    ("Ruby", &["rb"]),
+    ("Rust", &["rs"]),
+    ("Scala", &[]),
+    ("Scheme", &[]),
+    ("SCSS", &[]),
+    ("Shell", &["sh", "bash", "zsh"]),
+    ("Solidity", &["sol"]),
+    ("SQL", &[]),
+    ("Svelte", &[]),
+    ("Swift", &[]),
+    ("TeX", &["latex"]),
+    ("TypeScript", &["ts"]),
+    ("Vim Script", &["vim", "viml", "vimscript"]),
+    ("Vue", &[]),
+    ("WebAssembly", &["wasm", "wast"]),
+    ("Zig", &[]),
+];
+
+/// Resolves a user supplied language name or alias to its linguist name, ignoring case.
+pub fn normalize_language(input: &str) -> Option<&'static str> {
+    let needle = input.trim().to_lowercase();
+    if needle.is_empty() {
+        return None;
+    }
+
+    LANGUAGES.iter().find_map(|(name, aliases)| {
+        if name.to_lowercase() == needle || aliases.contains(&needle.as_str()) {
+            Some(*name)
+        } else {
+            None
+        }
+    })
+}
+
+/// Encodes a linguist name so it can be used as a `language:` qualifier in a search query.
+pub fn language_qualifier(language: &str) -> String {
+    let encoded = language
+        .replace('+', "%2B")
+        .replace('#', "%23")
+        .replace(' ', "%20");
+    if language.contains(' ') {
+        format!("%22{}%22", encoded)
+    } else {
+        encoded
+    }
+}
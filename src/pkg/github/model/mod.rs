@@ -1 +1,3 @@
-pub mod model;
\ No newline at end of file
+pub mod model;
+pub mod language;
+pub mod readme;
\ No newline at end of file
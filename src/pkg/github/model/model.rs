@@ -1,6 +1,10 @@
 use std::fmt::{Debug, Formatter};
 use serde::Deserialize;
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::github::model::readme::ReadmeFormat;
+use crate::pkg::markdown::markdown::LinkBase;
 
+#[derive(Clone)]
 pub struct SearchRepositoriesRequest {
     pub min_stars: u32,
     pub max_stars: Option<u32>,
@@ -8,6 +12,8 @@ pub struct SearchRepositoriesRequest {
     pub language: String,
     pub good_first_issues_count: u32,
     pub help_wanted_count: u32,
+    /// Only search hosts of this forge
+    pub forge: Option<Forge>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -25,6 +31,11 @@ pub struct Repository {
     pub html_url: String,
     pub description: String,
     pub owner: Owner,
+    /// Host the repository was fetched from, e.g. `github.com`. Not part of the API payload.
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub forge: Forge,
 }
 
 #[derive(Deserialize, Debug)]
@@ -35,16 +46,27 @@ pub struct Repositories {
 
 #[derive(Deserialize, Debug)]
 pub struct ReadmeClientResponse {
+    #[serde(default)]
+    pub path: String,
+    /// Empty for files over 1 MB, which come with encoding `none`
+    #[serde(default)]
     pub content: String,
     pub encoding: String,
+    pub html_url: Option<String>,
+    pub download_url: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct ReadmeResponse {
     pub found: bool,
     pub content: Option<String>,
+    pub format: Option<ReadmeFormat>,
+    /// What relative links in `content` are relative to
+    #[serde(skip)]
+    pub links: LinkBase,
 }
 
+#[derive(Clone)]
 pub enum IssueState {
     Open,
     Closed,
@@ -61,6 +83,7 @@ impl Debug for IssueState {
     }
 }
 
+#[derive(Clone)]
 pub enum SearchIssuesSortKey {
     Created,
     Updated,
@@ -77,6 +100,7 @@ impl Debug for SearchIssuesSortKey {
     }
 }
 
+#[derive(Clone)]
 pub enum SortOrder {
     Asc,
     Desc,
@@ -91,8 +115,10 @@ impl Debug for SortOrder {
     }
 }
 
+#[derive(Clone)]
 pub struct SearchIssuesRequest {
     pub state: IssueState,
+    #[allow(dead_code)]
     pub assignee: String,
     pub labels: Vec<String>,
     pub sort_key: SearchIssuesSortKey,
@@ -106,8 +132,33 @@ pub struct Issue {
     pub body: Option<String>,
 }
 
+/// Response of `GET /repos/{owner}/{repo}/git/trees/{sha}?recursive=1`.
+#[derive(Deserialize, Debug)]
+pub struct GitTree {
+    pub tree: Vec<GitTreeEntry>,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitTreeEntry {
+    pub path: String,
+    /// `blob` for files, `tree` for directories and `commit` for submodules
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// Paths of the files on a repository's default branch, whichever forge it lives on.
+#[derive(Debug, Clone, Default)]
+pub struct FileTree {
+    pub paths: Vec<String>,
+    /// Whether the forge left files out because the repository is too large
+    pub truncated: bool,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Issues {
+    #[allow(dead_code)]
     pub total_count: u32,
     pub items: Vec<Issue>,
 }
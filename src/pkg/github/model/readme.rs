@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Markup language a README is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadmeFormat {
+    Markdown,
+    #[serde(rename = "restructuredtext")]
+    ReStructuredText,
+    AsciiDoc,
+    Org,
+    #[serde(rename = "plaintext")]
+    PlainText,
+}
+
+impl ReadmeFormat {
+    /// Goes by the extension of `file_name` and, for files without a known one such as a bare
+    /// `README`, by telltale markup in the first lines of `content`.
+    pub fn detect(file_name: &str, content: &str) -> Self {
+        let extension = file_name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("md" | "markdown" | "mdown" | "mkd" | "mkdn") => return ReadmeFormat::Markdown,
+            Some("rst" | "rest") => return ReadmeFormat::ReStructuredText,
+            Some("adoc" | "asciidoc" | "asc") => return ReadmeFormat::AsciiDoc,
+            Some("org") => return ReadmeFormat::Org,
+            Some("txt" | "text") => return ReadmeFormat::PlainText,
+            _ => {}
+        }
+
+        let lines: Vec<&str> = content.lines().take(40).collect();
+        let any = |test: fn(&str) -> bool| lines.iter().any(|l| test(l));
+        // Setext headings: a line of text underlined with = or -.
+        let underlined = lines.windows(2).any(|pair| {
+            let rule = pair[1].trim_end();
+            !pair[0].trim().is_empty() && rule.len() >= 3 && (rule.bytes().all(|b| b == b'=') || rule.bytes().all(|b| b == b'-'))
+        });
+
+        if any(|l| l.to_ascii_lowercase().starts_with("#+title:")) {
+            ReadmeFormat::Org
+        } else if any(|l| l.starts_with("= ") || l.starts_with(":toc:")) {
+            ReadmeFormat::AsciiDoc
+        } else if any(|l| l.starts_with(".. ") || l.contains("`_")) {
+            ReadmeFormat::ReStructuredText
+        } else if underlined || any(|l| l.starts_with('#') || l.starts_with("```") || l.contains("](")) {
+            ReadmeFormat::Markdown
+        } else {
+            ReadmeFormat::PlainText
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pkg::github::model::readme::ReadmeFormat;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(ReadmeFormat::detect("README.md", ""), ReadmeFormat::Markdown);
+        assert_eq!(ReadmeFormat::detect("docs/README.RST", ""), ReadmeFormat::ReStructuredText);
+        assert_eq!(ReadmeFormat::detect("README.adoc", ""), ReadmeFormat::AsciiDoc);
+        assert_eq!(ReadmeFormat::detect("README.org", ""), ReadmeFormat::Org);
+        assert_eq!(ReadmeFormat::detect("README.txt", "# not a heading"), ReadmeFormat::PlainText);
+
+        assert_eq!(ReadmeFormat::detect("README", "# Title\n\nSee [docs](docs/)."), ReadmeFormat::Markdown);
+        assert_eq!(ReadmeFormat::detect("README", "Title\n=====\n\n.. image:: logo.png"), ReadmeFormat::ReStructuredText);
+        assert_eq!(ReadmeFormat::detect("README", "= Title\n:toc:\n"), ReadmeFormat::AsciiDoc);
+        assert_eq!(ReadmeFormat::detect("README", "#+TITLE: Title\n* Install\n"), ReadmeFormat::Org);
+        assert_eq!(ReadmeFormat::detect("README", "Just some words.\nNothing more."), ReadmeFormat::PlainText);
+    }
+}
@@ -1,36 +1,112 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::error::Error;
-use log::info;
-use crate::pkg::github::client::client::GithubApiClient;
-use crate::pkg::github::model::model::{SearchRepositoriesRequest, Repositories, ReadmeClientResponse, ReadmeResponse, SearchIssuesRequest, Issue, Issues};
-use crate::pkg::utils::base64::base64::decode_to_string;
-
-const SEARCH_REPOSITORIES_URL: &str = "https://api.github.com/search/repositories";
-const REPOSITORY_URL: &str = "https://api.github.com";
+use futures::future::join_all;
+use reqwest::Url;
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::github::client::client::{GithubApiClient, GithubClientResponse};
+use crate::pkg::github::graphql::repository_batch::RepositoryBatchFetcher;
+use crate::pkg::github::model::language::language_qualifier;
+use crate::pkg::github::model::model::{SearchRepositoriesRequest, Repositories, ReadmeClientResponse, ReadmeResponse, SearchIssuesRequest, Issue, Issues, FileTree, GitTree};
+use crate::pkg::github::model::readme::ReadmeFormat;
+use crate::pkg::markdown::markdown::LinkBase;
+use crate::pkg::utils::base64::base64::decode;
+use crate::pkg::utils::charset::charset::decode_text;
+use crate::pkg::utils::validation::validation::ValidationErrors;
 
+/// The repository methods take the host to look the repository up on, as in
+/// `Repository::host`; clients of a single host ignore it.
 pub trait RepositoryClient {
+    /// Host that `host`, or the default host when `None`, resolves to.
+    fn host(&self, host: Option<&str>) -> Result<String, Box<dyn Error>>;
     async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>>;
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
+    /// READMEs of many `(owner, repo)` pairs in a few requests, in request order. An item is
+    /// `None` when it has to be fetched with `fetch_top_readme` instead, and the whole result
+    /// is `None` for forges without a batch API.
+    async fn fetch_top_readmes(&self, _host: Option<&str>, _repositories: &[(String, String)]) -> Result<Option<Vec<Option<ReadmeResponse>>>, Box<dyn Error>> {
+        Ok(None)
+    }
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>>;
+    /// Contribution guidelines, looked up in the places the forge itself links them from.
+    async fn fetch_contributing(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>>;
+    /// Files on the default branch; empty when the repository or its branch does not exist.
+    async fn fetch_file_tree(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>>;
 }
 
+/// Where GitHub looks for contribution guidelines, in the order it prefers them.
+const CONTRIBUTING_FILES: &[&str] = &["CONTRIBUTING.md", ".github/CONTRIBUTING.md", "docs/CONTRIBUTING.md"];
+
 #[derive(Clone)]
 pub struct GithubRepositoryClient<C: GithubApiClient> {
     client: C,
+    batch: RepositoryBatchFetcher<C>,
 }
 
 impl<C: GithubApiClient> GithubRepositoryClient<C> {
-    pub fn new(client: C) -> Self {
+    pub fn new(client: C) -> Self
+    where
+        C: Clone,
+    {
         Self {
+            batch: RepositoryBatchFetcher::new(client.clone()),
             client,
         }
     }
+
+    /// Files over 1 MB come without content, so they are fetched with the raw media type, or
+    /// from `download_url` if that fails too.
+    async fn fetch_raw_file(&self, owner_name: &str, repository_name: &str, readme: &ReadmeClientResponse) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = format!("{}/repos/{}/{}/contents/{}", self.client.base_url(), owner_name, repository_name, readme.path);
+        let mut res = self.client.get_raw(&url).await?;
+        if !res.status.is_success() {
+            if let Some(download_url) = &readme.download_url {
+                res = self.client.get_raw(download_url).await?;
+            }
+        }
+        if !res.status.is_success() {
+            return Err(format!("GitHub responded {} to the raw request for {}", res.status, readme.path).into());
+        }
+        Ok(res.bytes)
+    }
+
+    /// Decodes a file from the contents API, whatever its transfer encoding and charset.
+    async fn read_file(&self, owner_name: &str, repository_name: &str, file: ReadmeClientResponse) -> Result<ReadmeResponse, Box<dyn Error>> {
+        let bytes = match file.encoding.as_str() {
+            "base64" => decode(&file.content)?,
+            "none" => self.fetch_raw_file(owner_name, repository_name, &file).await?,
+            encoding => return Err(format!("Unknown encoding {:?} for the content of {}", encoding, file.path).into()),
+        };
+        let decoded = decode_text(&bytes);
+        if decoded.lossy {
+            log::warn!("{} of {}/{} is not valid {}; undecodable bytes were replaced", file.path, owner_name, repository_name, decoded.charset);
+        }
+
+        Ok(ReadmeResponse {
+            found: true,
+            format: Some(ReadmeFormat::detect(&file.path, &decoded.text)),
+            content: Some(decoded.text),
+            links: LinkBase::for_file(file.html_url.as_deref(), file.download_url.as_deref(), &file.path),
+        })
+    }
 }
 
 impl<C: GithubApiClient> RepositoryClient for GithubRepositoryClient<C> {
+    fn host(&self, _host: Option<&str>) -> Result<String, Box<dyn Error>> {
+        Ok(self.client.host().to_string())
+    }
+
     async fn fetch_repositories(
         &self,
         req: SearchRepositoriesRequest,
     ) -> Result<Repositories, Box<dyn Error>> {
+        if req.forge.is_some_and(|forge| forge != Forge::Github) {
+            return Ok(Repositories {
+                total_count: 0,
+                items: vec![],
+            });
+        }
+
         let q_stars =
             if let Some(max_stars) = req.max_stars {
                 format!("stars:{}..{}", req.min_stars, max_stars)
@@ -38,21 +114,25 @@ impl<C: GithubApiClient> RepositoryClient for GithubRepositoryClient<C> {
                 format!("stars:>={}", req.min_stars)
             };
         let url = format!(
-            "{}\
+            "{}/search/repositories\
             ?q={}+language:{}+archived:false+good-first-issues:>={}+help-wanted-issues:>={}+pushed:>={}\
             &sort=stars&order=desc",
-            SEARCH_REPOSITORIES_URL,
-            q_stars, req.language, req.good_first_issues_count, req.help_wanted_count, req.last_pushed
+            self.client.base_url(),
+            q_stars, language_qualifier(&req.language), req.good_first_issues_count, req.help_wanted_count, req.last_pushed
         );
 
         let res = self.client.get(&url).await?;
-        let repos: Repositories = serde_json::from_str(&res.text)?;
+        ensure_success(&res)?;
+        let mut repos: Repositories = serde_json::from_str(&res.text)?;
+        for repo in repos.items.iter_mut() {
+            repo.host = self.client.host().to_string();
+        }
 
         Ok(repos)
     }
 
-    async fn fetch_top_readme(&self, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
-        let url = format!("{}/{}/{}/readme", REPOSITORY_URL, owner_name, repository_name);
+    async fn fetch_top_readme(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        let url = format!("{}/repos/{}/{}/readme", self.client.base_url(), owner_name, repository_name);
 
         let res = self.client.get(&url).await?;
 
@@ -60,53 +140,232 @@ impl<C: GithubApiClient> RepositoryClient for GithubRepositoryClient<C> {
             return Ok(ReadmeResponse {
                 found: false,
                 content: None,
+                format: None,
+                links: LinkBase::default(),
             })
         }
 
+        ensure_success(&res)?;
         let readme: ReadmeClientResponse = serde_json::from_str(&res.text)?;
+        self.read_file(owner_name, repository_name, readme).await
+    }
 
-        if readme.encoding == "base64" {
-            let content = decode_to_string(&readme.content)?;
-            Ok(ReadmeResponse {
-                found: true,
-                content: Some(content)
-            })
-        } else {
-            Err("Unknown encoding for README content".into())
+    /// One GraphQL query per `MAX_BATCH_SIZE` repositories. Only `README.md`, `README.rst` and
+    /// `README` at the root are looked at, so other READMEs, too large ones and missing
+    /// repositories are left to `fetch_top_readme`.
+    async fn fetch_top_readmes(&self, _host: Option<&str>, repositories: &[(String, String)]) -> Result<Option<Vec<Option<ReadmeResponse>>>, Box<dyn Error>> {
+        let mut unique: Vec<(String, String)> = vec![];
+        for repository in repositories {
+            if !unique.contains(repository) {
+                unique.push(repository.clone());
+            }
         }
-    }
+        let batch = self.batch.fetch(&unique).await?;
+        let readmes: HashMap<String, ReadmeResponse> = batch.repositories.into_iter()
+            .filter_map(|snapshot| {
+                let (text, path) = (snapshot.readme?, snapshot.readme_path?);
+                let branch = snapshot.default_branch?;
+                let readme = ReadmeResponse {
+                    found: true,
+                    format: Some(ReadmeFormat::detect(&path, &text)),
+                    links: LinkBase::for_file(
+                        Some(&format!("{}/blob/{}/{}", snapshot.html_url, branch, path)),
+                        Some(&format!("{}/raw/{}/{}", snapshot.html_url, branch, path)),
+                        &path,
+                    ),
+                    content: Some(text),
+                };
+                Some((snapshot.requested.to_lowercase(), readme))
+            })
+            .collect();
 
-    async fn fetch_issues(&self, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
-        let url = format!(
-            "{}/search/issues\
-            ?sort={:?}&order={:?}&q=repo:{}/{} is:issue state:{:?} no:assignee",
-            REPOSITORY_URL, req.sort_key, req.sort_order, owner_name, repository_name, req.state,
-        );
+        Ok(Some(repositories.iter()
+            .map(|(owner_name, repository_name)| readmes.get(&format!("{}/{}", owner_name, repository_name).to_lowercase()).cloned())
+            .collect()))
+    }
 
+    async fn fetch_issues(&self, _host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let url = search_issues_url(self.client.base_url(), owner_name, repository_name, &req)?;
         let res = self.client.get(&url).await?;
+        ensure_success(&res)?;
         let issues: Issues = serde_json::from_str(&res.text)?;
 
         Ok(issues.items)
     }
+
+    async fn fetch_contributing(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        for path in CONTRIBUTING_FILES {
+            let url = format!("{}/repos/{}/{}/contents/{}", self.client.base_url(), owner_name, repository_name, path);
+            let res = self.client.get(&url).await?;
+            if res.status == 404 {
+                continue;
+            }
+            ensure_success(&res)?;
+            let file: ReadmeClientResponse = serde_json::from_str(&res.text)?;
+            return self.read_file(owner_name, repository_name, file).await;
+        }
+
+        Ok(ReadmeResponse {
+            found: false,
+            content: None,
+            format: None,
+            links: LinkBase::default(),
+        })
+    }
+
+    async fn fetch_file_tree(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>> {
+        let url = format!("{}/repos/{}/{}/git/trees/HEAD?recursive=1", self.client.base_url(), owner_name, repository_name);
+        let res = self.client.get(&url).await?;
+        // 409 means the repository is empty.
+        if res.status == 404 || res.status == 409 {
+            return Ok(FileTree::default());
+        }
+        ensure_success(&res)?;
+        let tree: GitTree = serde_json::from_str(&res.text)?;
+
+        Ok(FileTree {
+            paths: tree.tree.into_iter().filter(|e| e.kind == "blob").map(|e| e.path).collect(),
+            truncated: tree.truncated,
+        })
+    }
+}
+
+/// Turns error statuses into errors carrying GitHub's `message`, instead of failing later on
+/// deserializing the error body.
+pub(crate) fn ensure_success(res: &GithubClientResponse) -> Result<(), Box<dyn Error>> {
+    if res.status.is_success() {
+        return Ok(());
+    }
+    let message = serde_json::from_str::<serde_json::Value>(&res.text).ok()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(|m| m.to_string()))
+        .unwrap_or_else(|| res.text.clone());
+    Err(format!("GitHub API responded {}: {}", res.status, message).into())
+}
+
+/// Searches several hosts (github.com, Enterprise servers, other forges, stubs) as one, and
+/// looks repositories up on the host a request names, or on the first configured one.
+#[derive(Clone)]
+pub struct MultiHostRepositoryClient<R: RepositoryClient> {
+    clients: Vec<R>,
+}
+
+impl<R: RepositoryClient> MultiHostRepositoryClient<R> {
+    pub fn new(clients: Vec<R>) -> Self {
+        Self {
+            clients,
+        }
+    }
+
+    fn client(&self, host: Option<&str>) -> Result<&R, Box<dyn Error>> {
+        let client = match host {
+            Some(host) => self.clients.iter().find(|c| c.host(None).is_ok_and(|h| h.eq_ignore_ascii_case(host))),
+            None => self.clients.first(),
+        };
+        client.ok_or_else(|| {
+            let mut errors = ValidationErrors::new();
+            errors.add("host", match host {
+                Some(host) => format!("{} is not configured", host),
+                None => "no repository host is configured".to_string(),
+            });
+            errors.into()
+        })
+    }
+}
+
+impl<R: RepositoryClient> RepositoryClient for MultiHostRepositoryClient<R> {
+    fn host(&self, host: Option<&str>) -> Result<String, Box<dyn Error>> {
+        self.client(host)?.host(None)
+    }
+
+    /// Hosts are searched at once. One that fails is logged and left out of the results, so
+    /// the search only fails when every host does.
+    async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>> {
+        let results = join_all(self.clients.iter().map(|client| client.fetch_repositories(req.clone()))).await;
+
+        let mut merged = Repositories {
+            total_count: 0,
+            items: vec![],
+        };
+        let mut last_err = None;
+        let mut succeeded = false;
+        for (client, result) in self.clients.iter().zip(results) {
+            match result {
+                Ok(repos) => {
+                    succeeded = true;
+                    merged.total_count += repos.total_count;
+                    merged.items.extend(repos.items);
+                }
+                Err(e) => {
+                    log::warn!("Searching {} failed, leaving it out of the results: {}", client.host(None).unwrap_or_default(), e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        if let (false, Some(e)) = (succeeded, last_err) {
+            return Err(e);
+        }
+        merged.items.sort_by_key(|r| Reverse(r.stargazers_count));
+
+        Ok(merged)
+    }
+
+    async fn fetch_top_readme(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        self.client(host)?.fetch_top_readme(host, owner_name, repository_name).await
+    }
+
+    async fn fetch_top_readmes(&self, host: Option<&str>, repositories: &[(String, String)]) -> Result<Option<Vec<Option<ReadmeResponse>>>, Box<dyn Error>> {
+        self.client(host)?.fetch_top_readmes(host, repositories).await
+    }
+
+    async fn fetch_issues(&self, host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        self.client(host)?.fetch_issues(host, owner_name, repository_name, req).await
+    }
+
+    async fn fetch_contributing(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        self.client(host)?.fetch_contributing(host, owner_name, repository_name).await
+    }
+
+    async fn fetch_file_tree(&self, host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>> {
+        self.client(host)?.fetch_file_tree(host, owner_name, repository_name).await
+    }
+}
+
+/// Labels are quoted and the query percent-encoded, so labels such as `C++` or `a&b` survive.
+fn search_issues_url(base_url: &str, owner_name: &str, repository_name: &str, req: &SearchIssuesRequest) -> Result<String, Box<dyn Error>> {
+    // Each label narrows the search further, as the label filters of other forges do.
+    let labels: String = req.labels.iter()
+        .filter(|label| !label.is_empty())
+        .map(|label| format!(" label:\"{}\"", label))
+        .collect();
+    let q = format!("repo:{}/{} is:issue state:{:?} no:assignee{}", owner_name, repository_name, req.state, labels);
+    let url = Url::parse_with_params(
+        &format!("{}/search/issues", base_url),
+        [("sort", format!("{:?}", req.sort_key)), ("order", format!("{:?}", req.sort_order)), ("q", q)],
+    )?;
+    Ok(url.into())
 }
 
 #[cfg(test)]
 mod tests {
     use reqwest::StatusCode;
-    use crate::pkg::github::client::client::{GithubApiClient, GithubClientResponse};
+    use crate::pkg::github::client::client::{GithubApiClient, GithubClientResponse, GithubRawResponse};
     use crate::pkg::github::model::model::{IssueState, SearchIssuesSortKey, SortOrder};
-    use crate::pkg::github::repositories::tests::Mode::{FetchRepositories, FetchTopReadmeOkFound, FetchTopReadmeOkNotFound, FetchIssues};
+    use crate::pkg::github::repositories::tests::Mode::{FetchRepositories, FetchTopReadmeOkFound, FetchTopReadmeOkNotFound, FetchIssues, Unavailable};
     use super::*;
 
+    #[derive(Clone)]
     enum Mode {
         FetchRepositories,
         FetchTopReadmeOkFound,
         FetchTopReadmeOkNotFound,
         FetchIssues,
+        Unavailable,
     }
 
+    #[derive(Clone)]
     struct MockClient {
-        mode: Mode
+        mode: Mode,
+        host: &'static str,
     }
 
     impl GithubApiClient for MockClient {
@@ -183,15 +442,30 @@ mod tests {
                         status: StatusCode::OK
                     })
                 }
+                Unavailable => Err("connection refused".into()),
             }
         }
+
+        async fn get_raw(&self, _url: &str) -> Result<GithubRawResponse, Box<dyn Error>> {
+            Err("unexpected raw GET".into())
+        }
+
+        async fn post(&self, _url: &str, _body: &str) -> Result<GithubClientResponse, Box<dyn Error>> {
+            Err("unexpected POST".into())
+        }
+
+        fn base_url(&self) -> &str {
+            "https://api.github.com"
+        }
+
+        fn host(&self) -> &str {
+            self.host
+        }
     }
 
     #[tokio::test]
     async fn test_fetch_repositories() {
-        let client = MockClient {
-            mode: FetchRepositories
-        };
+        let client = MockClient { mode: FetchRepositories, host: "github.com" };
         let repository_client = GithubRepositoryClient::new(client);
         let result = repository_client.fetch_repositories(SearchRepositoriesRequest {
             min_stars: 1000,
@@ -200,6 +474,7 @@ mod tests {
             language: "go".to_string(),
             good_first_issues_count: 1,
             help_wanted_count: 1,
+            forge: None,
         }).await.unwrap();
 
         assert_eq!(result.total_count, 2);
@@ -220,15 +495,60 @@ mod tests {
         assert_eq!(result.items[1].description, "dsc2");
         assert_eq!(result.items[1].owner.login, "mock");
         assert_eq!(result.items[1].owner.avatar_url, "https://avatar.com/2");
+        assert_eq!(result.items[1].host, "github.com");
     }
 
     #[tokio::test]
-    async fn test_fetch_top_readme_ok_found() {
-        let client = MockClient {
-            mode: FetchTopReadmeOkFound
+    async fn test_multi_host_fetch_repositories() {
+        let repository_client = MultiHostRepositoryClient::new(vec![
+            GithubRepositoryClient::new(MockClient { mode: FetchRepositories, host: "github.com" }),
+            GithubRepositoryClient::new(MockClient { mode: Unavailable, host: "ghe.example.com" }),
+            GithubRepositoryClient::new(MockClient { mode: FetchRepositories, host: "ghe.example.org" }),
+        ]);
+        let req = SearchRepositoriesRequest {
+            min_stars: 1000,
+            max_stars: None,
+            last_pushed: "2024-09-07".to_string(),
+            language: "Go".to_string(),
+            good_first_issues_count: 1,
+            help_wanted_count: 1,
+            forge: None,
         };
+        let result = repository_client.fetch_repositories(req.clone()).await.unwrap();
+
+        // The unavailable host is left out rather than failing the search.
+        assert_eq!(result.total_count, 4);
+        let stars: Vec<u32> = result.items.iter().map(|r| r.stargazers_count).collect();
+        assert_eq!(stars, vec![1001, 1001, 1000, 1000]);
+
+        let unavailable = MultiHostRepositoryClient::new(vec![
+            GithubRepositoryClient::new(MockClient { mode: Unavailable, host: "github.com" }),
+        ]);
+        assert!(unavailable.fetch_repositories(req).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multi_host_looks_repositories_up_on_the_requested_host() {
+        let repository_client = MultiHostRepositoryClient::new(vec![
+            GithubRepositoryClient::new(MockClient { mode: FetchTopReadmeOkNotFound, host: "github.com" }),
+            GithubRepositoryClient::new(MockClient { mode: FetchTopReadmeOkFound, host: "ghe.example.com" }),
+        ]);
+
+        assert!(repository_client.fetch_top_readme(Some("GHE.example.com"), "owner", "repo").await.unwrap().found);
+        assert!(!repository_client.fetch_top_readme(None, "owner", "repo").await.unwrap().found);
+        assert_eq!(repository_client.host(None).unwrap(), "github.com");
+
+        let err = repository_client.fetch_top_readme(Some("gitlab.com"), "owner", "repo").await.unwrap_err();
+        let errors = &err.downcast_ref::<ValidationErrors>().unwrap().errors;
+        assert_eq!(errors[0].field, "host");
+        assert_eq!(errors[0].message, "gitlab.com is not configured");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_top_readme_ok_found() {
+        let client = MockClient { mode: FetchTopReadmeOkFound, host: "github.com" };
         let repository_client = GithubRepositoryClient::new(client);
-        let result = repository_client.fetch_top_readme("owner", "repo").await.unwrap();
+        let result = repository_client.fetch_top_readme(None, "owner", "repo").await.unwrap();
 
         assert!(result.found);
         assert_eq!(result.content.unwrap(), "<div align=\"center\">\n<p align=\"center\">\n\n<img");
@@ -236,22 +556,18 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_top_readme_ok_not_found() {
-        let client = MockClient {
-            mode: FetchTopReadmeOkNotFound
-        };
+        let client = MockClient { mode: FetchTopReadmeOkNotFound, host: "github.com" };
         let repository_client = GithubRepositoryClient::new(client);
-        let result = repository_client.fetch_top_readme("owner", "repo").await.unwrap();
+        let result = repository_client.fetch_top_readme(None, "owner", "repo").await.unwrap();
 
         assert!(!result.found);
     }
     
     #[tokio::test]
     async fn test_fetch_issues_ok() {
-        let client = MockClient {
-            mode: FetchIssues,
-        };
+        let client = MockClient { mode: FetchIssues, host: "github.com" };
         let repository_client = GithubRepositoryClient::new(client);
-        let result = repository_client.fetch_issues("owner", "repo", SearchIssuesRequest {
+        let result = repository_client.fetch_issues(None, "owner", "repo", SearchIssuesRequest {
             state: IssueState::Open,
             assignee: "none".to_string(),
             labels: vec!["label1".to_string(), "label2".to_string()],
@@ -261,4 +577,20 @@ mod tests {
 
         assert_eq!(result.len(), 2);
     }
+
+    #[test]
+    fn test_search_issues_url_encodes_labels() {
+        let url = search_issues_url("https://api.github.com", "owner", "repo", &SearchIssuesRequest {
+            state: IssueState::Open,
+            assignee: "none".to_string(),
+            labels: vec!["C++".to_string(), "a&b #1".to_string()],
+            sort_key: SearchIssuesSortKey::Updated,
+            sort_order: SortOrder::Desc,
+        }).unwrap();
+
+        let url = Url::parse(&url).unwrap();
+        let q = url.query_pairs().find(|(name, _)| name == "q").unwrap().1;
+        assert_eq!(q, r#"repo:owner/repo is:issue state:open no:assignee label:"C++" label:"a&b #1""#);
+        assert_eq!(url.query_pairs().count(), 3);
+    }
 }
@@ -0,0 +1,441 @@
+//! A stand-in for the GitHub REST API that serves responses from a fixture directory.
+//!
+//! Fixture layout, relative to the fixture root:
+//!
+//! - `search/repositories.json`, `search/issues.json`
+//! - `repos/{owner}/{repo}/readme.json`, `issues.json`, `community.json`, `git_tree.json` and, optionally,
+//!   `search_issues.json` which is preferred over `search/issues.json` for `q=repo:{owner}/{repo}`
+//! - `repos/{owner}/{repo}/contents/{path}`, served as is for `GET /repos/{owner}/{repo}/contents/{path}`
+//!   when the raw media type is requested, and wrapped in a base64 contents object otherwise
+//! - `rate_limit.json`
+//!
+//! `POST /graphql` answers the `RepositoryBatch` query from each repository's `readme.json`:
+//! repositories without one are missing, and READMEs not inlined as base64 come back truncated.
+//!
+//! `POST /app/installations/{id}/access_tokens` needs no fixture: it checks the app JWT's
+//! claims (not its signature) and mints `ghs_stub_{id}_{n}` tokens.
+//!
+//! This file only depends on external crates so the `github-stub` binary can include it directly.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use actix_web::dev::Server;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+const DEFAULT_PER_PAGE: usize = 30;
+const RATE_LIMIT: u32 = 5000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scenario {
+    Normal,
+    NotFound,
+    RateLimited,
+    Malformed,
+    Slow(Duration),
+}
+
+impl FromStr for Scenario {
+    type Err = String;
+
+    /// Accepts `normal`, `not-found`, `rate-limited`, `malformed`, `slow` and `slow:{millis}`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Scenario::Normal),
+            "not-found" => Ok(Scenario::NotFound),
+            "rate-limited" => Ok(Scenario::RateLimited),
+            "malformed" => Ok(Scenario::Malformed),
+            "slow" => Ok(Scenario::Slow(Duration::from_secs(2))),
+            _ => match s.strip_prefix("slow:").map(|ms| ms.parse::<u64>()) {
+                Some(Ok(ms)) => Ok(Scenario::Slow(Duration::from_millis(ms))),
+                _ => Err(format!("unknown scenario {:?}", s)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: HashMap<String, String>,
+}
+
+struct StubState {
+    fixtures: PathBuf,
+    scenario: RwLock<Scenario>,
+    requests: Mutex<Vec<RecordedRequest>>,
+    revoked_tokens: RwLock<Vec<String>>,
+    installation_tokens_issued: AtomicU64,
+    installation_token_ttl: RwLock<Duration>,
+}
+
+/// Lets a test switch scenarios and inspect what the stub received while it runs.
+#[derive(Clone)]
+pub struct StubHandle {
+    state: web::Data<StubState>,
+    pub base_url: String,
+}
+
+impl StubHandle {
+    pub fn set_scenario(&self, scenario: Scenario) {
+        *self.state.scenario.write().unwrap() = scenario;
+    }
+
+    /// Answers 401 Bad credentials to requests authorized with any of `tokens`.
+    pub fn revoke_tokens(&self, tokens: &[&str]) {
+        *self.state.revoked_tokens.write().unwrap() = tokens.iter().map(|t| t.to_string()).collect();
+    }
+
+    /// How long installation tokens minted from now on are valid for.
+    pub fn set_installation_token_ttl(&self, ttl: Duration) {
+        *self.state.installation_token_ttl.write().unwrap() = ttl;
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+}
+
+pub fn start_stub_server(fixtures: PathBuf, scenario: Scenario, listener: TcpListener) -> io::Result<(Server, StubHandle)> {
+    let base_url = format!("http://{}", listener.local_addr()?);
+    let state = web::Data::new(StubState {
+        fixtures,
+        scenario: RwLock::new(scenario),
+        requests: Mutex::new(vec![]),
+        revoked_tokens: RwLock::new(vec![]),
+        installation_tokens_issued: AtomicU64::new(0),
+        installation_token_ttl: RwLock::new(Duration::from_secs(3600)),
+    });
+
+    let app_state = state.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_state.clone())
+            .route("/search/repositories", web::get().to(search_repositories_handler))
+            .route("/search/issues", web::get().to(search_issues_handler))
+            .route("/repos/{owner}/{repo}/readme", web::get().to(repo_fixture_handler))
+            .route("/repos/{owner}/{repo}/issues", web::get().to(repo_fixture_handler))
+            .route("/repos/{owner}/{repo}/community/profile", web::get().to(repo_fixture_handler))
+            .route("/repos/{owner}/{repo}/contents/{path:.*}", web::get().to(raw_contents_handler))
+            .route("/repos/{owner}/{repo}/git/trees/{sha}", web::get().to(git_tree_handler))
+            .route("/rate_limit", web::get().to(rate_limit_handler))
+            .route("/graphql", web::post().to(graphql_handler))
+            .route("/app/installations/{id}/access_tokens", web::post().to(installation_token_handler))
+            .route("/_stub/scenario/{name}", web::put().to(set_scenario_handler))
+            .route("/_stub/requests", web::get().to(requests_handler))
+    })
+        .workers(1)
+        .listen(listener)?
+        .run();
+
+    Ok((server, StubHandle { state, base_url }))
+}
+
+async fn search_repositories_handler(state: web::Data<StubState>, req: HttpRequest) -> HttpResponse {
+    respond(&state, &req, vec!["search/repositories.json".to_string()]).await
+}
+
+async fn search_issues_handler(state: web::Data<StubState>, req: HttpRequest, query: web::Query<HashMap<String, String>>) -> HttpResponse {
+    let mut candidates = vec![];
+    let repo = query.get("q")
+        .and_then(|q| q.split_whitespace().find_map(|term| term.strip_prefix("repo:")));
+    if let Some(repo) = repo {
+        candidates.push(format!("repos/{}/search_issues.json", repo));
+    }
+    candidates.push("search/issues.json".to_string());
+
+    respond(&state, &req, candidates).await
+}
+
+async fn repo_fixture_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<(String, String)>) -> HttpResponse {
+    let (owner, repo) = path.into_inner();
+    let file = match req.path().rsplit('/').next() {
+        Some("profile") => "community.json",
+        Some("issues") => "issues.json",
+        _ => "readme.json",
+    };
+
+    respond(&state, &req, vec![format!("repos/{}/{}/{}", owner, repo, file)]).await
+}
+
+/// Serves `git_tree.json` whatever tree or ref is asked for.
+async fn git_tree_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<(String, String, String)>) -> HttpResponse {
+    let (owner, repo, _) = path.into_inner();
+    respond(&state, &req, vec![format!("repos/{}/{}/git_tree.json", owner, repo)]).await
+}
+
+async fn raw_contents_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<(String, String, String)>) -> HttpResponse {
+    record(&state, &req);
+    let (owner, repo, file) = path.into_inner();
+    if file.split('/').any(|segment| segment == "..") {
+        return not_found();
+    }
+
+    let Ok(bytes) = fs::read(state.fixtures.join(format!("repos/{}/{}/contents/{}", owner, repo, file))) else {
+        return not_found();
+    };
+
+    let raw = req.headers().get("accept")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("raw"));
+    if raw {
+        return HttpResponse::Ok().content_type("application/vnd.github.raw").body(bytes);
+    }
+    HttpResponse::Ok().json(serde_json::json!({
+        "name": file.rsplit('/').next().unwrap_or_default(),
+        "path": file,
+        "size": bytes.len(),
+        "encoding": "base64",
+        "content": STANDARD.encode(&bytes),
+        "html_url": format!("https://github.com/{}/{}/blob/main/{}", owner, repo, file),
+        "download_url": format!("https://raw.githubusercontent.com/{}/{}/main/{}", owner, repo, file),
+    }))
+}
+
+async fn rate_limit_handler(state: web::Data<StubState>, req: HttpRequest) -> HttpResponse {
+    respond(&state, &req, vec!["rate_limit.json".to_string()]).await
+}
+
+async fn installation_token_handler(state: web::Data<StubState>, req: HttpRequest, path: web::Path<u64>) -> HttpResponse {
+    record(&state, &req);
+
+    let jwt = req.headers().get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let claims = jwt
+        .and_then(|jwt| jwt.split('.').nth(1))
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+        .and_then(|payload| serde_json::from_slice::<Value>(&payload).ok());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let valid = claims.is_some_and(|c| {
+        let (iat, exp) = (c["iat"].as_u64().unwrap_or_default(), c["exp"].as_u64().unwrap_or_default());
+        c["iss"].is_string() && iat <= now && exp > now && exp - iat <= 600
+    });
+    if !valid {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "message": "A JSON web token could not be decoded",
+            "documentation_url": "https://docs.github.com/rest",
+        }));
+    }
+
+    let n = state.installation_tokens_issued.fetch_add(1, Ordering::Relaxed) + 1;
+    let ttl = *state.installation_token_ttl.read().unwrap();
+    let expires_at = chrono::Utc::now() + ttl;
+    HttpResponse::Created().json(serde_json::json!({
+        "token": format!("ghs_stub_{}_{}", path.into_inner(), n),
+        "expires_at": expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "permissions": {"metadata": "read", "contents": "read", "issues": "read"},
+    }))
+}
+
+async fn set_scenario_handler(state: web::Data<StubState>, path: web::Path<String>) -> HttpResponse {
+    match path.into_inner().parse() {
+        Ok(scenario) => {
+            *state.scenario.write().unwrap() = scenario;
+            HttpResponse::NoContent().finish()
+        }
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+async fn requests_handler(state: web::Data<StubState>) -> HttpResponse {
+    HttpResponse::Ok().json(&*state.requests.lock().unwrap())
+}
+
+fn record(state: &StubState, req: &HttpRequest) {
+    state.requests.lock().unwrap().push(RecordedRequest {
+        method: req.method().to_string(),
+        path: req.path().to_string(),
+        query: req.query_string().to_string(),
+        headers: req.headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect(),
+    });
+}
+
+/// Like GitHub, reads the body as JSON whatever its content type.
+async fn graphql_handler(state: web::Data<StubState>, req: HttpRequest, body: web::Bytes) -> HttpResponse {
+    record(&state, &req);
+    if let Some(res) = intercept(&state, &req).await {
+        return res;
+    }
+
+    let Ok(body) = serde_json::from_slice::<Value>(&body) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({"message": "Problems parsing JSON"}));
+    };
+    let variables = body["variables"].as_object().cloned().unwrap_or_default();
+    let mut data = serde_json::Map::new();
+    data.insert("rateLimit".to_string(), serde_json::json!({"cost": 1, "limit": RATE_LIMIT, "remaining": RATE_LIMIT - 1, "resetAt": "2024-10-19T00:00:00Z"}));
+    for i in 0..variables.len() / 2 {
+        let (owner, name) = (variables[&format!("o{}", i)].as_str().unwrap_or_default(), variables[&format!("n{}", i)].as_str().unwrap_or_default());
+        let readme = fs::read_to_string(state.fixtures.join(format!("repos/{}/{}/readme.json", owner, name)))
+            .ok()
+            .and_then(|fixture| serde_json::from_str::<Value>(&fixture).ok());
+        let node = readme.map(|readme| {
+            let text = STANDARD.decode(readme["content"].as_str().unwrap_or_default().replace('\n', ""))
+                .ok()
+                .filter(|_| readme["encoding"] == "base64")
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+            let blob = serde_json::json!({"isTruncated": text.is_none(), "text": text});
+            let field = |path: &str| if readme["path"] == path { blob.clone() } else { Value::Null };
+            serde_json::json!({
+                "nameWithOwner": format!("{}/{}", owner, name),
+                "description": null,
+                "url": format!("https://github.com/{}/{}", owner, name),
+                "stargazerCount": 0,
+                "forkCount": 0,
+                "pushedAt": null,
+                "primaryLanguage": null,
+                "defaultBranchRef": {"name": "main"},
+                "labels": {"nodes": []},
+                "goodFirstIssues": {"totalCount": 0},
+                "helpWantedIssues": {"totalCount": 0},
+                "readmeMd": field("README.md"),
+                "readmeRst": field("README.rst"),
+                "readme": field("README"),
+            })
+        });
+        data.insert(format!("r{}", i), node.unwrap_or(Value::Null));
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({"data": data}))
+}
+
+/// Revoked tokens and scenarios other than `normal` take precedence over the fixtures.
+async fn intercept(state: &StubState, req: &HttpRequest) -> Option<HttpResponse> {
+    let token = req.headers().get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("token "));
+    if token.is_some_and(|t| state.revoked_tokens.read().unwrap().iter().any(|r| r == t)) {
+        return Some(HttpResponse::Unauthorized().json(serde_json::json!({
+            "message": "Bad credentials",
+            "documentation_url": "https://docs.github.com/rest",
+        })));
+    }
+
+    let scenario = state.scenario.read().unwrap().clone();
+    match scenario {
+        Scenario::NotFound => Some(not_found()),
+        Scenario::RateLimited => Some(rate_limited()),
+        Scenario::Malformed => Some(
+            HttpResponse::Ok()
+                .content_type("application/json")
+                .body(r#"{"total_count": 1, "items": [{"id": "#),
+        ),
+        Scenario::Slow(delay) => {
+            tokio::time::sleep(delay).await;
+            None
+        }
+        Scenario::Normal => None,
+    }
+}
+
+async fn respond(state: &StubState, req: &HttpRequest, candidates: Vec<String>) -> HttpResponse {
+    record(state, req);
+    if let Some(res) = intercept(state, req).await {
+        return res;
+    }
+
+    let fixture = candidates.iter().find_map(|path| fs::read_to_string(state.fixtures.join(path)).ok());
+    let Some(fixture) = fixture else {
+        return not_found();
+    };
+    let Ok(body) = serde_json::from_str::<Value>(&fixture) else {
+        return HttpResponse::InternalServerError().body("fixture is not valid JSON");
+    };
+
+    paginate(req, body)
+}
+
+/// Applies `page`/`per_page` to array bodies and to the `items` of search bodies,
+/// and adds a `Link` header the way GitHub does when there are more pages.
+/// Like GitHub, answers 304 without a body when `If-None-Match` matches the ETag.
+fn paginate(req: &HttpRequest, mut body: Value) -> HttpResponse {
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map(|q| q.into_inner())
+        .unwrap_or_default();
+    let per_page = query.get("per_page").and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(DEFAULT_PER_PAGE);
+    let page = query.get("page").and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(1);
+
+    let items = match &mut body {
+        Value::Array(items) => Some(items),
+        Value::Object(map) => map.get_mut("items").and_then(Value::as_array_mut),
+        _ => None,
+    };
+
+    let mut res = HttpResponse::Ok();
+    res.insert_header(("x-ratelimit-limit", RATE_LIMIT.to_string()))
+        .insert_header(("x-ratelimit-remaining", (RATE_LIMIT - 1).to_string()))
+        .insert_header(("x-ratelimit-reset", reset_at().to_string()));
+
+    if let Some(items) = items {
+        let total = items.len();
+        let last_page = total.div_ceil(per_page).max(1);
+        *items = items.drain(..).skip((page - 1) * per_page).take(per_page).collect();
+
+        let link = |page: usize, rel: &str| {
+            let mut params: Vec<(String, String)> = query.iter()
+                .filter(|(k, _)| *k != "page")
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            params.sort();
+            params.push(("page".to_string(), page.to_string()));
+            let params: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v.replace(' ', "+"))).collect();
+            let info = req.connection_info();
+            format!("<{}://{}{}?{}>; rel=\"{}\"", info.scheme(), info.host(), req.path(), params.join("&"), rel)
+        };
+        if page < last_page {
+            res.insert_header(("link", format!("{}, {}", link(page + 1, "next"), link(last_page, "last"))));
+        }
+    }
+
+    let body = body.to_string();
+    let etag = format!(
+        "W/\"{}\"",
+        Sha256::digest(body.as_bytes())[..8].iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    );
+    res.insert_header(("etag", etag.clone()));
+
+    let if_none_match = req.headers().get("if-none-match").and_then(|v| v.to_str().ok());
+    if if_none_match == Some(etag.as_str()) {
+        return res.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+    }
+
+    res.content_type("application/json").body(body)
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "message": "Not Found",
+        "documentation_url": "https://docs.github.com/rest",
+    }))
+}
+
+fn rate_limited() -> HttpResponse {
+    HttpResponse::Forbidden()
+        .insert_header(("x-ratelimit-limit", RATE_LIMIT.to_string()))
+        .insert_header(("x-ratelimit-remaining", "0"))
+        .insert_header(("x-ratelimit-reset", reset_at().to_string()))
+        .json(serde_json::json!({
+            "message": "API rate limit exceeded",
+            "documentation_url": "https://docs.github.com/rest/overview/resources-in-the-rest-api#rate-limiting",
+        }))
+}
+
+fn reset_at() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default() + 3600
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use reqwest::{Client as ReqwestClient, StatusCode};
+use crate::pkg::github::client::client::host_of;
+use crate::pkg::utils::http_fixture::http_fixture::{HttpFixtures, RecordedResponse};
+
+pub trait GitlabApiClient {
+    async fn get(&self, url: &str) -> Result<GitlabClientResponse, Box<dyn Error>>;
+    /// API root every request URL is built from, e.g. `https://gitlab.com/api/v4`, without a
+    /// trailing slash.
+    fn base_url(&self) -> &str;
+    /// Host projects fetched through this client are tagged with, e.g. `gitlab.com`.
+    fn host(&self) -> &str;
+}
+
+#[derive(Debug)]
+pub struct GitlabClientResponse {
+    pub text: String,
+    pub status: StatusCode,
+}
+
+#[derive(Clone)]
+pub struct GitlabClient {
+    /// Personal or project access token; public projects can be read without one
+    token: Option<String>,
+    base_url: String,
+    host: String,
+    reqwest_client: ReqwestClient,
+    fixtures: Option<HttpFixtures>,
+}
+
+impl GitlabClient {
+    pub fn new(token: Option<String>, base_url: &str, reqwest_client: ReqwestClient) -> Result<Self, Box<dyn Error>> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let host = host_of(&base_url)?;
+        Ok(Self {
+            token: token.filter(|t| !t.is_empty()),
+            base_url,
+            host,
+            reqwest_client,
+            fixtures: None,
+        })
+    }
+
+    /// Records responses to, or replays them from, `fixtures` instead of only using the network.
+    pub fn with_fixtures(self, fixtures: HttpFixtures) -> Self {
+        Self {
+            fixtures: Some(fixtures),
+            ..self
+        }
+    }
+}
+
+impl GitlabApiClient for GitlabClient {
+    async fn get(&self, url: &str) -> Result<GitlabClientResponse, Box<dyn Error>> {
+        if let Some(fixtures) = self.fixtures.as_ref().filter(|f| f.is_replay()) {
+            let recorded = fixtures.replay("GET", url, None)?;
+            return Ok(GitlabClientResponse {
+                text: recorded.body,
+                status: StatusCode::from_u16(recorded.status)?,
+            });
+        }
+
+        let mut headers = vec![("User-Agent", "rust-api-client".to_string())];
+        if let Some(token) = &self.token {
+            headers.push(("PRIVATE-TOKEN", token.clone()));
+        }
+        let mut req = self.reqwest_client.get(url);
+        for (name, value) in &headers {
+            req = req.header(*name, value);
+        }
+        let res = req.send().await?;
+        let status = res.status();
+        let response_headers: BTreeMap<String, String> = res.headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+            .collect();
+        let text = res.text().await?;
+
+        if let Some(fixtures) = &self.fixtures {
+            fixtures.record("GET", url, &headers, None, RecordedResponse {
+                status: status.as_u16(),
+                headers: response_headers,
+                body: text.clone(),
+                base64: false,
+            })?;
+        }
+
+        Ok(GitlabClientResponse {
+            text,
+            status,
+        })
+    }
+
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn host(&self) -> &str {
+        &self.host
+    }
+}
@@ -0,0 +1,44 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Namespace {
+    pub full_path: String,
+    pub avatar_url: Option<String>,
+}
+
+/// An entry of `GET /projects/{id}/repository/tree`.
+#[derive(Deserialize, Debug)]
+pub struct TreeEntry {
+    pub path: String,
+    /// `blob`, `tree` or `commit`
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Project {
+    pub id: u32,
+    pub path: String,
+    pub path_with_namespace: String,
+    pub description: Option<String>,
+    pub web_url: String,
+    pub star_count: u32,
+    pub avatar_url: Option<String>,
+    pub namespace: Namespace,
+    pub default_branch: Option<String>,
+    /// Web URL of the README blob, e.g. `https://gitlab.com/group/project/-/blob/main/README.md`
+    pub readme_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Issue {
+    pub web_url: String,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/// Just enough of an issue to tell it apart from the others.
+#[derive(Deserialize, Debug)]
+pub struct IssueId {
+    pub id: u64,
+}
@@ -0,0 +1,481 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::error::Error;
+use futures::future::try_join_all;
+use reqwest::Url;
+use crate::pkg::forge::forge::Forge;
+use crate::pkg::github::model::model::{FileTree, Issue, IssueState, Owner, ReadmeResponse, Repositories, Repository, SearchIssuesRequest, SearchIssuesSortKey, SearchRepositoriesRequest, SortOrder};
+use crate::pkg::github::model::readme::ReadmeFormat;
+use crate::pkg::markdown::markdown::LinkBase;
+use crate::pkg::github::repositories::RepositoryClient;
+use crate::pkg::utils::charset::charset::decode_text;
+use crate::pkg::gitlab::client::client::{GitlabApiClient, GitlabClientResponse};
+use crate::pkg::gitlab::model::model::{Issue as GitlabIssue, IssueId, Project, TreeEntry};
+
+/// Projects fetched per search; stars and issue counts are filtered client side, so this
+/// bounds the number of follow-up requests.
+const SEARCH_PER_PAGE: u32 = 50;
+const ISSUES_PER_PAGE: u32 = 50;
+/// GitLab's maximum page size, for listing the issues that are counted.
+const MAX_PER_PAGE: u32 = 100;
+/// GitLab's maximum page size; only the first page of the tree is read.
+const TREE_PER_PAGE: u32 = 100;
+/// Where GitLab looks for contribution guidelines.
+const CONTRIBUTING_FILES: &[&str] = &["CONTRIBUTING.md", ".gitlab/CONTRIBUTING.md", "docs/CONTRIBUTING.md"];
+
+/// Maps GitLab projects and issues onto the models shared with the GitHub backend.
+///
+/// GitLab search cannot filter on stars or label counts, so projects are ordered by stars and
+/// narrowed down here. A project's good-first-issue count is the number of its open issues
+/// carrying any label in `good_first_issue_labels`, and likewise for help wanted.
+#[derive(Clone)]
+pub struct GitlabRepositoryClient<C: GitlabApiClient> {
+    client: C,
+    good_first_issue_labels: Vec<String>,
+    help_wanted_labels: Vec<String>,
+}
+
+impl<C: GitlabApiClient> GitlabRepositoryClient<C> {
+    pub fn new(client: C, good_first_issue_labels: Vec<String>, help_wanted_labels: Vec<String>) -> Self {
+        Self {
+            client,
+            good_first_issue_labels,
+            help_wanted_labels,
+        }
+    }
+
+    async fn get(&self, url: &str) -> Result<Option<GitlabClientResponse>, Box<dyn Error>> {
+        let res = self.client.get(url).await?;
+        if res.status == 404 {
+            return Ok(None);
+        }
+        ensure_success(&res)?;
+        Ok(Some(res))
+    }
+
+    /// Distinct open issues carrying any of `labels`, counted up to `needed`. GitLab's `labels`
+    /// filter requires every label, so each label is listed on its own, all at once, and an
+    /// issue with two of them is told apart by its id.
+    async fn count_open_issues(&self, project_id: u32, labels: &[String], needed: u32) -> Result<u32, Box<dyn Error>> {
+        let per_label = try_join_all(labels.iter().map(|label| self.open_issue_ids(project_id, label, needed))).await?;
+        let ids: HashSet<u64> = per_label.into_iter().flatten().collect();
+        Ok(ids.len() as u32)
+    }
+
+    /// Ids of up to `needed` open issues labelled `label`; more are never needed, as those alone
+    /// reach the count.
+    async fn open_issue_ids(&self, project_id: u32, label: &str, needed: u32) -> Result<Vec<u64>, Box<dyn Error>> {
+        let per_page = needed.clamp(1, MAX_PER_PAGE);
+        let mut ids = vec![];
+        for page in 1.. {
+            let url = Url::parse_with_params(
+                &format!("{}/projects/{}/issues", self.client.base_url(), project_id),
+                &[
+                    ("labels", label.to_string()),
+                    ("state", "opened".to_string()),
+                    ("per_page", per_page.to_string()),
+                    ("page", page.to_string()),
+                ],
+            )?;
+            let Some(res) = self.get(url.as_str()).await? else {
+                break;
+            };
+            let issues: Vec<IssueId> = serde_json::from_str(&res.text)?;
+            let last = (issues.len() as u32) < per_page;
+            ids.extend(issues.into_iter().map(|issue| issue.id));
+            if last || ids.len() as u32 >= needed {
+                break;
+            }
+        }
+        Ok(ids)
+    }
+
+    fn repository(&self, project: Project) -> Repository {
+        Repository {
+            id: project.id,
+            name: project.path,
+            full_name: project.path_with_namespace,
+            stargazers_count: project.star_count,
+            html_url: project.web_url,
+            description: project.description.unwrap_or_default(),
+            owner: Owner {
+                login: project.namespace.full_path,
+                avatar_url: project.namespace.avatar_url.or(project.avatar_url).unwrap_or_default(),
+            },
+            host: self.client.host().to_string(),
+            forge: Forge::Gitlab,
+        }
+    }
+}
+
+impl<C: GitlabApiClient> RepositoryClient for GitlabRepositoryClient<C> {
+    fn host(&self, _host: Option<&str>) -> Result<String, Box<dyn Error>> {
+        Ok(self.client.host().to_string())
+    }
+
+    async fn fetch_repositories(&self, req: SearchRepositoriesRequest) -> Result<Repositories, Box<dyn Error>> {
+        if req.forge.is_some_and(|forge| forge != Forge::Gitlab) {
+            return Ok(Repositories {
+                total_count: 0,
+                items: vec![],
+            });
+        }
+
+        let url = Url::parse_with_params(
+            &format!("{}/projects", self.client.base_url()),
+            &[
+                ("order_by", "star_count".to_string()),
+                ("sort", "desc".to_string()),
+                ("archived", "false".to_string()),
+                ("visibility", "public".to_string()),
+                ("with_programming_language", req.language.clone()),
+                ("last_activity_after", format!("{}T00:00:00Z", req.last_pushed)),
+                ("per_page", SEARCH_PER_PAGE.to_string()),
+            ],
+        )?;
+        let res = self.client.get(url.as_str()).await?;
+        ensure_success(&res)?;
+        let projects: Vec<Project> = serde_json::from_str(&res.text)?;
+
+        let mut items = vec![];
+        for project in projects {
+            if project.star_count < req.min_stars || req.max_stars.is_some_and(|max| project.star_count > max) {
+                continue;
+            }
+            if req.good_first_issues_count > 0
+                && self.count_open_issues(project.id, &self.good_first_issue_labels, req.good_first_issues_count).await? < req.good_first_issues_count {
+                continue;
+            }
+            if req.help_wanted_count > 0
+                && self.count_open_issues(project.id, &self.help_wanted_labels, req.help_wanted_count).await? < req.help_wanted_count {
+                continue;
+            }
+            items.push(self.repository(project));
+        }
+        items.sort_by_key(|r| Reverse(r.stargazers_count));
+
+        Ok(Repositories {
+            total_count: items.len() as u32,
+            items,
+        })
+    }
+
+    async fn fetch_top_readme(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        let not_found = ReadmeResponse {
+            found: false,
+            content: None,
+            format: None,
+            links: LinkBase::default(),
+        };
+
+        let url = format!("{}/projects/{}", self.client.base_url(), project_path(owner_name, repository_name));
+        let Some(res) = self.get(&url).await? else {
+            return Ok(not_found);
+        };
+        let project: Project = serde_json::from_str(&res.text)?;
+        let (Some(readme_url), Some(branch)) = (project.readme_url, project.default_branch) else {
+            return Ok(not_found);
+        };
+        let Some(file) = readme_url.split_once(&format!("/-/blob/{}/", branch)).map(|(_, file)| file.to_string()) else {
+            return Ok(not_found);
+        };
+
+        let url = Url::parse_with_params(
+            &format!("{}/projects/{}/repository/files/{}/raw", self.client.base_url(), project.id, encode_component(&file)),
+            &[("ref", branch.as_str())],
+        )?;
+        let Some(res) = self.get(url.as_str()).await? else {
+            return Ok(not_found);
+        };
+
+        // The client has already decoded the body; this only drops a byte order mark.
+        let content = decode_text(res.text.as_bytes()).text;
+        Ok(ReadmeResponse {
+            found: true,
+            format: Some(ReadmeFormat::detect(&file, &content)),
+            content: Some(content),
+            links: LinkBase::for_file(Some(&readme_url), Some(&readme_url.replacen("/-/blob/", "/-/raw/", 1)), &file),
+        })
+    }
+
+    async fn fetch_issues(&self, _host: Option<&str>, owner_name: &str, repository_name: &str, req: SearchIssuesRequest) -> Result<Vec<Issue>, Box<dyn Error>> {
+        let state = match req.state {
+            IssueState::Open => "opened",
+            IssueState::Closed => "closed",
+            IssueState::All => "all",
+        };
+        // GitLab has no comment count ordering; upvotes are the closest measure of activity.
+        let order_by = match req.sort_key {
+            SearchIssuesSortKey::Created => "created_at",
+            SearchIssuesSortKey::Updated => "updated_at",
+            SearchIssuesSortKey::Comments => "popularity",
+        };
+        let sort = match req.sort_order {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        };
+        let mut params = vec![
+            ("state", state.to_string()),
+            ("assignee_id", "None".to_string()),
+            ("order_by", order_by.to_string()),
+            ("sort", sort.to_string()),
+            ("per_page", ISSUES_PER_PAGE.to_string()),
+        ];
+        if !req.labels.is_empty() {
+            params.push(("labels", req.labels.join(",")));
+        }
+
+        let url = Url::parse_with_params(
+            &format!("{}/projects/{}/issues", self.client.base_url(), project_path(owner_name, repository_name)),
+            &params,
+        )?;
+        let res = self.client.get(url.as_str()).await?;
+        ensure_success(&res)?;
+        let issues: Vec<GitlabIssue> = serde_json::from_str(&res.text)?;
+
+        Ok(issues.into_iter().map(|issue| Issue {
+            html_url: issue.web_url,
+            title: issue.title,
+            body: issue.description,
+        }).collect())
+    }
+
+    async fn fetch_contributing(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<ReadmeResponse, Box<dyn Error>> {
+        let not_found = ReadmeResponse {
+            found: false,
+            content: None,
+            format: None,
+            links: LinkBase::default(),
+        };
+
+        let url = format!("{}/projects/{}", self.client.base_url(), project_path(owner_name, repository_name));
+        let Some(res) = self.get(&url).await? else {
+            return Ok(not_found);
+        };
+        let project: Project = serde_json::from_str(&res.text)?;
+        let Some(branch) = project.default_branch else {
+            return Ok(not_found);
+        };
+
+        for file in CONTRIBUTING_FILES {
+            let url = Url::parse_with_params(
+                &format!("{}/projects/{}/repository/files/{}/raw", self.client.base_url(), project.id, encode_component(file)),
+                &[("ref", branch.as_str())],
+            )?;
+            let Some(res) = self.get(url.as_str()).await? else {
+                continue;
+            };
+
+            let content = decode_text(res.text.as_bytes()).text;
+            let blob_url = format!("{}/-/blob/{}/{}", project.web_url, branch, file);
+            return Ok(ReadmeResponse {
+                found: true,
+                format: Some(ReadmeFormat::detect(file, &content)),
+                content: Some(content),
+                links: LinkBase::for_file(Some(&blob_url), Some(&blob_url.replacen("/-/blob/", "/-/raw/", 1)), file),
+            });
+        }
+
+        Ok(not_found)
+    }
+
+    async fn fetch_file_tree(&self, _host: Option<&str>, owner_name: &str, repository_name: &str) -> Result<FileTree, Box<dyn Error>> {
+        let url = Url::parse_with_params(
+            &format!("{}/projects/{}/repository/tree", self.client.base_url(), project_path(owner_name, repository_name)),
+            &[("recursive", "true".to_string()), ("per_page", TREE_PER_PAGE.to_string())],
+        )?;
+        let Some(res) = self.get(url.as_str()).await? else {
+            return Ok(FileTree::default());
+        };
+        let entries: Vec<TreeEntry> = serde_json::from_str(&res.text)?;
+
+        Ok(FileTree {
+            truncated: entries.len() as u32 >= TREE_PER_PAGE,
+            paths: entries.into_iter().filter(|e| e.kind == "blob").map(|e| e.path).collect(),
+        })
+    }
+}
+
+/// GitLab addresses projects by their URL-encoded full path, e.g. `gnome%2Fgnome-shell`.
+fn project_path(owner_name: &str, repository_name: &str) -> String {
+    encode_component(&format!("{}/{}", owner_name, repository_name))
+}
+
+fn encode_component(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// GitLab reports errors as `{"message": ...}` (a string or a map of field errors) or
+/// `{"error": ...}`.
+fn ensure_success(res: &GitlabClientResponse) -> Result<(), Box<dyn Error>> {
+    if res.status.is_success() {
+        return Ok(());
+    }
+    let body = serde_json::from_str::<serde_json::Value>(&res.text).ok();
+    let message = body.as_ref()
+        .and_then(|v| v.get("message").or_else(|| v.get("error")))
+        .map(|m| m.as_str().map(|s| s.to_string()).unwrap_or_else(|| m.to_string()))
+        .unwrap_or_else(|| res.text.clone());
+    Err(format!("GitLab API responded {}: {}", res.status, message).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use reqwest::{StatusCode, Url};
+    use serde_json::json;
+    use crate::pkg::forge::forge::Forge;
+    use crate::pkg::github::model::model::{IssueState, SearchIssuesRequest, SearchIssuesSortKey, SearchRepositoriesRequest, SortOrder};
+    use crate::pkg::github::repositories::RepositoryClient;
+    use crate::pkg::gitlab::client::client::{GitlabApiClient, GitlabClientResponse};
+    use crate::pkg::gitlab::repositories::GitlabRepositoryClient;
+
+    /// Serves canned GitLab responses by path and records every requested URL.
+    #[derive(Clone, Default)]
+    struct MockClient {
+        urls: Arc<Mutex<Vec<String>>>,
+    }
+
+    fn project(id: u32, path: &str, stars: u32) -> serde_json::Value {
+        json!({
+            "id": id,
+            "path": path,
+            "path_with_namespace": format!("gnome/{}", path),
+            "description": null,
+            "web_url": format!("https://gitlab.com/gnome/{}", path),
+            "star_count": stars,
+            "avatar_url": null,
+            "namespace": {"full_path": "gnome", "avatar_url": "https://gitlab.com/gnome.png"},
+            "default_branch": "main",
+            "readme_url": format!("https://gitlab.com/gnome/{}/-/blob/main/docs/README.md", path),
+        })
+    }
+
+    impl GitlabApiClient for MockClient {
+        async fn get(&self, url: &str) -> Result<GitlabClientResponse, Box<dyn Error>> {
+            self.urls.lock().unwrap().push(url.to_string());
+            let url = Url::parse(url)?;
+            let path = url.path().trim_start_matches("/api/v4");
+            let label = url.query_pairs().find(|(k, _)| k == "labels").map(|(_, v)| v.to_string()).unwrap_or_default();
+            let issue_ids = |ids: &[u64]| (200, ids.iter().map(|id| json!({"id": id})).collect::<serde_json::Value>().to_string());
+            let (status, text) = match (path, label.as_str()) {
+                ("/projects", _) => (200, json!([project(1, "big", 5000), project(2, "mid", 1500), project(3, "small", 10), project(4, "overlap", 2000)]).to_string()),
+                ("/projects/1/issues", _) => issue_ids(&[]),
+                // One of mid's issues carries both labels, and all of overlap's do.
+                ("/projects/2/issues", "good first issue") => issue_ids(&[10, 11]),
+                ("/projects/2/issues", "quick win") => issue_ids(&[11, 12]),
+                ("/projects/4/issues", _) => issue_ids(&[20, 21]),
+                _ => match path {
+                    "/projects/gnome%2Fmid" | "/projects/gnome%2Fcore%2Fmid" => (200, project(2, "mid", 1500).to_string()),
+                    "/projects/2/repository/files/docs%2FREADME.md/raw" => (200, "# Mid\n".to_string()),
+                    "/projects/gnome%2Fmid/issues" => (200, json!([{"web_url": "https://gitlab.com/gnome/mid/-/issues/1", "title": "Fix typo", "description": null}]).to_string()),
+                    _ => (404, json!({"message": "404 Project Not Found"}).to_string()),
+                },
+            };
+            Ok(GitlabClientResponse {
+                text,
+                status: StatusCode::from_u16(status)?,
+            })
+        }
+
+        fn base_url(&self) -> &str {
+            "https://gitlab.com/api/v4"
+        }
+
+        fn host(&self) -> &str {
+            "gitlab.com"
+        }
+    }
+
+    fn client(mock: &MockClient) -> GitlabRepositoryClient<MockClient> {
+        GitlabRepositoryClient::new(mock.clone(), vec!["good first issue".to_string(), "quick win".to_string()], vec![])
+    }
+
+    fn search_request(forge: Option<Forge>) -> SearchRepositoriesRequest {
+        SearchRepositoriesRequest {
+            min_stars: 1000,
+            max_stars: None,
+            last_pushed: "2024-09-07".to_string(),
+            language: "C++".to_string(),
+            good_first_issues_count: 3,
+            help_wanted_count: 0,
+            forge,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_repositories_filters_by_stars_and_newcomer_issues() {
+        let mock = MockClient::default();
+
+        let result = client(&mock).fetch_repositories(search_request(None)).await.unwrap();
+
+        // "big" has no newcomer issues, "mid" has 3 over both labels, "overlap" only 2 as each of its
+        // issues carries both, and "small" has too few stars.
+        assert_eq!(result.total_count, 1);
+        let repo = &result.items[0];
+        assert_eq!((repo.full_name.as_str(), repo.host.as_str(), repo.forge), ("gnome/mid", "gitlab.com", Forge::Gitlab));
+        assert_eq!(repo.owner.login, "gnome");
+        let urls = mock.urls.lock().unwrap();
+        assert!(urls[0].contains("with_programming_language=C%2B%2B"), "{}", urls[0]);
+        assert!(urls.iter().any(|u| u.contains("labels=quick+win")), "{:?}", urls);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_repositories_skips_other_forges() {
+        let mock = MockClient::default();
+
+        let result = client(&mock).fetch_repositories(search_request(Some(Forge::Github))).await.unwrap();
+
+        assert_eq!(result.total_count, 0);
+        assert!(mock.urls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_top_readme_follows_readme_url() {
+        let mock = MockClient::default();
+
+        let found = client(&mock).fetch_top_readme(None, "gnome", "mid").await.unwrap();
+        assert_eq!(found.content.as_deref(), Some("# Mid\n"));
+        assert!(mock.urls.lock().unwrap()[1].ends_with("/raw?ref=main"));
+
+        let missing = client(&mock).fetch_top_readme(None, "gnome", "unknown").await.unwrap();
+        assert!(!missing.found);
+
+        // Projects in subgroups are addressed by their whole namespace.
+        let nested = client(&mock).fetch_top_readme(None, "gnome/core", "mid").await.unwrap();
+        assert!(nested.found);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_issues() {
+        let mock = MockClient::default();
+
+        let issues = client(&mock).fetch_issues(None, "gnome", "mid", SearchIssuesRequest {
+            state: IssueState::Open,
+            assignee: "none".to_string(),
+            labels: vec!["quick win".to_string()],
+            sort_key: SearchIssuesSortKey::Comments,
+            sort_order: SortOrder::Desc,
+        }).await.unwrap();
+
+        assert_eq!(issues[0].title, "Fix typo");
+        let url = mock.urls.lock().unwrap()[0].clone();
+        assert!(url.contains("state=opened&assignee_id=None&order_by=popularity&sort=desc"), "{}", url);
+        assert!(url.contains("labels=quick+win"), "{}", url);
+
+        let err = client(&mock).fetch_issues(None, "gnome", "unknown", SearchIssuesRequest {
+            state: IssueState::Open,
+            assignee: "none".to_string(),
+            labels: vec![],
+            sort_key: SearchIssuesSortKey::Created,
+            sort_order: SortOrder::Desc,
+        }).await.unwrap_err();
+        assert_eq!(err.to_string(), "GitLab API responded 404 Not Found: 404 Project Not Found");
+    }
+}
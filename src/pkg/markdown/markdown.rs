@@ -0,0 +1,275 @@
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use reqwest::Url;
+
+/// Where relative links and images in a document point to.
+///
+/// Forges resolve README links against the README's own location on the default branch:
+/// `docs/setup.md` is a sibling of the README and `/docs/setup.md` starts at the repository
+/// root. Images resolve the same way, but against the raw file URL so they can be embedded.
+#[derive(Debug, Clone, Default)]
+pub struct LinkBase {
+    page: Option<Url>,
+    raw: Option<Url>,
+    /// How many directories below the repository root the document sits
+    depth: usize,
+}
+
+impl LinkBase {
+    /// Base for a file at `path` in the repository, viewable at `html_url` and downloadable
+    /// from `download_url`.
+    pub fn for_file(html_url: Option<&str>, download_url: Option<&str>, path: &str) -> Self {
+        Self {
+            page: html_url.and_then(|u| Url::parse(u).ok()),
+            raw: download_url.and_then(|u| Url::parse(u).ok()),
+            depth: path.trim_matches('/').matches('/').count(),
+        }
+    }
+
+    /// Base for a page outside the repository tree, such as an issue.
+    pub fn for_page(html_url: &str) -> Self {
+        Self {
+            page: Url::parse(html_url).ok(),
+            ..Self::default()
+        }
+    }
+
+    fn resolve(&self, href: &str, image: bool) -> String {
+        if href.is_empty() || href.starts_with('#') || Url::parse(href).is_ok() {
+            return href.to_string();
+        }
+        let base = if image { self.raw.as_ref().or(self.page.as_ref()) } else { self.page.as_ref() };
+        let Some(base) = base else {
+            return href.to_string();
+        };
+
+        let joined = match href.strip_prefix('/').filter(|_| !href.starts_with("//")) {
+            Some(from_root) => base.join(&format!("{}{}", "../".repeat(self.depth), from_root)),
+            None => base.join(href),
+        };
+        joined.map(|u| u.to_string()).unwrap_or_else(|_| href.to_string())
+    }
+}
+
+pub(crate) fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | Options::ENABLE_FOOTNOTES
+}
+
+/// Renders GitHub-flavoured Markdown to HTML that is safe to insert into a page: scripts,
+/// event handlers and other active content are removed, and links open without a referrer.
+pub fn render_html(markdown: &str, base: &LinkBase) -> String {
+    let events = Parser::new_ext(markdown, options()).map(|event| match event {
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::from(base.resolve(&dest_url, false)),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: CowStr::from(base.resolve(&dest_url, true)),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(&html)
+        .to_string()
+}
+
+/// Reduces Markdown to readable plain text for prompts.
+///
+/// Headings, list markers and fenced code blocks are kept since they carry structure; images,
+/// badges (links around nothing but images) and raw HTML are dropped. Links become
+/// `text (url)` with relative URLs resolved.
+pub fn to_plain_text(markdown: &str, base: &LinkBase) -> String {
+    let mut out = String::new();
+    let mut image_depth = 0;
+    let mut links: Vec<(String, usize)> = vec![];
+    let mut lists: Vec<Option<u64>> = vec![];
+
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Start(Tag::Image { .. }) => image_depth += 1,
+            Event::End(TagEnd::Image) => image_depth -= 1,
+            _ if image_depth > 0 => {}
+
+            Event::Start(Tag::Heading { level, .. }) => {
+                start_block(&mut out);
+                out.push_str(&"#".repeat(level as usize));
+                out.push(' ');
+            }
+            Event::Start(Tag::Paragraph) if lists.is_empty() => start_block(&mut out),
+            Event::Start(Tag::BlockQuote(_)) | Event::Start(Tag::Table(_)) => start_block(&mut out),
+            Event::End(TagEnd::Heading(_)) | Event::End(TagEnd::Paragraph) | Event::End(TagEnd::TableRow)
+            | Event::End(TagEnd::TableHead) => end_line(&mut out),
+            Event::End(TagEnd::TableCell) => out.push_str(" | "),
+            Event::Start(Tag::CodeBlock(kind)) => {
+                start_block(&mut out);
+                out.push_str("```");
+                if let CodeBlockKind::Fenced(lang) = kind {
+                    out.push_str(&lang);
+                }
+                out.push('\n');
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                end_line(&mut out);
+                out.push_str("```\n");
+            }
+            Event::Start(Tag::List(start)) => {
+                if lists.is_empty() {
+                    start_block(&mut out);
+                }
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                end_line(&mut out);
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        out.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            Event::End(TagEnd::Item) => end_line(&mut out),
+            Event::TaskListMarker(done) => out.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::Start(Tag::Link { dest_url, .. }) => links.push((base.resolve(&dest_url, false), out.len())),
+            Event::End(TagEnd::Link) => {
+                let Some((url, start)) = links.pop() else { continue };
+                let text = out[start..].trim();
+                if text.is_empty() {
+                    out.truncate(start);
+                } else if text != url && !url.starts_with('#') {
+                    out.push_str(&format!(" ({})", url));
+                }
+            }
+            Event::Text(text) => out.push_str(&text),
+            Event::Code(code) => {
+                out.push('`');
+                out.push_str(&code);
+                out.push('`');
+            }
+            Event::SoftBreak => out.push(' '),
+            Event::HardBreak => out.push('\n'),
+            _ => {}
+        }
+    }
+
+    tidy(&out)
+}
+
+fn start_block(out: &mut String) {
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        end_line(out);
+        out.push('\n');
+    }
+}
+
+fn end_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Trims trailing spaces, including those left by dropped images, and collapses blank runs.
+/// Fenced code is kept as written.
+fn tidy(text: &str) -> String {
+    let mut lines: Vec<&str> = vec![];
+    let mut in_code = false;
+    for line in text.lines() {
+        if in_code {
+            in_code = line != "```";
+            lines.push(line);
+            continue;
+        }
+        let line = line.trim_end();
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        in_code = line.starts_with("```");
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pkg::markdown::markdown::{render_html, to_plain_text, LinkBase};
+
+    const README: &str = r#"# Octo Repo [![CI](https://github.com/octo-org/octo-repo/actions/workflows/ci.yml/badge.svg)](https://github.com/octo-org/octo-repo/actions)
+
+<p align="center"><img src="logo.png" width="200"></p>
+
+A *friendly* sample project. See [the guide](docs/GUIDE.md), the [license](/LICENSE) and
+[our site](https://octo.example.com).
+
+![screenshot](assets/screen.png)
+
+## Build
+
+```sh
+cargo build
+```
+
+1. Fork it
+2. Open a pull request
+"#;
+
+    fn base() -> LinkBase {
+        LinkBase::for_file(
+            Some("https://github.com/octo-org/octo-repo/blob/main/docs/README.md"),
+            Some("https://raw.githubusercontent.com/octo-org/octo-repo/main/docs/README.md"),
+            "docs/README.md",
+        )
+    }
+
+    #[test]
+    fn test_to_plain_text() {
+        let text = to_plain_text(README, &base());
+
+        assert_eq!(text, "\
+# Octo Repo
+
+A friendly sample project. See the guide (https://github.com/octo-org/octo-repo/blob/main/docs/docs/GUIDE.md), \
+the license (https://github.com/octo-org/octo-repo/blob/main/LICENSE) and our site (https://octo.example.com).
+
+## Build
+
+```sh
+cargo build
+```
+
+1. Fork it
+2. Open a pull request");
+    }
+
+    #[test]
+    fn test_to_plain_text_keeps_code_blocks_as_written() {
+        let text = to_plain_text("Run:\n\n```py\nimport os\n\n\ndef main():  \n    pass\n```\n\n\n\nDone", &base());
+
+        assert_eq!(text, "Run:\n\n```py\nimport os\n\n\ndef main():  \n    pass\n```\n\nDone");
+    }
+
+    #[test]
+    fn test_render_html_sanitizes_and_resolves() {
+        let html = render_html(
+            "[guide](GUIDE.md) ![logo](logo.png)\n\n<script>alert(1)</script>\n\n<a href=\"javascript:alert(1)\" onclick=\"x()\">bad</a>",
+            &base(),
+        );
+
+        assert!(html.contains(r#"<a href="https://github.com/octo-org/octo-repo/blob/main/docs/GUIDE.md" rel="noopener noreferrer nofollow">guide</a>"#), "{}", html);
+        assert!(html.contains(r#"<img src="https://raw.githubusercontent.com/octo-org/octo-repo/main/docs/logo.png" alt="logo">"#), "{}", html);
+        assert!(!html.contains("script"), "{}", html);
+        assert!(!html.contains("javascript:"), "{}", html);
+        assert!(!html.contains("onclick"), "{}", html);
+    }
+}
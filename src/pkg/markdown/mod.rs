@@ -0,0 +1,2 @@
+pub mod markdown;
+pub mod sections;
\ No newline at end of file
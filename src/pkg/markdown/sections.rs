@@ -0,0 +1,256 @@
+use std::ops::Range;
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::pkg::markdown::markdown::options;
+
+/// What a README or CONTRIBUTING section helps a newcomer with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionKind {
+    Installation,
+    Build,
+    Test,
+    Contributing,
+    CodeOfConduct,
+    Community,
+    License,
+}
+
+/// Heading keywords per kind. A keyword matches at the start of a word, so `test` matches
+/// "Testing" but not "Latest".
+const KEYWORDS: &[(SectionKind, &[&str])] = &[
+    (SectionKind::Installation, &["install", "getting started", "get started", "quick start", "quickstart", "setup", "set up", "prerequisite", "requirements"]),
+    (SectionKind::Build, &["build", "compil", "development", "developing", "hacking", "from source"]),
+    (SectionKind::Test, &["test"]),
+    (SectionKind::CodeOfConduct, &["code of conduct", "conduct"]),
+    (SectionKind::Contributing, &["contribut", "pull request"]),
+    (SectionKind::Community, &["community", "chat", "discord", "slack", "matrix", "gitter", "zulip", "support", "help", "contact", "forum", "mailing list"]),
+    (SectionKind::License, &["license", "licence", "licensing", "copyright"]),
+];
+
+/// Languages of fenced code blocks that hold commands to run.
+const SHELL_LANGUAGES: &[&str] = &["sh", "bash", "shell", "zsh", "fish", "console", "shell-session", "shellsession", "terminal", "powershell", "pwsh", "cmd", "bat"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub kind: SectionKind,
+    pub heading: String,
+    /// Markdown between the heading and the next section, subsections included
+    pub content: String,
+    /// Shell commands from the section's code blocks; only extracted for installation, build
+    /// and test sections
+    pub commands: Vec<String>,
+}
+
+struct Heading {
+    level: usize,
+    text: String,
+    range: Range<usize>,
+}
+
+struct CodeBlock {
+    lang: String,
+    code: String,
+    start: usize,
+}
+
+/// Splits Markdown into sections by heading and keeps those whose heading says what they are
+/// about.
+///
+/// A section runs until the next heading of the same or a higher level, or until a subheading
+/// that is classified itself. Unclassified subheadings, like "Linux" under "Installation",
+/// stay part of their parent.
+pub fn extract_sections(markdown: &str) -> Vec<Section> {
+    let (headings, code_blocks) = scan(markdown);
+
+    let mut sections = vec![];
+    let mut open: Option<(SectionKind, &Heading)> = None;
+    for heading in &headings {
+        let kind = classify(&heading.text);
+        if let Some((open_kind, open_heading)) = open {
+            if kind.is_some() || heading.level <= open_heading.level {
+                sections.push(section(markdown, &code_blocks, open_kind, open_heading, heading.range.start));
+                open = None;
+            }
+        }
+        if let Some(kind) = kind {
+            open = Some((kind, heading));
+        }
+    }
+    if let Some((kind, heading)) = open {
+        sections.push(section(markdown, &code_blocks, kind, heading, markdown.len()));
+    }
+
+    sections
+}
+
+fn scan(markdown: &str) -> (Vec<Heading>, Vec<CodeBlock>) {
+    let mut headings = vec![];
+    let mut code_blocks = vec![];
+    let mut heading: Option<Heading> = None;
+    let mut code: Option<CodeBlock> = None;
+
+    for (event, range) in Parser::new_ext(markdown, options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => heading = Some(Heading { level: level as usize, text: String::new(), range }),
+            Event::End(TagEnd::Heading(_)) => headings.extend(heading.take()),
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => code = Some(CodeBlock {
+                lang: info.split(|c: char| c.is_whitespace() || c == ',' || c == '{').next().unwrap_or_default().to_ascii_lowercase(),
+                code: String::new(),
+                start: range.start,
+            }),
+            Event::End(TagEnd::CodeBlock) => code_blocks.extend(code.take()),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = heading.as_mut() {
+                    heading.text.push_str(&text);
+                } else if let Some(code) = code.as_mut() {
+                    code.code.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (headings, code_blocks)
+}
+
+fn section(markdown: &str, code_blocks: &[CodeBlock], kind: SectionKind, heading: &Heading, end: usize) -> Section {
+    let body = heading.range.end..end;
+    let commands = match kind {
+        SectionKind::Installation | SectionKind::Build | SectionKind::Test => code_blocks.iter()
+            .filter(|block| body.contains(&block.start))
+            .flat_map(|block| commands(&block.lang, &block.code))
+            .collect(),
+        _ => vec![],
+    };
+
+    Section {
+        kind,
+        heading: heading.text.trim().to_string(),
+        content: markdown[body].trim().to_string(),
+        commands,
+    }
+}
+
+/// Picks the kind whose keyword appears first in the heading, so "Building and testing" is
+/// about building.
+pub(crate) fn classify(heading: &str) -> Option<SectionKind> {
+    let heading = heading.to_lowercase();
+    KEYWORDS.iter()
+        .filter_map(|(kind, keywords)| {
+            keywords.iter().filter_map(|keyword| find_word(&heading, keyword)).min().map(|position| (position, *kind))
+        })
+        .min_by_key(|(position, _)| *position)
+        .map(|(_, kind)| kind)
+}
+
+fn find_word(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.match_indices(needle)
+        .map(|(i, _)| i)
+        .find(|&i| !haystack[..i].chars().next_back().is_some_and(|c| c.is_alphanumeric()))
+}
+
+/// Commands in a shell code block. Blocks without a language only count when their lines carry
+/// a `$ ` prompt; once a block uses prompts, lines without one are taken as output.
+fn commands(lang: &str, code: &str) -> Vec<String> {
+    let prompted = code.lines().any(|line| line.trim_start().starts_with("$ "));
+    if !(SHELL_LANGUAGES.contains(&lang) || lang.is_empty() && prompted) {
+        return vec![];
+    }
+
+    let mut commands = vec![];
+    let mut continued: Option<String> = None;
+    for line in code.lines() {
+        let line = line.trim();
+        let line = match continued.take() {
+            Some(mut command) => {
+                command.push(' ');
+                command.push_str(line);
+                command
+            }
+            None => {
+                let command = match line.strip_prefix("$ ") {
+                    Some(command) => command,
+                    None if prompted => continue,
+                    None => line,
+                };
+                if command.is_empty() || command.starts_with('#') || command.starts_with("REM ") || command.starts_with("::") {
+                    continue;
+                }
+                command.to_string()
+            }
+        };
+
+        match line.strip_suffix('\\').or_else(|| line.strip_suffix('`').filter(|_| lang == "powershell" || lang == "pwsh")) {
+            Some(head) => continued = Some(head.trim_end().to_string()),
+            None => commands.push(line),
+        }
+    }
+    commands.extend(continued);
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pkg::markdown::sections::{extract_sections, SectionKind};
+
+    const README: &str = r#"# Octo
+
+A friendly sample project.
+
+## Getting started
+
+### Linux
+
+```bash
+# needs root
+sudo apt install octo \
+  --yes
+```
+
+## Building and testing
+
+```console
+$ cargo build --release
+   Compiling octo v0.1.0
+$ cargo test
+```
+
+```json
+{"not": "a command"}
+```
+
+## Latest news
+
+Nothing yet.
+
+## Contributing
+
+Read the guide. Chat with us on [Discord](https://discord.gg/octo).
+
+## License
+
+MIT
+"#;
+
+    #[test]
+    fn test_extract_sections() {
+        let sections = extract_sections(README);
+        let summary: Vec<(SectionKind, &str)> = sections.iter().map(|s| (s.kind, s.heading.as_str())).collect();
+
+        assert_eq!(summary, vec![
+            (SectionKind::Installation, "Getting started"),
+            (SectionKind::Build, "Building and testing"),
+            (SectionKind::Contributing, "Contributing"),
+            (SectionKind::License, "License"),
+        ]);
+        assert!(sections[0].content.starts_with("### Linux"), "{}", sections[0].content);
+        assert_eq!(sections[0].commands, vec!["sudo apt install octo --yes"]);
+        assert_eq!(sections[1].commands, vec!["cargo build --release", "cargo test"]);
+        assert_eq!(sections[2].content, "Read the guide. Chat with us on [Discord](https://discord.gg/octo).");
+        assert!(sections[2].commands.is_empty());
+        assert_eq!(sections[3].content, "MIT");
+    }
+}
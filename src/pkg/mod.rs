@@ -1,3 +1,7 @@
 pub mod github;
+pub mod gitlab;
+pub mod gitea;
+pub mod forge;
+pub mod markdown;
 pub mod utils;
 pub mod ai;
\ No newline at end of file
@@ -1,9 +1,13 @@
 use base64::prelude::BASE64_STANDARD;
 use base64::{Engine};
 use std::error::Error;
+use crate::pkg::utils::charset::charset::decode_text;
 
+pub fn decode(input: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(BASE64_STANDARD.decode(input.replace("\n", "").replace("\r", ""))?)
+}
+
+/// Decodes base64 and then the text inside it, whatever its charset; see `decode_text`.
 pub fn decode_to_string(input: &str) -> Result<String, Box<dyn Error>> {
-    let decoded_bytes = BASE64_STANDARD.decode(input.replace("\n", "").replace("\r", ""))?;
-    let decoded = String::from_utf8(decoded_bytes)?;
-    Ok(decoded)
+    Ok(decode_text(&decode(input)?).text)
 }
\ No newline at end of file
@@ -0,0 +1,88 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Text decoded from bytes of unknown encoding.
+#[derive(Debug)]
+pub struct DecodedText {
+    pub text: String,
+    /// WHATWG name of the encoding used, e.g. `UTF-8` or `windows-1252`
+    pub charset: &'static str,
+    /// Whether malformed sequences were replaced with U+FFFD
+    pub lossy: bool,
+}
+
+/// Decodes `bytes` as text, stripping any byte order mark.
+///
+/// A BOM decides the encoding when present. Otherwise BOM-less UTF-16 is recognised by its NUL
+/// bytes, valid UTF-8 is taken as is, and anything else is decoded with the encoding
+/// chardetng guesses. Malformed sequences never fail the decode; they become U+FFFD.
+pub fn decode_text(bytes: &[u8]) -> DecodedText {
+    // UTF-16 goes before UTF-8 because ASCII text in UTF-16 is also valid UTF-8.
+    let encoding = match Encoding::for_bom(bytes).map(|(encoding, _)| encoding).or_else(|| utf16_without_bom(bytes)) {
+        Some(encoding) => encoding,
+        None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+        None => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(bytes, true);
+            detector.guess(None, true)
+        }
+    };
+
+    // decode() sniffs and removes the BOM itself, so it may settle on a different encoding.
+    let (text, encoding, lossy) = encoding.decode(bytes);
+    DecodedText {
+        text: text.into_owned(),
+        charset: encoding.name(),
+        lossy,
+    }
+}
+
+/// Mostly-ASCII UTF-16 has a NUL in every other byte, on the odd side for little endian.
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = bytes.len() / 2;
+    let nul_even = bytes.iter().step_by(2).filter(|b| **b == 0).count();
+    let nul_odd = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+
+    if nul_odd * 10 >= pairs * 7 && nul_even == 0 {
+        Some(UTF_16LE)
+    } else if nul_even * 10 >= pairs * 7 && nul_odd == 0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pkg::utils::charset::charset::decode_text;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_decode_text() {
+        let utf8 = decode_text("# Café\n".as_bytes());
+        assert_eq!((utf8.text.as_str(), utf8.charset, utf8.lossy), ("# Café\n", "UTF-8", false));
+
+        let bom = decode_text(b"\xEF\xBB\xBF# Title\n");
+        assert_eq!(bom.text, "# Title\n");
+
+        let mut with_bom = vec![0xFF, 0xFE];
+        with_bom.extend(utf16le("# Título\n"));
+        let decoded = decode_text(&with_bom);
+        assert_eq!((decoded.text.as_str(), decoded.charset), ("# Título\n", "UTF-16LE"));
+
+        let decoded = decode_text(&utf16le("# Plain UTF-16 without a byte order mark\n"));
+        assert_eq!(decoded.text, "# Plain UTF-16 without a byte order mark\n");
+
+        // "Résumé des modifications" in ISO-8859-1, which is not valid UTF-8.
+        let latin1 = b"R\xE9sum\xE9 des modifications apport\xE9es \xE0 la biblioth\xE8que\n";
+        let decoded = decode_text(latin1);
+        assert_eq!(decoded.text, "Résumé des modifications apportées à la bibliothèque\n");
+        assert_eq!(decoded.charset, "windows-1252");
+    }
+}
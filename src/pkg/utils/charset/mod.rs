@@ -0,0 +1 @@
+pub mod charset;
\ No newline at end of file
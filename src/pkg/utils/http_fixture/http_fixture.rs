@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const REDACTED: &str = "REDACTED";
+const SECRET_HEADERS: &[&str] = &["authorization", "proxy-authorization", "x-api-key", "api-key", "private-token", "cookie", "set-cookie"];
+const SECRET_QUERY_PARAMS: &[&str] = &["access_token", "api_key", "key", "client_secret", "token"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureMode {
+    /// Talk to the network and record nothing
+    Off,
+    /// Talk to the network and save every exchange
+    Record,
+    /// Never talk to the network; serve saved exchanges and fail on anything unknown
+    Replay,
+}
+
+impl FromStr for FixtureMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(FixtureMode::Off),
+            "record" => Ok(FixtureMode::Record),
+            "replay" => Ok(FixtureMode::Replay),
+            _ => Err(format!("unknown fixture mode {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    /// Set when `body` holds base64 because the response was not valid UTF-8
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub base64: bool,
+}
+
+impl RecordedResponse {
+    pub fn from_bytes(status: u16, headers: BTreeMap<String, String>, bytes: &[u8]) -> Self {
+        let (body, base64) = match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), false),
+            Err(_) => (BASE64_STANDARD.encode(bytes), true),
+        };
+        Self {
+            status,
+            headers,
+            body,
+            base64,
+        }
+    }
+
+    pub fn bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        if self.base64 {
+            Ok(BASE64_STANDARD.decode(&self.body)?)
+        } else {
+            Ok(self.body.as_bytes().to_vec())
+        }
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Exchange {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+/// Saves and serves HTTP exchanges as JSON files, one per method, URL and body.
+/// Secrets in headers and query strings are replaced before anything touches the disk.
+#[derive(Debug, Clone)]
+pub struct HttpFixtures {
+    mode: FixtureMode,
+    dir: PathBuf,
+}
+
+impl HttpFixtures {
+    pub fn new(mode: FixtureMode, dir: PathBuf) -> Self {
+        Self {
+            mode,
+            dir,
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.mode == FixtureMode::Replay
+    }
+
+    pub fn replay(&self, method: &str, url: &str, body: Option<&str>) -> Result<RecordedResponse, Box<dyn Error>> {
+        let path = self.path_for(method, url, body);
+        let text = fs::read_to_string(&path).map_err(|_| {
+            format!("No recorded fixture for {} {} (expected {})", method, scrub_url(url), path.display())
+        })?;
+        let exchange: Exchange = serde_json::from_str(&text)?;
+
+        Ok(exchange.response)
+    }
+
+    pub fn record(
+        &self,
+        method: &str,
+        url: &str,
+        request_headers: &[(&str, String)],
+        body: Option<&str>,
+        response: RecordedResponse,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.mode != FixtureMode::Record {
+            return Ok(());
+        }
+
+        let exchange = Exchange {
+            request: RecordedRequest {
+                method: method.to_string(),
+                url: scrub_url(url),
+                headers: scrub_headers(request_headers.iter().map(|(k, v)| (k.to_string(), v.clone()))),
+                body: body.map(|b| b.to_string()),
+            },
+            response: RecordedResponse {
+                headers: scrub_headers(response.headers.into_iter()),
+                ..response
+            },
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path_for(method, url, body), serde_json::to_string_pretty(&exchange)?)?;
+        Ok(())
+    }
+
+    /// `GET_api.github.com_repos_owner_repo_readme_1a2b3c4d5e6f7a8b.json`; the hash covers the
+    /// scrubbed URL and the body so different queries and prompts never collide.
+    fn path_for(&self, method: &str, url: &str, body: Option<&str>) -> PathBuf {
+        let url = scrub_url(url);
+        let mut hasher = Sha256::new();
+        hasher.update(method.as_bytes());
+        hasher.update(url.as_bytes());
+        if let Some(body) = body {
+            hasher.update(body.as_bytes());
+        }
+        let hash: String = hasher.finalize()[..8].iter().map(|b| format!("{:02x}", b)).collect();
+
+        let path = url.split_once("://").map(|(_, rest)| rest).unwrap_or(&url);
+        let path = path.split('?').next().unwrap_or_default();
+        let slug: String = path.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+            .take(100)
+            .collect();
+
+        self.dir.join(format!("{}_{}_{}.json", method.to_uppercase(), slug.trim_matches('_'), hash))
+    }
+}
+
+fn scrub_headers(headers: impl Iterator<Item = (String, String)>) -> BTreeMap<String, String> {
+    headers
+        .map(|(k, v)| {
+            let k = k.to_lowercase();
+            if SECRET_HEADERS.contains(&k.as_str()) {
+                (k, REDACTED.to_string())
+            } else {
+                (k, v)
+            }
+        })
+        .collect()
+}
+
+fn scrub_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let query: Vec<String> = query.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((k, _)) if SECRET_QUERY_PARAMS.contains(&k.to_lowercase().as_str()) => format!("{}={}", k, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, query.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use crate::pkg::utils::http_fixture::http_fixture::{FixtureMode, HttpFixtures, RecordedResponse};
+
+    #[test]
+    fn test_record_scrubs_secrets_and_replays() {
+        let dir = std::env::temp_dir().join(format!("fynoss-http-fixture-{}", std::process::id()));
+        let url = "https://api.example.com/v1/things?q=rust&access_token=secret";
+        let recorder = HttpFixtures::new(FixtureMode::Record, dir.clone());
+
+        recorder.record(
+            "GET",
+            url,
+            &[("Authorization", "token secret".to_string()), ("User-Agent", "fynoss".to_string())],
+            None,
+            RecordedResponse {
+                status: 200,
+                headers: BTreeMap::from([("set-cookie".to_string(), "session=secret".to_string())]),
+                body: "{\"ok\":true}".to_string(),
+                base64: false,
+            },
+        ).unwrap();
+
+        let files: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 1);
+        let saved = fs::read_to_string(&files[0]).unwrap();
+        assert!(!saved.contains("secret"), "{}", saved);
+        assert!(saved.contains("fynoss"));
+
+        let replayer = HttpFixtures::new(FixtureMode::Replay, dir.clone());
+        assert_eq!(replayer.replay("GET", url, None).unwrap().body, "{\"ok\":true}");
+        let err = replayer.replay("GET", "https://api.example.com/v1/other", None).unwrap_err();
+        assert!(err.to_string().starts_with("No recorded fixture for GET https://api.example.com/v1/other"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_binary_bodies_round_trip_as_base64() {
+        let latin1 = b"R\xE9sum\xE9";
+        let recorded = RecordedResponse::from_bytes(200, BTreeMap::new(), latin1);
+
+        assert!(recorded.base64);
+        assert_eq!(recorded.bytes().unwrap(), latin1);
+        assert!(!RecordedResponse::from_bytes(200, BTreeMap::new(), b"plain").base64);
+    }
+}
@@ -0,0 +1 @@
+pub mod http_fixture;
\ No newline at end of file
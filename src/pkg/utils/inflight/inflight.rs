@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// A call's result, set once by whichever caller runs it.
+type Call<V> = Arc<OnceCell<Result<V, String>>>;
+
+/// Collapses concurrent calls for the same key into one.
+///
+/// The first caller for a key runs its future; callers arriving while it is still running wait
+/// for and receive a clone of its result. The key is forgotten once the call finishes, so later
+/// callers start a fresh one. Errors are shared as strings because `Box<dyn Error>` can't be
+/// cloned.
+pub struct InFlight<K, V> {
+    calls: Mutex<HashMap<K, Call<V>>>,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> InFlight<K, V> {
+    pub fn new() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn run<F, Fut>(&self, key: K, call: F) -> Result<V, String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, String>>,
+    {
+        let cell = self.calls.lock().unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .clone();
+
+        // If the caller running the call is dropped mid-flight, a waiter runs its own instead.
+        let result = cell.get_or_init(call).await.clone();
+
+        let mut calls = self.calls.lock().unwrap();
+        if calls.get(&key).is_some_and(|current| Arc::ptr_eq(current, &cell)) {
+            calls.remove(&key);
+        }
+        result
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Default for InFlight<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use crate::pkg::utils::inflight::inflight::InFlight;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_result() {
+        let inflight: InFlight<&str, u32> = InFlight::new();
+        let calls = AtomicUsize::new(0);
+        let call = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(7)
+        };
+
+        let (a, b, c) = tokio::join!(
+            inflight.run("octo/repo", call),
+            inflight.run("octo/repo", call),
+            inflight.run("octo/other", call),
+        );
+
+        assert_eq!((a, b, c), (Ok(7), Ok(7), Ok(7)));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Finished calls are not cached.
+        inflight.run("octo/repo", call).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1 @@
+pub mod inflight;
\ No newline at end of file
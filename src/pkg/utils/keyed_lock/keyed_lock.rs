@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+type Lock = Arc<tokio::sync::Mutex<()>>;
+
+/// Lets one caller at a time hold a key, making the others wait their turn.
+///
+/// Keys are forgotten once nobody holds or waits for them, so locking many distinct keys does not
+/// grow the map.
+pub struct KeyedLock<K> {
+    locks: Mutex<HashMap<K, Lock>>,
+}
+
+/// Held until dropped.
+pub struct KeyedGuard<'a, K: Eq + Hash> {
+    locks: &'a Mutex<HashMap<K, Lock>>,
+    key: K,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl<K: Eq + Hash + Clone> KeyedLock<K> {
+    pub fn new() -> Self {
+        Self {
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn lock(&self, key: K) -> KeyedGuard<'_, K> {
+        let lock = self.locks.lock().unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        KeyedGuard {
+            locks: &self.locks,
+            key,
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Default for KeyedLock<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash> Drop for KeyedGuard<'_, K> {
+    fn drop(&mut self) {
+        self.guard.take();
+        // Callers take their clone while holding the map, so one left means nobody is waiting.
+        let mut locks = self.locks.lock().unwrap();
+        if locks.get(&self.key).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use crate::pkg::utils::keyed_lock::keyed_lock::KeyedLock;
+
+    #[tokio::test]
+    async fn test_holders_of_a_key_take_turns() {
+        let locks: KeyedLock<&str> = KeyedLock::new();
+        let (running, most) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let hold = |key| {
+            let (locks, running, most) = (&locks, &running, &most);
+            async move {
+                let _guard = locks.lock(key).await;
+                most.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+            }
+        };
+
+        tokio::join!(hold("a"), hold("a"), hold("a"));
+        assert_eq!(most.load(Ordering::SeqCst), 1);
+
+        // Other keys do not wait.
+        most.store(0, Ordering::SeqCst);
+        tokio::join!(hold("a"), hold("b"));
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1 @@
+pub mod keyed_lock;
\ No newline at end of file
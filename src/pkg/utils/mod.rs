@@ -1 +1,6 @@
-pub mod base64;
\ No newline at end of file
+pub mod base64;
+pub mod validation;
+pub mod http_fixture;
+pub mod inflight;
+pub mod charset;
+pub mod keyed_lock;
\ No newline at end of file
@@ -0,0 +1 @@
+pub mod validation;
\ No newline at end of file
@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug, Default, ToSchema)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// One error for input that could not be deserialized at all, on the field serde names when
+    /// it does and on `part` (`body`, `query`) otherwise.
+    pub fn malformed(part: &str, message: &str) -> Self {
+        let field = message.strip_prefix("missing field `")
+            .and_then(|rest| rest.split_once('`'))
+            .map_or(part, |(field, _)| field);
+        let mut errors = Self::new();
+        errors.add(field, message);
+        errors
+    }
+
+    pub fn into_result<T>(self, value: T) -> Result<T, ValidationErrors> {
+        if self.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self.errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect();
+        write!(f, "Validation failed: {}", messages.join(", "))
+    }
+}
+
+impl Error for ValidationErrors {}
+
+#[cfg(test)]
+mod tests {
+    use crate::pkg::utils::validation::validation::ValidationErrors;
+
+    #[test]
+    fn test_malformed_names_the_missing_field() {
+        let errors = ValidationErrors::malformed("body", "missing field `language` at line 1 column 2");
+        assert_eq!(errors.errors[0].field, "language");
+        let errors = ValidationErrors::malformed("query", "invalid digit found in string");
+        assert_eq!((errors.errors[0].field.as_str(), errors.errors[0].message.as_str()), ("query", "invalid digit found in string"));
+    }
+}
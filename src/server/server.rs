@@ -0,0 +1,233 @@
+use std::error::Error;
+use std::fs;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, web};
+use actix_web::error::{InternalError, JsonPayloadError, QueryPayloadError};
+use actix_web::dev::Server;
+use actix_web::middleware::{from_fn, Logger};
+use reqwest::Client as ReqwestClient;
+use sqlx::MySqlPool;
+use crate::config::config::{AIAnswerCacheTier, ChatStoreTier, Config, GithubCacheTier};
+use crate::internals::admin::middleware::admin_auth::{require_admin_token, AdminToken};
+use crate::internals::admin::routers::admin_router::{AdminRouter, GithubAdminRouter};
+use crate::internals::ai::controllers::ai_controller::OpenAIController;
+use crate::internals::ai::repositories::answer_repository::{AIAnswerRepository, AnswerStore};
+use crate::internals::ai::repositories::chat_repository::{AIChatRepository, ChatStore};
+use crate::internals::ai::routers::ai_router::{AIRouter, OpenAIRouter};
+use crate::internals::ai::services::ai_service::OpenAIService;
+use crate::internals::ai::usecases::ai_usecase::{OpenAIUseCase, BUILTIN_TEMPLATES};
+use crate::internals::github::controller::repository_controller::GithubRepositoryController;
+use crate::internals::github::repositories::repository_repository::{GithubRepositoryRepository};
+use crate::internals::github::router::repository_router::{GithubRepositoryRouter, RepositoryRouter};
+use crate::internals::github::services::repository_service::GithubRepositoryService;
+use crate::internals::github::usecases::repository_usecase::GithubRepositoryUseCase;
+use crate::internals::openapi::api_doc::ApiDoc;
+use crate::internals::openapi::openapi_router::openapi_config;
+use crate::pkg::ai::client::anthropic::AnthropicClient;
+use crate::pkg::ai::client::client::{GenAIClientSettings, OpenAIClient};
+use crate::pkg::ai::client::ollama::OllamaClient;
+use crate::pkg::ai::model::model::AIProvider;
+use crate::pkg::ai::prompt::template::PromptTemplates;
+use crate::pkg::ai::provider::provider::{AIProviderClient, MultiProviderClient};
+use crate::pkg::forge::forge::ForgeRepositoryClient;
+use crate::pkg::github::client::app_auth::GithubAppAuth;
+use crate::pkg::github::client::cache::{CacheTier, CacheTtls, ResponseCache};
+use crate::pkg::github::client::client::{GithubApiClient, GithubClient};
+use crate::pkg::github::client::token_pool::TokenPool;
+use crate::pkg::github::repositories::{GithubRepositoryClient, MultiHostRepositoryClient};
+use crate::pkg::gitea::client::client::GiteaClient;
+use crate::pkg::gitea::repositories::GiteaRepositoryClient;
+use crate::pkg::gitlab::client::client::GitlabClient;
+use crate::pkg::gitlab::repositories::GitlabRepositoryClient;
+use crate::pkg::utils::http_fixture::http_fixture::{FixtureMode, HttpFixtures};
+use crate::pkg::utils::validation::validation::ValidationErrors;
+
+/// Wires clients, services and routers from `config` and starts serving on `listener`.
+pub fn build_server(config: &Config, pool: MySqlPool, listener: TcpListener) -> Result<Server, Box<dyn Error>> {
+    let reqwest_client = ReqwestClient::new();
+    let fixtures = match config.fixtures.mode {
+        FixtureMode::Off => None,
+        mode => Some(HttpFixtures::new(mode, config.fixtures.dir.clone())),
+    };
+
+    let cache_tier = match config.cache.github_tier {
+        GithubCacheTier::None => None,
+        GithubCacheTier::Disk => Some(CacheTier::Disk(config.cache.github_disk_dir.clone())),
+        GithubCacheTier::Mysql => Some(CacheTier::MySql(pool.clone())),
+    };
+    let response_cache = Arc::new(ResponseCache::new(config.cache.github_memory_entries, cache_tier)
+        .with_ttls(CacheTtls {
+            readme: Duration::from_secs(config.cache.readme_ttl_secs),
+            issues: Duration::from_secs(config.cache.issues_ttl_secs),
+        }));
+
+    let mut repository_clients = vec![];
+    let mut token_pools = vec![];
+    let hosts = [(&config.github.base_url, &config.github.tokens, &config.github.app)].into_iter()
+        .chain(config.github.hosts.iter().map(|h| (&h.base_url, &h.tokens, &h.app)));
+    for (base_url, tokens, app) in hosts {
+        let token_pool = Arc::new(TokenPool::new(tokens.clone()));
+        let mut github_client = GithubClient::new(String::new(), base_url, reqwest_client.clone())?
+            .with_token_pool(token_pool.clone())
+            .with_cache(response_cache.clone());
+        token_pools.push((github_client.host().to_string(), token_pool));
+        if let Some(app) = app {
+            let private_key = fs::read(&app.private_key_path)
+                .map_err(|e| format!("Cannot read GitHub App private key {}: {}", app.private_key_path.display(), e))?;
+            let app_auth = GithubAppAuth::new(app.app_id.clone(), app.installation_id, &private_key, base_url, reqwest_client.clone())?;
+            github_client = github_client.with_app_auth(Arc::new(app_auth));
+        }
+        if let Some(fixtures) = &fixtures {
+            github_client = github_client.with_fixtures(fixtures.clone());
+        }
+        repository_clients.push(ForgeRepositoryClient::Github(GithubRepositoryClient::new(github_client)));
+    }
+    for host in &config.gitlab {
+        let mut gitlab_client = GitlabClient::new(host.token.clone(), &host.base_url, reqwest_client.clone())?;
+        if let Some(fixtures) = &fixtures {
+            gitlab_client = gitlab_client.with_fixtures(fixtures.clone());
+        }
+        repository_clients.push(ForgeRepositoryClient::Gitlab(GitlabRepositoryClient::new(
+            gitlab_client,
+            host.good_first_issue_labels.clone(),
+            host.help_wanted_labels.clone(),
+        )));
+    }
+    for host in &config.gitea {
+        let mut gitea_client = GiteaClient::new(host.token.clone(), &host.base_url, reqwest_client.clone())?;
+        if let Some(fixtures) = &fixtures {
+            gitea_client = gitea_client.with_fixtures(fixtures.clone());
+        }
+        repository_clients.push(ForgeRepositoryClient::Gitea(GiteaRepositoryClient::new(
+            gitea_client,
+            host.good_first_issue_labels.clone(),
+            host.help_wanted_labels.clone(),
+        )));
+    }
+    let github_repository_client = MultiHostRepositoryClient::new(repository_clients);
+    let github_repository_repository = GithubRepositoryRepository::new(pool.clone());
+    let answer_store = match config.cache.ai_answer_tier {
+        AIAnswerCacheTier::None => AnswerStore::None,
+        AIAnswerCacheTier::Memory => AnswerStore::Memory(Default::default()),
+        AIAnswerCacheTier::Mysql => AnswerStore::MySql(pool.clone()),
+    };
+    let answer_repository = AIAnswerRepository::new(answer_store, Duration::from_secs(config.cache.ai_answer_ttl_secs));
+    // Stored answers go when the README or issues they were drawn from change.
+    let github_repository_service = GithubRepositoryService::new(
+        github_repository_client,
+        github_repository_repository,
+        answer_repository.clone(),
+        config.github.batch_concurrency,
+    );
+    let github_repository_usecase = GithubRepositoryUseCase::new(github_repository_service.clone());
+    let github_repository_controller = GithubRepositoryController::new(github_repository_usecase);
+    let github_repository_router = GithubRepositoryRouter::new(github_repository_controller);
+    let github_repository_router = Arc::new(github_repository_router);
+
+    let mut ai_clients = vec![];
+    for provider in config.ai.all_providers() {
+        let settings = GenAIClientSettings {
+            base_url: provider.resolved_base_url(),
+            model: provider.model.clone(),
+            max_tokens: provider.max_tokens,
+            max_prompt_tokens: provider.resolved_max_prompt_tokens() as usize,
+            timeout: Duration::from_secs(config.ai.timeout_secs),
+        };
+        let ai_client = match provider.provider {
+            AIProvider::OpenAI | AIProvider::OpenAICompatible => {
+                let mut client = OpenAIClient::new(provider.api_key.clone(), settings, reqwest_client.clone())
+                    .with_provider(provider.provider);
+                if let Some(fixtures) = &fixtures {
+                    client = client.with_fixtures(fixtures.clone());
+                }
+                AIProviderClient::OpenAI(client)
+            }
+            AIProvider::Anthropic => {
+                let mut client = AnthropicClient::new(provider.api_key.clone(), settings, reqwest_client.clone());
+                if let Some(fixtures) = &fixtures {
+                    client = client.with_fixtures(fixtures.clone());
+                }
+                AIProviderClient::Anthropic(client)
+            }
+            AIProvider::Ollama => {
+                let mut client = OllamaClient::new(settings, reqwest_client.clone());
+                if let Some(fixtures) = &fixtures {
+                    client = client.with_fixtures(fixtures.clone());
+                }
+                AIProviderClient::Ollama(client)
+            }
+        };
+        ai_clients.push(ai_client);
+    }
+    let ai_service = OpenAIService::new(MultiProviderClient::new(ai_clients));
+    let templates = PromptTemplates::load(BUILTIN_TEMPLATES, config.ai.prompts_dir.as_deref())?;
+    let chat_store = match config.chat.store {
+        ChatStoreTier::Memory => ChatStore::Memory(Default::default()),
+        ChatStoreTier::Mysql => ChatStore::MySql(pool.clone()),
+    };
+    let chat_repository = AIChatRepository::new(chat_store, Duration::from_secs(config.chat.session_ttl_secs));
+    let ai_usecase = OpenAIUseCase::new(ai_service, github_repository_service, answer_repository, chat_repository, templates);
+    let ai_controller = OpenAIController::new(ai_usecase);
+    let ai_router = OpenAIRouter::new(ai_controller);
+    let ai_router = Arc::new(ai_router);
+
+    let admin_router = Arc::new(GithubAdminRouter::new(response_cache, token_pools));
+    let admin_token = web::Data::new(AdminToken(config.admin.token.clone()));
+
+    let openapi = web::Data::new(ApiDoc::with_scopes([
+        github_repository_router.repository_scope(),
+        ai_router.ai_scope(),
+        ai_router.ai_admin_scope(),
+        admin_router.admin_scope(),
+    ]));
+
+    let server = HttpServer::new(move || {
+        let github_router_clone = github_repository_router.clone();
+        let ai_router_clone = ai_router.clone();
+        let admin_router_clone = admin_router.clone();
+        App::new()
+            .app_data(web::Data::from(github_router_clone))
+            .app_data(web::Data::from(ai_router_clone))
+            .app_data(web::Data::from(admin_router_clone))
+            .app_data(admin_token.clone())
+            .app_data(openapi.clone())
+            .app_data(web::JsonConfig::default().error_handler(json_error))
+            .app_data(web::QueryConfig::default().error_handler(query_error))
+            .service(github_repository_router.repository_scope())
+            .service(ai_router.ai_scope())
+            .service(ai_router.ai_admin_scope())
+            .service(admin_router.admin_scope())
+            .configure(openapi_config)
+            .wrap(from_fn(require_admin_token))
+            .wrap(Logger::default())
+    });
+    let server = match config.server.workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+
+    Ok(server.listen(listener)?.run())
+}
+
+/// Bodies that do not deserialize get the same 422 as those failing validation; other payload
+/// errors, such as a wrong content type, keep their status.
+fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match err {
+        JsonPayloadError::Deserialize(e) => unprocessable("body", e.to_string()),
+        err => err.into(),
+    }
+}
+
+fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match err {
+        QueryPayloadError::Deserialize(e) => unprocessable("query", e.to_string()),
+        err => err.into(),
+    }
+}
+
+fn unprocessable(part: &str, message: String) -> actix_web::Error {
+    let response = HttpResponse::UnprocessableEntity().json(ValidationErrors::malformed(part, &message));
+    InternalError::from_response(message, response).into()
+}